@@ -0,0 +1,76 @@
+use pontoon::sim::shuffle::{report, riffle_distance, ShuffleReportConfig};
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: pontoon-shuffle-report [OPTIONS]
+
+Checks that Deck::shuffle, or a dealer's routine, makes every order of the
+cards equally likely. Exits with status 1 if any test finds evidence of bias.
+
+Options:
+  --trials <N>        Shuffles counted by each test (default 100000)
+  --seed <N>          Seed for the shuffles (default 0)
+  --significance <P>  Fail a test whose p-value is below P (default 0.001)
+  --routine <STEPS>   Check a routine such as \"riffle x3, strip, riffle, cut\"
+                      (steps: riffle, overhand, strip, cut)
+  --riffles <N>       Print the exact distance from uniform after 1 to N riffles
+                      of 52 cards, then exit
+  --help              Show this message";
+
+fn main() {
+    let mut config = ShuffleReportConfig::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            return;
+        }
+        let value = args.next().unwrap_or_else(|| fail(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--trials" => config.trials = parse_number(&flag, &value),
+            "--seed" => config.seed = parse_number(&flag, &value),
+            "--significance" => config.significance = parse_number(&flag, &value),
+            "--routine" => {
+                config.routine = Some(value.parse().unwrap_or_else(|error: String| fail(&error)))
+            }
+            "--riffles" => {
+                print_riffle_table(parse_number(&flag, &value));
+                return;
+            }
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+    if config.trials == 0 {
+        fail("--trials must be at least 1");
+    }
+    if !(config.significance > 0.0 && config.significance < 1.0) {
+        fail("--significance must be between 0 and 1");
+    }
+
+    let started = Instant::now();
+    let report = report(&config);
+    print!("{}", report);
+    println!("\nChecked in {:.2}s", started.elapsed().as_secs_f64());
+    if !report.passed() {
+        process::exit(1);
+    }
+}
+
+fn print_riffle_table(riffles: u32) {
+    println!("Riffles  Distance from uniform");
+    for riffle in 1..=riffles {
+        println!("{:>7}  {:.3}", riffle, riffle_distance(52, riffle));
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("Invalid number '{}' for {}", value, flag)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
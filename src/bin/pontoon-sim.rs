@@ -0,0 +1,89 @@
+use pontoon::game::banker::{banker_by_name, banker_names, BankerStrategy, StandOn};
+use pontoon::game::rules::Rules;
+use pontoon::game::strategy::{strategy_by_name, strategy_names};
+use pontoon::models::deck_type::DeckType;
+use pontoon::sim::{run, SimConfig};
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: pontoon-sim [OPTIONS]
+
+Options:
+  --rounds <N>      Number of rounds to play (default 1000000)
+  --seed <N>        Seed for the deck shuffles (default 0)
+  --threads <N>     Worker threads (default: all cores)
+  --player <NAME>   Player strategy (default basic)
+  --banker <NAME>   Banker strategy (default: the house banker for the rules)
+  --rules <NAME>    Rule preset (default standard)
+  --deck <NAME>     Deck preset (default standard)
+  --help            Show this message";
+
+fn main() {
+    let mut config = SimConfig::default();
+    let mut player_name = "basic".to_string();
+    let mut banker_name = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            println!("\nPlayer strategies: {}", strategy_names().join(", "));
+            println!("Banker strategies: {}", banker_names().join(", "));
+            println!("Rule presets:");
+            for rules in Rules::presets() {
+                println!("  {:<12} {}", rules.name, rules.description);
+            }
+            let decks: Vec<_> = DeckType::presets().iter().map(|deck| deck.name).collect();
+            println!("Deck presets:      {}", decks.join(", "));
+            return;
+        }
+        let value = args.next().unwrap_or_else(|| fail(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--rounds" => config.rounds = parse_number(&flag, &value),
+            "--seed" => config.seed = parse_number(&flag, &value),
+            "--threads" => config.threads = parse_number(&flag, &value),
+            "--player" => player_name = value,
+            "--banker" => banker_name = Some(value),
+            "--rules" => {
+                config.rules = Rules::preset(&value)
+                    .unwrap_or_else(|| fail(&format!("Unknown rule preset '{}'", value)))
+            }
+            "--deck" => {
+                config.deck_type = DeckType::preset(&value)
+                    .unwrap_or_else(|| fail(&format!("Unknown deck preset '{}'", value)))
+            }
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+
+    let player = strategy_by_name(&player_name)
+        .unwrap_or_else(|| fail(&format!("Unknown player strategy '{}'", player_name)));
+    let banker: Box<dyn BankerStrategy> = match banker_name {
+        Some(name) => banker_by_name(&name)
+            .unwrap_or_else(|| fail(&format!("Unknown banker strategy '{}'", name))),
+        None => Box::new(StandOn::house(&config.rules)),
+    };
+
+    let started = Instant::now();
+    let report = run(&config, player.as_ref(), banker.as_ref()).unwrap_or_else(|error| {
+        eprintln!("The simulation stopped: {}", error);
+        process::exit(error.exit_code());
+    });
+    print!("{}", report);
+    println!(
+        "\nPlayed in {:.2}s on {} threads",
+        started.elapsed().as_secs_f64(),
+        config.threads
+    );
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("Invalid number '{}' for {}", value, flag)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
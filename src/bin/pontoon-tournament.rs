@@ -0,0 +1,92 @@
+use pontoon::bot::process::DEFAULT_TIMEOUT;
+use pontoon::bot::tournament::{run, Entrant, Format, TournamentConfig};
+use pontoon::game::rules::Rules;
+use pontoon::game::strategy::strategy_names;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: pontoon-tournament [OPTIONS] <BOT> <BOT> [<BOT>...]
+
+Each BOT is a built-in strategy name or the command line of an external program
+speaking the protocol in BOT_PROTOCOL.md, e.g. \"python3 my_bot.py\".
+
+Options:
+  --swiss <ROUNDS>  Play a Swiss tournament instead of a round-robin
+  --deals <N>       Duplicate deals per match (default 2000)
+  --seed <N>        Seed of the first deal (default 0)
+  --decks <N>       Decks in the shoe (default 1)
+  --rules <NAME>    Rule preset (default standard)
+  --timeout-ms <N>  Time allowed for each answer from a program (default 1000)
+  --max-faults <N>  Refused answers allowed per match (default 10)
+  --help            Show this message";
+
+fn main() {
+    let mut config = TournamentConfig::default();
+    let mut timeout = DEFAULT_TIMEOUT;
+    let mut bots = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            println!("{}", USAGE);
+            println!("\nBuilt-in strategies: {}", strategy_names().join(", "));
+            return;
+        }
+        if !arg.starts_with("--") {
+            bots.push(arg);
+            continue;
+        }
+        let value = args.next().unwrap_or_else(|| fail(&format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--swiss" => {
+                config.format = Format::Swiss {
+                    rounds: parse_number(&arg, &value),
+                }
+            }
+            "--deals" => config.game.rounds = parse_number(&arg, &value),
+            "--seed" => config.game.seed = parse_number(&arg, &value),
+            "--decks" => config.game.decks = parse_number(&arg, &value),
+            "--rules" => {
+                config.game.rules = Rules::preset(&value)
+                    .unwrap_or_else(|| fail(&format!("Unknown rule preset '{}'", value)))
+            }
+            "--timeout-ms" => timeout = Duration::from_millis(parse_number(&arg, &value)),
+            "--max-faults" => config.game.max_faults = parse_number(&arg, &value),
+            _ => fail(&format!("Unknown option '{}'", arg)),
+        }
+    }
+    if bots.len() < 2 {
+        fail("A tournament needs at least two bots");
+    }
+    if config.game.decks == 0 {
+        fail("--decks must be at least 1");
+    }
+
+    let entrants: Vec<Entrant> = bots
+        .iter()
+        .map(|bot| {
+            Entrant::strategy(bot).unwrap_or_else(|| {
+                let mut words = bot.split_whitespace().map(str::to_string);
+                let program = words.next().unwrap_or_else(|| fail("A bot name cannot be empty"));
+                Entrant::program(&program, words.collect(), timeout)
+            })
+        })
+        .collect();
+
+    let report = run(&entrants, &config).unwrap_or_else(|error| {
+        eprintln!("The tournament stopped: {}", error);
+        process::exit(error.exit_code());
+    });
+    print!("{}", report);
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("Invalid number '{}' for {}", value, flag)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,93 @@
+use pontoon::models::card::Card;
+use pontoon::models::deck::Deck;
+use pontoon::models::shuffle::ShuffleRoutine;
+use pontoon::sim::tracking::{find_clumps, TrackingReport};
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: pontoon-track [OPTIONS]
+
+Forecasts where clumps of high cards (tens and Aces) in a gathered discard pile
+land after a dealer's shuffle, and checks the forecast against simulation.
+
+Options:
+  --discards <FILE>   Gathered discards, top card first, as notation such as
+                      \"Ah Td 7c\" (default: a seeded random deck)
+  --routine <STEPS>   The dealer's shuffle (default \"riffle, strip, riffle, cut\")
+  --segments <N>      Parts to split the next shoe into (default 6)
+  --window <N>        Cards in a clump (default 10)
+  --min-high <N>      High cards needed in a window to make a clump (default 6)
+  --trials <N>        Shuffles to simulate (default 20000)
+  --seed <N>          Seed for the random deck and the simulation (default 0)
+  --help              Show this message";
+
+fn main() {
+    let mut discards_path = None;
+    let mut routine: ShuffleRoutine = "riffle, strip, riffle, cut".parse().unwrap();
+    let mut segments = 6;
+    let mut window = 10;
+    let mut min_high = 6;
+    let mut trials = 20_000;
+    let mut seed = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            return;
+        }
+        let value = args.next().unwrap_or_else(|| fail(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--discards" => discards_path = Some(value),
+            "--routine" => routine = value.parse().unwrap_or_else(|error: String| fail(&error)),
+            "--segments" => segments = parse_number(&flag, &value),
+            "--window" => window = parse_number(&flag, &value),
+            "--min-high" => min_high = parse_number(&flag, &value),
+            "--trials" => trials = parse_number(&flag, &value),
+            "--seed" => seed = parse_number(&flag, &value),
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+
+    let discards = match discards_path {
+        Some(path) => read_discards(&path),
+        None => Deck::new_seeded(seed).cards_in_deal_order(),
+    };
+    if segments == 0 || segments > discards.len() {
+        fail(&format!("--segments must be between 1 and {}", discards.len()));
+    }
+    if trials == 0 {
+        fail("--trials must be at least 1");
+    }
+
+    let started = Instant::now();
+    let clumps = find_clumps(&discards, window, min_high);
+    let report = TrackingReport::run(&discards, &routine, segments, &clumps, trials, seed);
+    print!("{}", report);
+    println!("\nChecked in {:.2}s", started.elapsed().as_secs_f64());
+}
+
+fn read_discards(path: &str) -> Vec<Card> {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|error| fail(&format!("Could not read {}: {}", path, error)));
+    let cards: Vec<Card> = text
+        .split_whitespace()
+        .map(|word| word.parse().unwrap_or_else(|error| fail(&format!("{}: {}", path, error))))
+        .collect();
+    if cards.len() < 2 {
+        fail(&format!("{} needs at least two cards", path));
+    }
+    cards
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("Invalid number '{}' for {}", value, flag)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
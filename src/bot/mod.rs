@@ -0,0 +1,393 @@
+//! Bots: players driven by code, either in-process strategies or external programs
+//!
+//! External programs speak the line protocol in `BOT_PROTOCOL.md` over their
+//! stdin and stdout. Every bot plays against the real `Deck` and rules engine,
+//! but is only ever shown the player's `TableView`.
+
+pub mod process;
+pub mod protocol;
+pub mod tournament;
+
+use crate::error;
+use crate::game::rules::Rules;
+use crate::game::strategy::{Action, PlayerStrategy, Situation};
+use crate::game::table::Table;
+use crate::game::view::{TableView, Viewer, PLAYER_SEAT};
+use crate::models::deck::{mix_seed, Deck};
+use crate::sim::Tally;
+use std::fmt;
+use std::time::Duration;
+
+/// Why a bot's answer was not accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotFault {
+    /// No answer arrived in time
+    Timeout(Duration),
+    /// The answer was an action that is not legal now
+    Illegal(Action),
+    /// The answer could not be read
+    Unreadable(String),
+    /// The bot stopped responding altogether; it cannot play on
+    Disconnected(String),
+}
+
+impl BotFault {
+    /// Returns true if the bot cannot continue the match
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, BotFault::Disconnected(_))
+    }
+}
+
+impl fmt::Display for BotFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotFault::Timeout(limit) => write!(f, "no answer within {} ms", limit.as_millis()),
+            BotFault::Illegal(action) => write!(f, "{} is not a legal action", action),
+            BotFault::Unreadable(reason) => write!(f, "unreadable answer: {}", reason),
+            BotFault::Disconnected(reason) => write!(f, "disconnected: {}", reason),
+        }
+    }
+}
+
+/// A player driven by code
+///
+/// Only `name` and `decide` are required; the other calls let a bot follow the match.
+pub trait Bot: Send {
+    /// Name shown in reports
+    fn name(&self) -> String;
+
+    /// Called once before the first round
+    fn start(&mut self, _rules: &Rules) -> Result<(), BotFault> {
+        Ok(())
+    }
+
+    /// Called when a round has been dealt
+    fn round_started(&mut self, _number: u32, _stake: u32) -> Result<(), BotFault> {
+        Ok(())
+    }
+
+    /// Picks an action; anything not in `situation.legal_actions` is a fault
+    fn decide(&mut self, situation: &Situation) -> Result<Action, BotFault>;
+
+    /// Called when an answer was refused and a fallback move played instead
+    fn fault(&mut self, _fault: &BotFault) {}
+
+    /// Called when a round has been settled, with the table as the player now sees it
+    fn round_settled(&mut self, _view: &TableView) {}
+
+    /// Called once after the last round
+    fn finish(&mut self) {}
+}
+
+/// Wraps a built-in player strategy as a bot
+pub struct StrategyBot {
+    strategy: Box<dyn PlayerStrategy>,
+}
+
+impl StrategyBot {
+    pub fn new(strategy: Box<dyn PlayerStrategy>) -> Self {
+        StrategyBot { strategy }
+    }
+}
+
+impl Bot for StrategyBot {
+    fn name(&self) -> String {
+        self.strategy.name()
+    }
+
+    fn decide(&mut self, situation: &Situation) -> Result<Action, BotFault> {
+        Ok(self.strategy.decide(situation))
+    }
+}
+
+/// Settings for a bot match
+#[derive(Debug, Clone)]
+pub struct MatchConfig {
+    pub rounds: u32,
+    /// Seed for the shoe; the same seed deals the same cards to every bot
+    pub seed: u64,
+    pub decks: usize,
+    pub stake: u32,
+    pub rules: Rules,
+    /// Faults allowed before the bot is disqualified
+    pub max_faults: u32,
+    /// Deal every round from a fresh shoe seeded from `seed` and the round number
+    ///
+    /// Each round's cards then do not depend on earlier decisions, so different bots
+    /// face exactly the same deals (duplicate play).
+    pub duplicate: bool,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            rounds: 1_000,
+            seed: 0,
+            decks: 1,
+            stake: 10,
+            rules: Rules::standard(),
+            max_faults: 10,
+            duplicate: false,
+        }
+    }
+}
+
+/// A refused answer and the round it happened in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultRecord {
+    pub round: u32,
+    pub fault: BotFault,
+}
+
+/// How a bot fared over a match
+#[derive(Debug, Clone)]
+pub struct MatchReport {
+    pub bot: String,
+    /// Results of every round played to the end
+    pub tally: Tally,
+    /// Net result of each round played to the end, in order
+    pub nets: Vec<i64>,
+    pub faults: Vec<FaultRecord>,
+    /// Set when the bot was stopped before the last round
+    pub disqualified: Option<BotFault>,
+}
+
+impl fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bot:     {}", self.bot)?;
+        writeln!(f, "Rounds:  {}", self.tally.rounds)?;
+        writeln!(
+            f,
+            "Results: {} won, {} lost, {} pushed",
+            self.tally.wins, self.tally.losses, self.tally.pushes
+        )?;
+        writeln!(f, "Net:     {:+}", self.tally.sum_net)?;
+        writeln!(f, "Faults:  {}", self.faults.len())?;
+        for record in &self.faults {
+            writeln!(f, "  round {}: {}", record.round, record.fault)?;
+        }
+        if let Some(fault) = &self.disqualified {
+            writeln!(f, "Disqualified: {}", fault)?;
+        }
+        Ok(())
+    }
+}
+
+/// The move played for a bot whose answer was refused: Stick if allowed, otherwise Twist
+pub fn fallback_action(legal: &[Action]) -> Action {
+    if legal.contains(&Action::Stick) {
+        Action::Stick
+    } else {
+        Action::Twist
+    }
+}
+
+/// Plays a bot through `config.rounds` rounds against the house banker
+///
+/// A refused answer is recorded as a fault and the fallback move is played instead.
+/// The bot is disqualified once it exceeds `config.max_faults` or disconnects; the
+/// round in progress then does not count. Fails only if the engine does, e.g. when
+/// the shoe runs out.
+pub fn play_match(bot: &mut dyn Bot, config: &MatchConfig) -> error::Result<MatchReport> {
+    let mut report = MatchReport {
+        bot: bot.name(),
+        tally: Tally::default(),
+        nets: Vec::new(),
+        faults: Vec::new(),
+        disqualified: None,
+    };
+    if let Err(fault) = bot.start(&config.rules) {
+        report.disqualified = Some(fault);
+        return Ok(report);
+    }
+    report.bot = bot.name();
+
+    let deck = Deck::new_shoe_seeded(config.decks, config.seed);
+    let mut table = Table::new(deck, config.rules.clone());
+    for number in 1..=config.rounds {
+        if config.duplicate {
+            let deck = Deck::new_shoe_seeded(config.decks, deal_seed(config.seed, number));
+            table = Table::new(deck, config.rules.clone());
+        }
+        table.start_round(config.stake)?;
+        if let Err(fault) = bot.round_started(number, config.stake) {
+            if !record_fault(bot, &mut report, config, number, fault) {
+                return Ok(report);
+            }
+        }
+
+        let viewer = Viewer::Seat(PLAYER_SEAT);
+        while let Some(view) = table.view(viewer).filter(|view| view.turn.is_some()) {
+            let legal = view.legal_actions.clone();
+            let answer = view
+                .with_situation(|situation| bot.decide(situation))
+                .expect("It is the player's turn");
+            let fault = match answer {
+                Ok(action) if legal.contains(&action) => {
+                    table.act(action)?;
+                    continue;
+                }
+                Ok(action) => BotFault::Illegal(action),
+                Err(fault) => fault,
+            };
+            if !record_fault(bot, &mut report, config, number, fault) {
+                return Ok(report);
+            }
+            table.act(fallback_action(&legal))?;
+        }
+
+        table.play_banker()?;
+        let view = table.view(viewer).expect("A round was just played");
+        let settlement = view
+            .own_seat()
+            .and_then(|seat| seat.settlement)
+            .expect("The round was settled");
+        report.tally.record(&settlement);
+        report.nets.push(settlement.net);
+        bot.round_settled(&view);
+    }
+    bot.finish();
+    Ok(report)
+}
+
+/// Seed of the shoe for round `number` of a duplicate match
+pub fn deal_seed(seed: u64, number: u32) -> u64 {
+    mix_seed(seed, u64::from(number))
+}
+
+/// Records a fault and tells the bot; returns false if the bot is now disqualified
+fn record_fault(
+    bot: &mut dyn Bot,
+    report: &mut MatchReport,
+    config: &MatchConfig,
+    round: u32,
+    fault: BotFault,
+) -> bool {
+    bot.fault(&fault);
+    report.faults.push(FaultRecord {
+        round,
+        fault: fault.clone(),
+    });
+    if fault.is_fatal() || report.faults.len() as u64 > u64::from(config.max_faults) {
+        report.disqualified = Some(fault);
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::strategy::{BasicStrategy, StickOn};
+    use crate::sim::Tally;
+    use proptest::prelude::*;
+
+    /// Always answers with the same action, legal or not
+    struct Stubborn(Action);
+
+    impl Bot for Stubborn {
+        fn name(&self) -> String {
+            "stubborn".to_string()
+        }
+
+        fn decide(&mut self, _situation: &Situation) -> Result<Action, BotFault> {
+            Ok(self.0)
+        }
+    }
+
+    fn config(rounds: u32, seed: u64) -> MatchConfig {
+        MatchConfig {
+            rounds,
+            seed,
+            ..MatchConfig::default()
+        }
+    }
+
+    // Property: A strategy played as a bot gets the same results as the engine's own round loop
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+        #[test]
+        fn prop_strategy_bot_matches_engine(seed in any::<u64>(), rounds in 1u32..40) {
+            let mut bot = StrategyBot::new(Box::new(BasicStrategy));
+            let report = play_match(&mut bot, &config(rounds, seed)).unwrap();
+            prop_assert!(report.faults.is_empty());
+
+            let mut deck = Deck::new_seeded(seed);
+            let mut tally = Tally::default();
+            for _ in 0..rounds {
+                if deck.needs_reshuffle() {
+                    deck.reshuffle();
+                }
+                let (_, settlement) = crate::game::round::play_round(
+                    &mut deck,
+                    &Rules::standard(),
+                    10,
+                    &BasicStrategy,
+                    &crate::game::banker::StandOn::default(),
+                )
+                .unwrap();
+                tally.record(&settlement);
+            }
+            prop_assert_eq!(report.tally, tally);
+        }
+    }
+
+    #[test]
+    fn test_illegal_moves_fall_back_then_disqualify() {
+        // Sticking below 15 is illegal, so this bot faults on most hands
+        let mut bot = Stubborn(Action::Stick);
+        let strict = MatchConfig { max_faults: 3, ..config(200, 9) };
+        let report = play_match(&mut bot, &strict).unwrap();
+        assert_eq!(report.faults.len(), 4);
+        assert!(matches!(report.disqualified, Some(BotFault::Illegal(Action::Stick))));
+        assert!(report.tally.rounds < 200);
+    }
+
+    // Property: In duplicate play every bot is dealt the same first cards each round
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+        #[test]
+        fn prop_duplicate_deals_ignore_decisions(seed in any::<u64>(), number in 1u32..500) {
+            let first_cards = |strategy: Box<dyn PlayerStrategy>| {
+                let config = MatchConfig { duplicate: true, ..config(number, seed) };
+                let mut bot = Recorder { inner: StrategyBot::new(strategy), first: Vec::new() };
+                play_match(&mut bot, &config).unwrap();
+                bot.first
+            };
+            let cautious = first_cards(Box::new(StickOn { total: 15 }));
+            let greedy = first_cards(Box::new(StickOn { total: 21 }));
+            prop_assert_eq!(cautious.len(), number as usize);
+            prop_assert_eq!(cautious, greedy);
+        }
+    }
+
+    /// Remembers the player's first two cards of each round
+    struct Recorder {
+        inner: StrategyBot,
+        first: Vec<Vec<crate::models::card::Card>>,
+    }
+
+    impl Bot for Recorder {
+        fn name(&self) -> String {
+            self.inner.name()
+        }
+
+        fn decide(&mut self, situation: &Situation) -> Result<Action, BotFault> {
+            self.inner.decide(situation)
+        }
+
+        fn round_settled(&mut self, view: &TableView) {
+            let hand = view.own_seat().unwrap().hand.hand().unwrap();
+            self.first.push(hand.cards()[..2].to_vec());
+        }
+    }
+
+    #[test]
+    fn test_legal_bot_is_never_faulted() {
+        let mut bot = StrategyBot::new(Box::new(StickOn { total: 17 }));
+        let report = play_match(&mut bot, &config(100, 1)).unwrap();
+        assert_eq!(report.tally.rounds, 100);
+        assert!(report.disqualified.is_none());
+        assert_eq!(report.bot, "stick-on-17");
+    }
+}
@@ -0,0 +1,222 @@
+use super::protocol::{BotMessage, EngineMessage, PROTOCOL_VERSION};
+use super::{Bot, BotFault};
+use crate::game::rules::Rules;
+use crate::game::strategy::{Action, Situation};
+use crate::game::view::TableView;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time a bot gets to answer when none is given
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1_000);
+
+/// A bot running as a separate program, spoken to over its stdin and stdout
+///
+/// The program's stderr is passed through, so bots can log there freely.
+pub struct ExternalBot {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines read from the program's stdout by a background thread
+    lines: Receiver<String>,
+    timeout: Duration,
+    stake: u32,
+}
+
+impl ExternalBot {
+    /// Starts `program` with `args`; each answer must arrive within `timeout`
+    pub fn spawn(program: &str, args: &[String], timeout: Duration) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(ExternalBot {
+            name: program.to_string(),
+            child,
+            stdin,
+            lines,
+            timeout,
+            stake: 0,
+        })
+    }
+
+    fn send(&mut self, message: &EngineMessage) -> Result<(), BotFault> {
+        writeln!(self.stdin, "{}", message)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|error| BotFault::Disconnected(error.to_string()))
+    }
+
+    /// Waits for the next answer, skipping `info` lines
+    fn receive(&mut self) -> Result<BotMessage, BotFault> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(remaining) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err(BotFault::Timeout(self.timeout)),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(BotFault::Disconnected("the program closed its output".to_string()))
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse().map_err(BotFault::Unreadable)? {
+                BotMessage::Info(_) => continue,
+                message => return Ok(message),
+            }
+        }
+    }
+
+    /// Throws away answers that arrived too late for an earlier question
+    fn discard_late_answers(&mut self) {
+        while self.lines.try_recv().is_ok() {}
+    }
+}
+
+impl Bot for ExternalBot {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn start(&mut self, rules: &Rules) -> Result<(), BotFault> {
+        self.send(&EngineMessage::Hello {
+            version: PROTOCOL_VERSION,
+        })?;
+        match self.receive()? {
+            BotMessage::Ready { name } => {
+                if let Some(name) = name {
+                    self.name = name;
+                }
+            }
+            other => return Err(BotFault::Unreadable(format!("expected ready, got {:?}", other))),
+        }
+        self.send(&EngineMessage::rules(rules))
+    }
+
+    fn round_started(&mut self, number: u32, stake: u32) -> Result<(), BotFault> {
+        self.stake = stake;
+        self.discard_late_answers();
+        self.send(&EngineMessage::Round { number, stake })
+    }
+
+    fn decide(&mut self, situation: &Situation) -> Result<Action, BotFault> {
+        self.discard_late_answers();
+        self.send(&EngineMessage::state(situation, self.stake))?;
+        match self.receive()? {
+            BotMessage::Action(action) => Ok(action),
+            other => Err(BotFault::Unreadable(format!("expected an action, got {:?}", other))),
+        }
+    }
+
+    fn fault(&mut self, fault: &BotFault) {
+        let _ = self.send(&EngineMessage::Error {
+            message: fault.to_string(),
+        });
+    }
+
+    fn round_settled(&mut self, view: &TableView) {
+        if let Some(result) = EngineMessage::result(view) {
+            let _ = self.send(&result);
+        }
+    }
+
+    fn finish(&mut self) {
+        let _ = self.send(&EngineMessage::Quit);
+    }
+}
+
+impl Drop for ExternalBot {
+    /// Gives the program a moment to exit after `quit`, then stops it
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "{}", EngineMessage::Quit);
+        let deadline = Instant::now() + Duration::from_millis(200);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::bot::{play_match, MatchConfig};
+
+    fn shell_bot(script: &str, timeout: Duration) -> ExternalBot {
+        ExternalBot::spawn("sh", &["-c".to_string(), script.to_string()], timeout).unwrap()
+    }
+
+    /// Sticks on 15 or more, twists otherwise
+    const STICK_ON_15: &str = r#"
+        read hello
+        echo "info warming up"
+        echo "ready shell-bot"
+        while read line; do
+            case "$line" in
+                state*total=1[5-9]\ *|state*total=2[01]\ *) echo stick ;;
+                state*) echo twist ;;
+                quit) exit 0 ;;
+            esac
+        done
+    "#;
+
+    #[test]
+    fn test_external_bot_plays_a_match() {
+        let mut bot = shell_bot(STICK_ON_15, Duration::from_secs(5));
+        let config = MatchConfig {
+            rounds: 20,
+            seed: 4,
+            ..MatchConfig::default()
+        };
+        let report = play_match(&mut bot, &config).unwrap();
+        assert_eq!(report.bot, "shell-bot");
+        assert_eq!(report.faults, Vec::new());
+        assert_eq!(report.tally.rounds, 20);
+    }
+
+    #[test]
+    fn test_silent_bot_times_out() {
+        let mut bot = shell_bot("read hello; echo ready; while read line; do :; done", Duration::from_millis(50));
+        let config = MatchConfig {
+            rounds: 5,
+            max_faults: 0,
+            ..MatchConfig::default()
+        };
+        let report = play_match(&mut bot, &config).unwrap();
+        assert_eq!(report.disqualified, Some(BotFault::Timeout(Duration::from_millis(50))));
+    }
+
+    #[test]
+    fn test_exited_bot_is_disconnected() {
+        let mut bot = shell_bot("read hello; echo ready", Duration::from_secs(5));
+        let report = play_match(&mut bot, &MatchConfig::default()).unwrap();
+        assert!(matches!(report.disqualified, Some(BotFault::Disconnected(_))));
+    }
+
+    #[test]
+    fn test_missing_program_fails_to_spawn() {
+        assert!(ExternalBot::spawn("pontoon-no-such-bot", &[], DEFAULT_TIMEOUT).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! The text lines exchanged with an external bot, described in `BOT_PROTOCOL.md`
+
+use crate::game::rules::{HandValue, Rules};
+use crate::game::strategy::{Action, Situation};
+use crate::game::view::{SeenCard, TableView};
+use crate::history::{action_name, outcome_name};
+use crate::models::card::Card;
+use std::fmt;
+use std::str::FromStr;
+
+/// Version sent in the opening `pontoon` line
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A line sent from the engine to a bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineMessage {
+    /// Opens the session; the bot must answer `ready`
+    Hello { version: u32 },
+    /// The rules for every round that follows
+    Rules {
+        name: String,
+        min_stick: u8,
+        five_card_trick: bool,
+        ties_to_banker: bool,
+    },
+    /// A new round has been dealt
+    Round { number: u32, stake: u32 },
+    /// The bot must answer with `twist` or `stick`
+    State {
+        hand: Vec<Card>,
+        total: u8,
+        soft: bool,
+        upcard: Card,
+        legal: Vec<Action>,
+        stake: u32,
+    },
+    /// The last answer was not accepted; the engine played a fallback move instead
+    Error { message: String },
+    /// The round is over
+    Result {
+        outcome: String,
+        net: i64,
+        /// Every hand the bot held, more than one only after a split
+        player: Vec<Vec<Card>>,
+        banker: Vec<Card>,
+    },
+    /// The session is over; the bot should exit
+    Quit,
+}
+
+impl EngineMessage {
+    /// Describes the rules in play
+    pub fn rules(rules: &Rules) -> Self {
+        EngineMessage::Rules {
+            name: rules.name.to_string(),
+            min_stick: rules.min_stick,
+            five_card_trick: rules.five_card_trick(),
+            ties_to_banker: rules.ties_to_banker,
+        }
+    }
+
+    /// Asks for a decision in `situation`
+    pub fn state(situation: &Situation, stake: u32) -> Self {
+        let value = HandValue::of(situation.hand);
+        EngineMessage::State {
+            hand: situation.hand.cards().to_vec(),
+            total: value.total,
+            soft: value.soft,
+            upcard: situation.banker_upcard,
+            legal: situation.legal_actions.to_vec(),
+            stake,
+        }
+    }
+
+    /// Reports the viewer's settled round; None until the round is settled
+    pub fn result(view: &TableView) -> Option<Self> {
+        let seat = view.own_seat()?;
+        let settlement = seat.settlement?;
+        Some(EngineMessage::Result {
+            outcome: outcome_name(settlement.outcome).to_string(),
+            net: settlement.net,
+            player: seat
+                .hands
+                .iter()
+                .map(|hand| hand.cards().iter().filter_map(SeenCard::card).collect())
+                .collect(),
+            banker: view.banker.cards().iter().filter_map(SeenCard::card).collect(),
+        })
+    }
+}
+
+fn cards(cards: &[Card]) -> String {
+    let cards: Vec<String> = cards.iter().map(Card::notation).collect();
+    cards.join(",")
+}
+
+fn on_off(flag: bool) -> &'static str {
+    if flag {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl fmt::Display for EngineMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineMessage::Hello { version } => write!(f, "pontoon {}", version),
+            EngineMessage::Rules {
+                name,
+                min_stick,
+                five_card_trick,
+                ties_to_banker,
+            } => write!(
+                f,
+                "rules {} min_stick={} five_card_trick={} ties={}",
+                name,
+                min_stick,
+                on_off(*five_card_trick),
+                if *ties_to_banker { "banker" } else { "push" }
+            ),
+            EngineMessage::Round { number, stake } => write!(f, "round {} stake={}", number, stake),
+            EngineMessage::State {
+                hand,
+                total,
+                soft,
+                upcard,
+                legal,
+                stake,
+            } => {
+                let legal: Vec<&str> = legal.iter().map(|action| action_name(*action)).collect();
+                write!(
+                    f,
+                    "state hand={} total={} soft={} upcard={} legal={} stake={}",
+                    cards(hand),
+                    total,
+                    on_off(*soft),
+                    upcard.notation(),
+                    legal.join(","),
+                    stake
+                )
+            }
+            EngineMessage::Error { message } => write!(f, "error {}", message),
+            EngineMessage::Result {
+                outcome,
+                net,
+                player,
+                banker,
+            } => {
+                let player: Vec<String> = player.iter().map(|hand| cards(hand)).collect();
+                write!(
+                    f,
+                    "result {} net={} player={} banker={}",
+                    outcome,
+                    net,
+                    player.join("/"),
+                    cards(banker)
+                )
+            }
+            EngineMessage::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+/// A line sent from a bot to the engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotMessage {
+    /// Answers `pontoon`, optionally giving the bot's name
+    Ready { name: Option<String> },
+    /// Answers `state`
+    Action(Action),
+    /// Free text for the log; never an answer
+    Info(String),
+}
+
+impl FromStr for BotMessage {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match keyword {
+            "ready" => Ok(BotMessage::Ready {
+                name: Some(rest.trim()).filter(|name| !name.is_empty()).map(str::to_string),
+            }),
+            "twist" | "t" if rest.is_empty() => Ok(BotMessage::Action(Action::Twist)),
+            "stick" | "s" if rest.is_empty() => Ok(BotMessage::Action(Action::Stick)),
+            "double" if rest.is_empty() => Ok(BotMessage::Action(Action::Double)),
+            "split" if rest.is_empty() => Ok(BotMessage::Action(Action::Split)),
+            "surrender" if rest.is_empty() => Ok(BotMessage::Action(Action::Surrender)),
+            "info" => Ok(BotMessage::Info(rest.trim().to_string())),
+            _ => Err(format!("cannot read '{}'", line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::{Rank, Suit};
+    use crate::models::hand::Hand;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_state_uses_compact_notation() {
+        let mut hand = Hand::new();
+        hand.add_card(Card::new(Rank::Ace, Suit::Hearts));
+        hand.add_card(Card::new(Rank::Seven, Suit::Clubs));
+        let rules = Rules::standard();
+        let situation = Situation {
+            hand: &hand,
+            banker_upcard: Card::new(Rank::King, Suit::Diamonds),
+            rules: &rules,
+            legal_actions: &[Action::Twist, Action::Stick],
+        };
+        assert_eq!(
+            EngineMessage::state(&situation, 10).to_string(),
+            "state hand=Ah,7c total=18 soft=on upcard=Kd legal=twist,stick stake=10"
+        );
+        assert_eq!(
+            EngineMessage::rules(&rules).to_string(),
+            "rules standard min_stick=15 five_card_trick=on ties=banker"
+        );
+        let notation = |cards: &[&str]| cards.iter().map(|card| card.parse().unwrap()).collect();
+        let split = EngineMessage::Result {
+            outcome: "win".to_string(),
+            net: 20,
+            player: vec![notation(&["8h", "3c", "Td"]), notation(&["8d", "Kc"])],
+            banker: notation(&["9c", "7s", "2h"]),
+        };
+        assert_eq!(split.to_string(), "result win net=20 player=8h,3c,Td/8d,Kc banker=9c,7s,2h");
+    }
+
+    #[test]
+    fn test_bot_replies() {
+        assert_eq!("ready".parse(), Ok(BotMessage::Ready { name: None }));
+        assert_eq!(
+            "ready Stick Bot 2 ".parse(),
+            Ok(BotMessage::Ready {
+                name: Some("Stick Bot 2".to_string())
+            })
+        );
+        assert_eq!(" twist\r".parse(), Ok(BotMessage::Action(Action::Twist)));
+        assert_eq!("s".parse(), Ok(BotMessage::Action(Action::Stick)));
+        assert!("stick please".parse::<BotMessage>().is_err());
+        assert!("".parse::<BotMessage>().is_err());
+    }
+
+    // Property: Any line that is not a known reply is refused rather than guessed at
+    proptest! {
+        #[test]
+        fn prop_unknown_words_are_refused(word in "[a-z]{2,8}") {
+            let known = ["ready", "twist", "stick", "double", "split", "surrender", "info"];
+            prop_assume!(!known.contains(&word.as_str()));
+            prop_assert!(word.parse::<BotMessage>().is_err());
+        }
+    }
+}
@@ -0,0 +1,528 @@
+//! Bot-vs-bot tournaments with duplicate deals and Elo ratings
+//!
+//! In Pontoon every bot plays the banker, not each other, so a match compares two
+//! bots on the same deals: both play every deal of the match from the same seat
+//! with the same `Deck::new_shoe_seeded` shuffle, and the bot that wins more chips
+//! wins the match. Luck in the cards cancels out, leaving the difference in play.
+
+use super::process::ExternalBot;
+use super::{play_match, Bot, MatchConfig, MatchReport, StrategyBot};
+use crate::error;
+use crate::game::strategy::strategy_by_name;
+use crate::models::deck::mix_seed;
+use crate::sim::stats::{mean_interval, ConfidenceInterval};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+/// Rating every entrant starts with
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far one match can move an Elo rating
+pub const ELO_K: f64 = 32.0;
+
+/// Creates a fresh bot for each match
+pub type BotFactory = Box<dyn Fn() -> Result<Box<dyn Bot>, String> + Send + Sync>;
+
+/// A bot entered in a tournament
+pub struct Entrant {
+    pub name: String,
+    factory: BotFactory,
+}
+
+impl Entrant {
+    /// Enters a bot made by `factory`
+    pub fn new(name: impl Into<String>, factory: BotFactory) -> Self {
+        Entrant {
+            name: name.into(),
+            factory,
+        }
+    }
+
+    /// Enters a built-in player strategy by name
+    pub fn strategy(name: &str) -> Option<Self> {
+        strategy_by_name(name)?;
+        let owned = name.to_string();
+        Some(Self::new(
+            name,
+            Box::new(move || {
+                let strategy = strategy_by_name(&owned).expect("The name was checked on entry");
+                Ok(Box::new(StrategyBot::new(strategy)) as Box<dyn Bot>)
+            }),
+        ))
+    }
+
+    /// Enters an external program, started afresh for each match
+    pub fn program(program: &str, args: Vec<String>, timeout: Duration) -> Self {
+        let name = std::iter::once(program.to_string())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let program = program.to_string();
+        Self::new(
+            name,
+            Box::new(move || {
+                ExternalBot::spawn(&program, &args, timeout)
+                    .map(|bot| Box::new(bot) as Box<dyn Bot>)
+                    .map_err(|error| format!("could not start '{}': {}", program, error))
+            }),
+        )
+    }
+}
+
+/// How entrants are paired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Every entrant meets every other once
+    RoundRobin,
+    /// Entrants with similar scores meet, for a fixed number of rounds
+    Swiss { rounds: u32 },
+}
+
+/// Settings for a tournament
+#[derive(Debug, Clone)]
+pub struct TournamentConfig {
+    pub format: Format,
+    /// Deals, stake, rules and fault limit of every match; `game.seed` is the first deal
+    pub game: MatchConfig,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        TournamentConfig {
+            format: Format::RoundRobin,
+            game: MatchConfig {
+                rounds: 2_000,
+                duplicate: true,
+                ..MatchConfig::default()
+            },
+        }
+    }
+}
+
+/// The result of one match between two entrants
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchResult {
+    /// Tournament round, starting at 1
+    pub round: u32,
+    pub first: usize,
+    pub second: usize,
+    /// 1 for a win by `first`, 0.5 for a draw, 0 for a loss
+    pub score: f64,
+    /// Chips per deal that `first` won over `second`
+    pub difference: ConfidenceInterval,
+}
+
+impl MatchResult {
+    /// Returns true if the difference is unlikely to be luck (95% interval excludes zero)
+    pub fn is_significant(&self) -> bool {
+        !self.difference.contains(0.0)
+    }
+}
+
+/// One line of the leaderboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    /// Match points: 1 per win or bye, half per draw
+    pub points: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub byes: u32,
+    /// Chips won per deal over all the deals the entrant played
+    pub per_deal: ConfidenceInterval,
+    /// The first fault that got the entrant disqualified from a match, if any
+    pub disqualified: Option<String>,
+}
+
+/// The outcome of a tournament
+#[derive(Debug, Clone)]
+pub struct TournamentReport {
+    /// Ranked by points, then rating
+    pub standings: Vec<Standing>,
+    pub matches: Vec<MatchResult>,
+    /// Entrant names in entry order, as indexed by `MatchResult`
+    pub names: Vec<String>,
+}
+
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Leaderboard")?;
+        writeln!(
+            f,
+            "{:>3}  {:<24} {:>7} {:>6}  {:>9}  {:>9}  95% CI",
+            "#", "Bot", "Rating", "Points", "W-D-L", "Chips/deal"
+        )?;
+        for (rank, standing) in self.standings.iter().enumerate() {
+            let record = format!("{}-{}-{}", standing.wins, standing.draws, standing.losses);
+            writeln!(
+                f,
+                "{:>3}  {:<24} {:>7.0} {:>6.1}  {:>9}  {:>+9.3}  {:+.3} to {:+.3}{}",
+                rank + 1,
+                standing.name,
+                standing.rating,
+                standing.points,
+                record,
+                standing.per_deal.estimate,
+                standing.per_deal.low,
+                standing.per_deal.high,
+                if standing.disqualified.is_some() { "  (disqualified)" } else { "" }
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Matches (chips per deal, * = significant at 95%)")?;
+        for result in &self.matches {
+            let verdict = match result.score {
+                score if score > 0.5 => "beat",
+                score if score < 0.5 => "lost to",
+                _ => "drew with",
+            };
+            writeln!(
+                f,
+                "  R{} {} {} {}: {:+.3} ({:+.3} to {:+.3}){}",
+                result.round,
+                self.names[result.first],
+                verdict,
+                self.names[result.second],
+                result.difference.estimate,
+                result.difference.low,
+                result.difference.high,
+                if result.is_significant() { " *" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Chance that a player rated `rating` scores against one rated `opponent`
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// New ratings for two players after `first` scored `score` (1, 0.5 or 0) against `second`
+pub fn elo_update(first: f64, second: f64, score: f64) -> (f64, f64) {
+    let change = ELO_K * (score - expected_score(first, second));
+    (first + change, second - change)
+}
+
+/// Runs a tournament between `entrants`
+///
+/// Each entrant plays a given set of deals at most once; its result is reused in
+/// every match on those deals, so a round-robin costs one run per entrant.
+pub fn run(entrants: &[Entrant], config: &TournamentConfig) -> error::Result<TournamentReport> {
+    let mut tournament = Tournament::new(entrants, config);
+    match config.format {
+        Format::RoundRobin => {
+            for first in 0..entrants.len() {
+                for second in first + 1..entrants.len() {
+                    tournament.play(1, first, second)?;
+                }
+            }
+        }
+        Format::Swiss { rounds } => {
+            for round in 1..=rounds {
+                let (pairs, bye) = tournament.swiss_pairs();
+                for (first, second) in pairs {
+                    tournament.play(round, first, second)?;
+                }
+                if let Some(entrant) = bye {
+                    tournament.standings[entrant].points += 1.0;
+                    tournament.standings[entrant].byes += 1;
+                }
+            }
+        }
+    }
+    Ok(tournament.report())
+}
+
+/// Running state of a tournament
+struct Tournament<'a> {
+    entrants: &'a [Entrant],
+    config: &'a TournamentConfig,
+    standings: Vec<Standing>,
+    matches: Vec<MatchResult>,
+    /// Each entrant's result on the deals starting at a given seed
+    runs: HashMap<(usize, u64), MatchReport>,
+    met: HashSet<(usize, usize)>,
+}
+
+impl<'a> Tournament<'a> {
+    fn new(entrants: &'a [Entrant], config: &'a TournamentConfig) -> Self {
+        let standings = entrants
+            .iter()
+            .map(|entrant| Standing {
+                name: entrant.name.clone(),
+                rating: INITIAL_RATING,
+                points: 0.0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                byes: 0,
+                per_deal: mean_interval(0, 0.0, 0.0),
+                disqualified: None,
+            })
+            .collect();
+        Tournament {
+            entrants,
+            config,
+            standings,
+            matches: Vec::new(),
+            runs: HashMap::new(),
+            met: HashSet::new(),
+        }
+    }
+
+    /// Deals for a tournament round: round-robin uses one set, Swiss a new set each round
+    ///
+    /// The first round deals from `game.seed` itself, like a single match with that seed.
+    fn seed(&self, round: u32) -> u64 {
+        match round {
+            1 => self.config.game.seed,
+            _ => mix_seed(self.config.game.seed, u64::from(round - 1)),
+        }
+    }
+
+    /// Plays (or looks up) an entrant's run over a set of deals
+    fn run_for(&mut self, entrant: usize, seed: u64) -> error::Result<&MatchReport> {
+        if !self.runs.contains_key(&(entrant, seed)) {
+            let game = MatchConfig {
+                seed,
+                duplicate: true,
+                ..self.config.game.clone()
+            };
+            let report = match (self.entrants[entrant].factory)() {
+                Ok(mut bot) => play_match(bot.as_mut(), &game)?,
+                Err(message) => MatchReport {
+                    bot: self.entrants[entrant].name.clone(),
+                    tally: Default::default(),
+                    nets: Vec::new(),
+                    faults: Vec::new(),
+                    disqualified: Some(super::BotFault::Disconnected(message)),
+                },
+            };
+            self.runs.insert((entrant, seed), report);
+        }
+        Ok(&self.runs[&(entrant, seed)])
+    }
+
+    fn play(&mut self, round: u32, first: usize, second: usize) -> error::Result<()> {
+        let seed = self.seed(round);
+        let a = self.run_for(first, seed)?.clone();
+        let b = self.run_for(second, seed)?.clone();
+        for (index, run) in [(first, &a), (second, &b)] {
+            if let (Some(fault), None) = (&run.disqualified, &self.standings[index].disqualified) {
+                self.standings[index].disqualified = Some(fault.to_string());
+            }
+        }
+
+        // A disqualified bot forfeits; otherwise compare chips over the deals both finished
+        let deals = a.nets.len().min(b.nets.len());
+        let differences = a.nets[..deals].iter().zip(&b.nets[..deals]).map(|(x, y)| (x - y) as f64);
+        let (sum, sum_of_squares) = differences.fold((0.0, 0.0), |(s, q), d| (s + d, q + d * d));
+        let difference = mean_interval(deals as u64, sum, sum_of_squares);
+        let score = match (a.disqualified.is_some(), b.disqualified.is_some()) {
+            (true, false) => 0.0,
+            (false, true) => 1.0,
+            _ if sum > 0.0 => 1.0,
+            _ if sum < 0.0 => 0.0,
+            _ => 0.5,
+        };
+
+        let (rating_a, rating_b) =
+            elo_update(self.standings[first].rating, self.standings[second].rating, score);
+        self.standings[first].rating = rating_a;
+        self.standings[second].rating = rating_b;
+        self.record(first, score);
+        self.record(second, 1.0 - score);
+        self.met.insert((first.min(second), first.max(second)));
+        self.matches.push(MatchResult {
+            round,
+            first,
+            second,
+            score,
+            difference,
+        });
+        Ok(())
+    }
+
+    fn record(&mut self, entrant: usize, score: f64) {
+        let standing = &mut self.standings[entrant];
+        standing.points += score;
+        match score {
+            s if s > 0.5 => standing.wins += 1,
+            s if s < 0.5 => standing.losses += 1,
+            _ => standing.draws += 1,
+        }
+    }
+
+    /// Pairs entrants with similar points, avoiding rematches where possible
+    ///
+    /// With an odd number of entrants the lowest-placed one without a bye sits out.
+    fn swiss_pairs(&self) -> (Vec<(usize, usize)>, Option<usize>) {
+        let mut order: Vec<usize> = (0..self.standings.len()).collect();
+        order.sort_by(|&x, &y| self.rank_order(x, y));
+
+        let mut bye = None;
+        if order.len() % 2 == 1 {
+            let position = order
+                .iter()
+                .rposition(|&entrant| self.standings[entrant].byes == 0)
+                .unwrap_or(order.len() - 1);
+            bye = Some(order.remove(position));
+        }
+
+        let pairs = self.pair_without_rematches(&order).unwrap_or_else(|| {
+            // Everyone left has met; pair neighbours in ranking order
+            order.chunks(2).map(|pair| (pair[0], pair[1])).collect()
+        });
+        (pairs, bye)
+    }
+
+    /// Pairs the best-placed entrant with the next one it has not met, backtracking if needed
+    fn pair_without_rematches(&self, order: &[usize]) -> Option<Vec<(usize, usize)>> {
+        let Some((&first, rest)) = order.split_first() else {
+            return Some(Vec::new());
+        };
+        for (position, &second) in rest.iter().enumerate() {
+            if self.met.contains(&(first.min(second), first.max(second))) {
+                continue;
+            }
+            let mut remaining = rest.to_vec();
+            remaining.remove(position);
+            if let Some(mut pairs) = self.pair_without_rematches(&remaining) {
+                pairs.insert(0, (first, second));
+                return Some(pairs);
+            }
+        }
+        None
+    }
+
+    fn rank_order(&self, x: usize, y: usize) -> std::cmp::Ordering {
+        let (a, b) = (&self.standings[x], &self.standings[y]);
+        b.points
+            .total_cmp(&a.points)
+            .then(b.rating.total_cmp(&a.rating))
+            .then(x.cmp(&y))
+    }
+
+    fn report(mut self) -> TournamentReport {
+        // Every deal an entrant played counts towards its chips per deal
+        for (index, standing) in self.standings.iter_mut().enumerate() {
+            let nets = self
+                .runs
+                .iter()
+                .filter(|((entrant, _), _)| *entrant == index)
+                .flat_map(|(_, run)| run.nets.iter().map(|net| *net as f64));
+            let (count, sum, sum_of_squares) =
+                nets.fold((0u64, 0.0, 0.0), |(c, s, q), net| (c + 1, s + net, q + net * net));
+            standing.per_deal = mean_interval(count, sum, sum_of_squares);
+        }
+
+        let mut order: Vec<usize> = (0..self.standings.len()).collect();
+        order.sort_by(|&x, &y| self.rank_order(x, y));
+        TournamentReport {
+            standings: order.iter().map(|&index| self.standings[index].clone()).collect(),
+            matches: self.matches,
+            names: self.entrants.iter().map(|entrant| entrant.name.clone()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn entrants(names: &[&str]) -> Vec<Entrant> {
+        names.iter().map(|name| Entrant::strategy(name).unwrap()).collect()
+    }
+
+    fn config(format: Format, deals: u32) -> TournamentConfig {
+        let mut config = TournamentConfig {
+            format,
+            ..TournamentConfig::default()
+        };
+        config.game.rounds = deals;
+        config
+    }
+
+    // Property: Elo updates move both ratings by the same amount in opposite directions
+    proptest! {
+        #[test]
+        fn prop_elo_is_zero_sum(
+            first in 800.0f64..2400.0,
+            second in 800.0f64..2400.0,
+            score in prop_oneof![Just(0.0), Just(0.5), Just(1.0)]
+        ) {
+            let (a, b) = elo_update(first, second, score);
+            prop_assert!(((a + b) - (first + second)).abs() < 1e-9);
+            prop_assert!((a - first).abs() <= ELO_K);
+            let expected = expected_score(first, second) + expected_score(second, first);
+            prop_assert!((expected - 1.0).abs() < 1e-12);
+        }
+    }
+
+    // Property: A round-robin plays every pair once and hands out one point per match
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+        #[test]
+        fn prop_round_robin_is_complete(seed in any::<u64>()) {
+            let entrants = entrants(&["basic", "stick-on-15", "stick-on-17", "stick-on-19"]);
+            let mut config = config(Format::RoundRobin, 200);
+            config.game.seed = seed;
+            let report = run(&entrants, &config).unwrap();
+            prop_assert_eq!(report.matches.len(), 6);
+            let points: f64 = report.standings.iter().map(|s| s.points).sum();
+            prop_assert!((points - 6.0).abs() < 1e-9);
+            let ratings: f64 = report.standings.iter().map(|s| s.rating).sum();
+            prop_assert!((ratings - 4.0 * INITIAL_RATING).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_bot_against_itself_draws_on_duplicate_deals() {
+        let report = run(&entrants(&["basic", "basic"]), &config(Format::RoundRobin, 300)).unwrap();
+        assert_eq!(report.matches[0].score, 0.5);
+        assert_eq!(report.matches[0].difference.estimate, 0.0);
+        assert!(!report.matches[0].is_significant());
+    }
+
+    #[test]
+    fn test_basic_strategy_beats_reckless_play() {
+        let entrants = entrants(&["stick-on-21", "basic"]);
+        let report = run(&entrants, &config(Format::RoundRobin, 3_000)).unwrap();
+        assert_eq!(report.standings[0].name, "basic");
+        assert!(report.matches[0].is_significant());
+        assert!(report.standings[0].rating > INITIAL_RATING);
+    }
+
+    #[test]
+    fn test_swiss_avoids_rematches_and_gives_byes() {
+        let entrants = entrants(&["basic", "stick-on-15", "stick-on-16", "stick-on-17", "stick-on-18"]);
+        let report = run(&entrants, &config(Format::Swiss { rounds: 3 }, 100)).unwrap();
+        assert_eq!(report.matches.len(), 6);
+        let byes: u32 = report.standings.iter().map(|s| s.byes).sum();
+        assert_eq!(byes, 3);
+        assert!(report.standings.iter().all(|s| s.byes <= 1));
+
+        let mut pairs: Vec<_> = report
+            .matches
+            .iter()
+            .map(|m| (m.first.min(m.second), m.first.max(m.second)))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 6);
+    }
+
+    #[test]
+    fn test_bot_that_cannot_start_forfeits() {
+        let broken = Entrant::new("broken", Box::new(|| Err("no such bot".to_string())));
+        let entrants = vec![broken, Entrant::strategy("basic").unwrap()];
+        let report = run(&entrants, &config(Format::RoundRobin, 50)).unwrap();
+        assert_eq!(report.standings[0].name, "basic");
+        assert_eq!(report.standings[1].disqualified.as_deref(), Some("disconnected: no such bot"));
+    }
+}
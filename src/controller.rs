@@ -0,0 +1,463 @@
+use crate::error::{PontoonError, Result};
+use crate::game::advisor::{Advisor, SeenCards};
+use crate::game::event::EventListener;
+use crate::game::rules::Rules;
+use crate::game::stats::SessionStats;
+use crate::game::strategy::Action;
+use crate::game::table::Table;
+use crate::game::view::{TableView, Viewer, PLAYER_SEAT};
+use crate::history::HistoryWriter;
+use crate::models::deck::Deck;
+use crate::ui::display::Display;
+use crate::ui::input::{Command, Input};
+use std::io::BufRead;
+use std::sync::{Arc, Mutex};
+
+/// Stake placed on every interactive round
+pub const DEFAULT_STAKE: u32 = 10;
+
+/// Runs the interactive game: deals rounds, reads the player's commands and shows results
+pub struct GameController<R: BufRead> {
+    display: Display,
+    input: Input<R>,
+    table: Table,
+    advisor: Advisor,
+    hints: bool,
+    /// Show the cards still to come before each decision
+    odds: bool,
+    /// Every card the player has seen since the last shuffle
+    seen: Arc<Mutex<SeenCards>>,
+    stats: Arc<Mutex<SessionStats>>,
+    history: Option<HistoryWriter>,
+}
+
+impl<R: BufRead> GameController<R> {
+    /// Creates a controller with the house banker and hints turned off
+    pub fn new(display: Display, input: Input<R>, deck: Deck, rules: Rules) -> Self {
+        let seen = Arc::new(Mutex::new(SeenCards::default()));
+        let stats = Arc::new(Mutex::new(SessionStats::default()));
+        let mut table = Table::new(deck, rules);
+        table.subscribe(Box::new(seen.clone()));
+        table.subscribe(Box::new(stats.clone()));
+        GameController {
+            display,
+            input,
+            table,
+            advisor: Advisor::default(),
+            hints: false,
+            odds: false,
+            seen,
+            stats,
+            history: None,
+        }
+    }
+
+    /// Sends every game event to another listener as well
+    pub fn subscribe(&mut self, listener: Box<dyn EventListener>) {
+        self.table.subscribe(listener);
+    }
+
+    /// Records every round to a hand history
+    pub fn with_history(mut self, history: HistoryWriter) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Deals practice rounds, in which moves can be undone and redone but nothing is scored
+    pub fn with_practice(mut self) -> Self {
+        self.table = self.table.with_practice();
+        self
+    }
+
+    /// Shows the odds panel before each decision from the start
+    pub fn with_odds(mut self) -> Self {
+        self.odds = true;
+        self
+    }
+
+    /// Returns true if the advisor is shown before each decision
+    pub fn hints_enabled(&self) -> bool {
+        self.hints
+    }
+
+    /// Returns true if the odds panel is shown before each decision
+    pub fn odds_enabled(&self) -> bool {
+        self.odds
+    }
+
+    /// Returns the totals for the session so far
+    pub fn stats(&self) -> SessionStats {
+        self.stats.lock().expect("Session stats poisoned").clone()
+    }
+
+    /// Plays rounds until the player quits or declines another round
+    ///
+    /// Stops early if the engine fails, e.g. when the deck runs out part way through a round.
+    pub fn run(&mut self) -> Result<()> {
+        self.display.show_welcome();
+        self.display.show_message("Welcome to Pontoon!");
+        let rules = self.table.rules();
+        if rules.name != "standard" {
+            self.display.show_message(&format!("Rules: {} - {}", rules.name, rules.description));
+        }
+        if self.table.is_practice() {
+            self.display.show_message(
+                "Practice game: [u]ndo and [r]edo take back or replay a move. Practice hands are not scored.",
+            );
+        }
+        let extras = [
+            (rules.double_down, "[d]ouble"),
+            (rules.split, "s[p]lit"),
+            (rules.surrender, "[su]rrender"),
+        ];
+        let mut commands = vec!["[t]wist", "[s]tick"];
+        commands.extend(extras.iter().filter(|(allowed, _)| *allowed).map(|(_, name)| *name));
+        commands.extend(["[h]ints on/off", "[o]dds on/off", "[q]uit"]);
+        self.display.show_message(&format!("Commands: {}", commands.join(", ")));
+
+        let mut deal = true;
+        loop {
+            if deal {
+                self.deal_round()?;
+            }
+            if !self.play_round()? {
+                break;
+            }
+            match self.ask_play_again() {
+                Command::Undo => deal = false,
+                answer => {
+                    self.record_round();
+                    if answer != Command::Yes {
+                        break;
+                    }
+                    deal = true;
+                }
+            }
+        }
+        self.display.show_session_stats(&self.stats());
+        self.display.show_message("Thanks for playing!");
+        Ok(())
+    }
+
+    /// Asks until the player answers yes or no; in practice they may also undo their last move
+    fn ask_play_again(&mut self) -> Command {
+        let prompt = if self.table.is_practice() {
+            "Play another round? (y/n, u to undo)"
+        } else {
+            "Play another round? (y/n)"
+        };
+        loop {
+            self.display.show_prompt(prompt);
+            match self.input.read_command() {
+                Command::Yes => return Command::Yes,
+                Command::No | Command::Quit => return Command::No,
+                Command::Undo if self.undo() => return Command::Undo,
+                _ => {}
+            }
+        }
+    }
+
+    /// Takes back the last move in practice, explaining why not otherwise
+    fn undo(&mut self) -> bool {
+        if !self.table.is_practice() {
+            self.display.show_message("Undo is only available in practice games.");
+            false
+        } else if self.table.undo() {
+            self.display.show_message("Move taken back.");
+            true
+        } else {
+            self.display.show_message("There is nothing to undo.");
+            false
+        }
+    }
+
+    /// Replays an undone move in practice, explaining why not otherwise
+    fn redo(&mut self) {
+        if !self.table.is_practice() {
+            self.display.show_message("Redo is only available in practice games.");
+        } else if self.table.redo() {
+            self.display.show_message("Move played again.");
+        } else {
+            self.display.show_message("There is nothing to redo.");
+        }
+    }
+
+    /// Shuffles if needed and deals a new round
+    fn deal_round(&mut self) -> Result<()> {
+        if self.table.deck().needs_reshuffle() {
+            self.display.show_message("Shuffling the deck...");
+        }
+        self.table.start_round(DEFAULT_STAKE)?;
+        Ok(())
+    }
+
+    /// Plays the dealt round to the end; returns false if the player quit part way through
+    fn play_round(&mut self) -> Result<bool> {
+        while let Some(view) = self.view().filter(|view| view.turn.is_some()) {
+            self.display.show_separator();
+            if let Some(round) = self.table.round().filter(|round| round.player_hands().len() > 1) {
+                let hand = round.state().played.len() + 1;
+                let hands = round.player_hands().len();
+                self.display.show_message(&format!("Playing split hand {} of {}", hand, hands));
+            }
+            self.show_table(&view);
+
+            if self.hints {
+                let seen = self.seen.lock().expect("Seen cards poisoned");
+                let shoe = self.table.deck().shoe_composition();
+                let advice = view.with_situation(|situation| {
+                    self.advisor.advise(
+                        situation.hand,
+                        situation.banker_upcard,
+                        situation.rules,
+                        situation.legal_actions,
+                        &shoe,
+                        seen.cards(),
+                    )
+                });
+                if let Some(advice) = advice {
+                    self.display.show_advice(&advice);
+                }
+            }
+
+            if self.odds {
+                let seen = self.seen.lock().expect("Seen cards poisoned");
+                let unseen = self.table.deck().shoe_composition().without(seen.cards());
+                if let Some(hand) = view.own_seat().and_then(|seat| seat.hand.hand()) {
+                    self.display.show_composition(&unseen, &hand);
+                }
+            }
+
+            self.display.show_prompt(&prompt(&view.legal_actions));
+            let action = match self.input.read_command() {
+                Command::Twist => Action::Twist,
+                Command::Stick => Action::Stick,
+                Command::Double => Action::Double,
+                Command::Split => Action::Split,
+                Command::Surrender => Action::Surrender,
+                Command::ToggleHints => {
+                    self.hints = !self.hints;
+                    let state = if self.hints { "on" } else { "off" };
+                    self.display.show_message(&format!("Hints turned {}.", state));
+                    continue;
+                }
+                Command::ToggleOdds => {
+                    self.odds = !self.odds;
+                    let state = if self.odds { "on" } else { "off" };
+                    self.display.show_message(&format!("Odds turned {}.", state));
+                    continue;
+                }
+                Command::Undo => {
+                    self.undo();
+                    continue;
+                }
+                Command::Redo => {
+                    self.redo();
+                    continue;
+                }
+                Command::Quit => return Ok(false),
+                _ => {
+                    let practice = self.table.is_practice();
+                    self.display.show_message(&commands_help(&view.legal_actions, practice));
+                    continue;
+                }
+            };
+
+            match self.table.act(action) {
+                Err(PontoonError::IllegalAction { .. }) if action == Action::Stick => {
+                    self.display.show_message(&format!(
+                        "You can't stick below {}.",
+                        self.table.rules().min_stick
+                    ))
+                }
+                Err(PontoonError::IllegalAction { .. }) => self
+                    .display
+                    .show_message(&format!("You can't {} now.", action.to_string().to_lowercase())),
+                result => result?,
+            }
+        }
+
+        self.table.play_banker()?;
+        let view = self.view().expect("A round was just dealt");
+
+        self.display.show_separator();
+        match self.table.round().filter(|round| round.player_hands().len() > 1) {
+            Some(round) => {
+                self.display.show_split_hands(&round.player_hands(), &round.hand_settlements());
+                self.display.show_banker_view(&view.banker);
+            }
+            None => self.show_table(&view),
+        }
+        if let Some(settlement) = view.own_seat().and_then(|seat| seat.settlement) {
+            self.display.show_settlement(&settlement);
+        }
+        self.display.show_message(&format!(
+            "Cards remaining in deck: {}",
+            self.table.deck().cards_remaining()
+        ));
+        Ok(true)
+    }
+
+    /// Returns the player's view of the current round
+    fn view(&self) -> Option<TableView> {
+        self.table.view(Viewer::Seat(PLAYER_SEAT))
+    }
+
+    /// Shows the player's hand and the banker's hand as the player sees them
+    fn show_table(&self, view: &TableView) {
+        if let Some(hand) = view.own_seat().and_then(|seat| seat.hand.hand()) {
+            self.display.show_player_hand(&hand);
+        }
+        self.display.show_banker_view(&view.banker);
+    }
+
+    /// Writes the finished round to the hand history, once the player has moved on from it
+    fn record_round(&mut self) {
+        let (Some(history), Some(round)) = (self.history.as_mut(), self.table.round()) else {
+            return;
+        };
+        if let Err(error) = history.record_round(self.table.round_deck().to_vec(), round) {
+            self.display
+                .show_message(&format!("Could not write hand history ({}); recording stopped.", error));
+            self.history = None;
+        }
+    }
+}
+
+/// Asks "Twist or Stick?", naming any further actions the rules offer on this hand
+fn prompt(legal: &[Action]) -> String {
+    let extras: Vec<String> = legal
+        .iter()
+        .filter(|action| !matches!(action, Action::Twist | Action::Stick))
+        .map(Action::to_string)
+        .collect();
+    match extras.split_last() {
+        None => "Twist or Stick?".to_string(),
+        Some((last, rest)) => {
+            let mut actions = vec!["Twist".to_string(), "Stick".to_string()];
+            actions.extend_from_slice(rest);
+            format!("{} or {}?", actions.join(", "), last)
+        }
+    }
+}
+
+/// Lists the commands that can be typed now: the legal moves, then the other commands
+fn commands_help(legal: &[Action], practice: bool) -> String {
+    let mut commands: Vec<String> = legal
+        .iter()
+        .map(|action| {
+            let key = match action {
+                Action::Twist => "t",
+                Action::Stick => "s",
+                Action::Double => "d",
+                Action::Split => "p",
+                Action::Surrender => "su",
+            };
+            format!("{} ({})", key, action.to_string().to_lowercase())
+        })
+        .collect();
+    commands.extend(["h (hints)", "o (odds)"].map(String::from));
+    if practice {
+        commands.extend(["u (undo)", "r (redo)"].map(String::from));
+    }
+    format!("Please type {} or q (quit).", commands.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::round::Phase;
+    use std::io::Cursor;
+
+    fn controller(script: &str) -> GameController<Cursor<String>> {
+        GameController::new(
+            Display::new(),
+            Input::new(Cursor::new(script.to_string())),
+            Deck::new_seeded(3),
+            Rules::standard(),
+        )
+    }
+
+    #[test]
+    fn test_game_ends_when_input_runs_out() {
+        let mut game = controller("t\nt\nt\nt\n");
+        game.run().unwrap();
+        // The first twist busts; the rest are not answers to "Play another round?", and the
+        // end of input after them is taken as no
+        assert_eq!(game.table.round().unwrap().phase(), Phase::Finished);
+        let stats = game.stats();
+        assert_eq!((stats.rounds, stats.player_busts, stats.net), (1, 1, -10));
+    }
+
+    #[test]
+    fn test_rounds_are_recorded_to_history() {
+        let path = std::env::temp_dir().join(format!("pontoon-history-{}.txt", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        let history = HistoryWriter::new(Box::new(file), 3, 1, "standard", "standard").unwrap();
+        let mut game = controller("t\nt\nt\nt\nt\ny\nt\nt\nt\nt\nt\nn\n").with_history(history);
+        game.run().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let entries = crate::history::parse(&text).unwrap();
+        let ends = entries.iter().filter(|e| **e == crate::history::Entry::End).count();
+        assert_eq!(ends, 2);
+        assert_eq!(game.stats().rounds, 2);
+    }
+
+    #[test]
+    fn test_undo_is_refused_in_scored_play() {
+        let mut game = controller("u\ns\nt\nt\nt\nt\nn\n");
+        game.run().unwrap();
+        assert_eq!(game.stats().undos, 0);
+        assert_eq!(game.stats().rounds, 1);
+    }
+
+    #[test]
+    fn test_practice_round_can_be_undone_and_replayed() {
+        // Twist to the end of the hand, take the last twist back, finish it again, then stop
+        let mut game = controller("t\nt\nt\nt\nu\nt\nt\nt\nt\nn\n").with_practice();
+        game.run().unwrap();
+        let stats = game.stats();
+        assert_eq!(stats.rounds, 0);
+        assert_eq!(stats.undos, 1);
+        assert_eq!(stats.practice_hands, 2);
+    }
+
+    #[test]
+    fn test_prompt_lists_only_legal_extras() {
+        assert_eq!(prompt(&[Action::Twist, Action::Stick]), "Twist or Stick?");
+        let legal = [Action::Twist, Action::Stick, Action::Double, Action::Surrender];
+        assert_eq!(prompt(&legal), "Twist, Stick, Double or Surrender?");
+    }
+
+    #[test]
+    fn test_help_lists_only_commands_available_now() {
+        assert_eq!(
+            commands_help(&[Action::Twist], false),
+            "Please type t (twist), h (hints), o (odds) or q (quit)."
+        );
+        let legal = [Action::Twist, Action::Stick, Action::Split, Action::Surrender];
+        assert_eq!(
+            commands_help(&legal, true),
+            "Please type t (twist), s (stick), p (split), su (surrender), h (hints), o (odds), \
+             u (undo), r (redo) or q (quit)."
+        );
+    }
+
+    #[test]
+    fn test_hints_toggle_at_runtime() {
+        let mut game = controller("h\nq\n");
+        assert!(!game.hints_enabled());
+        game.run().unwrap();
+        assert!(game.hints_enabled());
+    }
+
+    #[test]
+    fn test_odds_toggle_at_runtime() {
+        let mut game = controller("o\ns\no\nq\n").with_odds();
+        assert!(game.odds_enabled());
+        game.run().unwrap();
+        assert!(game.odds_enabled());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::error::Result;
+use crate::game::counting::{Counter, CountingSystem};
+use crate::models::deck::Deck;
+use crate::ui::display::Display;
+use crate::ui::input::Input;
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+/// Settings for a counting drill
+#[derive(Debug, Clone)]
+pub struct DrillConfig {
+    /// Total cards to flash before the drill ends
+    pub cards: usize,
+    /// Ask for the count after this many cards
+    pub quiz_every: usize,
+    /// How long each card stays on screen
+    pub delay: Duration,
+}
+
+impl Default for DrillConfig {
+    fn default() -> Self {
+        DrillConfig {
+            cards: 60,
+            quiz_every: 10,
+            delay: Duration::from_millis(700),
+        }
+    }
+}
+
+/// Accuracy and speed over a drill
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrillScore {
+    pub asked: usize,
+    pub correct: usize,
+    /// Total time spent answering, used for the average answer time
+    pub answer_time: Duration,
+}
+
+impl DrillScore {
+    /// Returns the mean time taken per answer
+    pub fn average_answer(&self) -> Duration {
+        if self.asked == 0 {
+            Duration::ZERO
+        } else {
+            self.answer_time / self.asked as u32
+        }
+    }
+}
+
+/// Flashes cards from a shoe and quizzes the player on the count
+pub struct CountDrill<R: BufRead> {
+    display: Display,
+    input: Input<R>,
+    deck: Deck,
+    counter: Counter,
+    config: DrillConfig,
+}
+
+impl<R: BufRead> CountDrill<R> {
+    /// Creates a drill counting `deck` with the given system
+    pub fn new(
+        display: Display,
+        input: Input<R>,
+        deck: Deck,
+        system: Box<dyn CountingSystem>,
+        config: DrillConfig,
+    ) -> Self {
+        let counter = Counter::new(system, deck.deck_count());
+        CountDrill {
+            display,
+            input,
+            deck,
+            counter,
+            config,
+        }
+    }
+
+    /// Runs the drill and returns the score; stops early at end of input
+    pub fn run(&mut self) -> Result<DrillScore> {
+        let mut score = DrillScore::default();
+        self.display.show_message(&format!(
+            "Counting drill: {} with {} deck(s). Keep the running count!",
+            self.counter.system().name(),
+            self.deck.deck_count()
+        ));
+
+        let mut flashed = 0;
+        while flashed < self.config.cards {
+            let batch = self.config.quiz_every.max(1).min(self.config.cards - flashed);
+            for _ in 0..batch {
+                if self.deck.needs_reshuffle() {
+                    self.deck.reshuffle();
+                    self.counter.reset();
+                    self.display.show_message("Shoe reshuffled - the count starts again.");
+                }
+                let card = self.deck.deal()?;
+                self.counter.observe(card);
+                self.display.show_flash_card(&card, self.config.delay);
+            }
+            flashed += batch;
+
+            if !self.quiz("Running count?", self.counter.running_count(), &mut score) {
+                break;
+            }
+            if let Some(true_count) = self.counter.true_count(self.deck.decks_remaining()) {
+                if !self.quiz("True count?", true_count, &mut score) {
+                    break;
+                }
+            }
+        }
+
+        self.display
+            .show_drill_score(score.correct, score.asked, score.average_answer());
+        Ok(score)
+    }
+
+    /// Asks one question; returns false at end of input
+    fn quiz(&mut self, question: &str, expected: i32, score: &mut DrillScore) -> bool {
+        self.display.show_prompt(question);
+        let started = Instant::now();
+        let Some(answer) = self.input.read_line() else {
+            return false;
+        };
+        score.answer_time += started.elapsed();
+        score.asked += 1;
+
+        if answer.parse::<i32>() == Ok(expected) {
+            score.correct += 1;
+            self.display.show_message("✔ Correct");
+        } else {
+            self.display
+                .show_message(&format!("✘ The count was {}", expected));
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::counting::{HiLo, KnockOut};
+    use std::io::Cursor;
+
+    fn config(cards: usize, quiz_every: usize) -> DrillConfig {
+        DrillConfig {
+            cards,
+            quiz_every,
+            delay: Duration::ZERO,
+        }
+    }
+
+    fn expected_running_counts(seed: u64, cards: usize, quiz_every: usize) -> Vec<i32> {
+        let mut deck = Deck::new_seeded(seed);
+        let mut counter = Counter::new(Box::new(KnockOut), 1);
+        let mut counts = Vec::new();
+        for i in 1..=cards {
+            counter.observe(deck.deal().unwrap());
+            if i % quiz_every == 0 {
+                counts.push(counter.running_count());
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_correct_answers_score_full_marks() {
+        let answers: Vec<String> = expected_running_counts(9, 30, 10)
+            .iter()
+            .map(|count| count.to_string())
+            .collect();
+        let script = answers.join("\n") + "\n";
+        let mut drill = CountDrill::new(
+            Display::new(),
+            Input::new(Cursor::new(script)),
+            Deck::new_seeded(9),
+            Box::new(KnockOut),
+            config(30, 10),
+        );
+        let score = drill.run().unwrap();
+        assert_eq!(score.asked, 3);
+        assert_eq!(score.correct, 3);
+    }
+
+    #[test]
+    fn test_balanced_system_also_asks_true_count() {
+        let mut drill = CountDrill::new(
+            Display::new(),
+            Input::new(Cursor::new("x\nx\n")),
+            Deck::new_seeded(1),
+            Box::new(HiLo),
+            config(10, 10),
+        );
+        let score = drill.run().unwrap();
+        assert_eq!(score.asked, 2);
+        assert_eq!(score.correct, 0);
+    }
+
+    #[test]
+    fn test_drill_stops_at_end_of_input() {
+        let mut drill = CountDrill::new(
+            Display::new(),
+            Input::new(Cursor::new(String::new())),
+            Deck::new_seeded(1),
+            Box::new(HiLo),
+            config(100, 5),
+        );
+        assert_eq!(drill.run().unwrap(), DrillScore::default());
+    }
+}
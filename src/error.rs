@@ -0,0 +1,115 @@
+use crate::game::strategy::Action;
+use crate::history::ParseHistoryError;
+use crate::models::card::ParseCardError;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while playing, reading or writing a game
+#[derive(Debug)]
+pub enum PontoonError {
+    /// The deck ran out of cards part way through a deal
+    DeckExhausted,
+    /// An action was tried when it is not allowed; `legal` is what could be played
+    IllegalAction { action: Action, legal: Vec<Action> },
+    /// A player has fewer chips than they need to stake
+    InsufficientFunds { needed: u32, available: u32 },
+    /// Text that is not a card, a hand history line or a protocol line
+    ParseError(String),
+    /// A setting out of range, such as an empty stake schedule
+    InvalidConfig(String),
+    /// The other end of a connection broke the protocol
+    Protocol(String),
+    /// Reading or writing a file, pipe or connection failed
+    Io(io::Error),
+}
+
+impl PontoonError {
+    /// Returns the process exit code for this error, following BSD `sysexits.h`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PontoonError::DeckExhausted
+            | PontoonError::IllegalAction { .. }
+            | PontoonError::InsufficientFunds { .. } => 70,
+            PontoonError::ParseError(_) => 65,
+            PontoonError::InvalidConfig(_) => 64,
+            PontoonError::Protocol(_) => 76,
+            PontoonError::Io(_) => 74,
+        }
+    }
+}
+
+impl fmt::Display for PontoonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PontoonError::DeckExhausted => write!(f, "the deck has run out of cards"),
+            PontoonError::IllegalAction { action, legal } if legal.is_empty() => {
+                write!(f, "{} is not allowed: it is not the player's turn", action)
+            }
+            PontoonError::IllegalAction { action, legal } => {
+                let legal: Vec<String> = legal.iter().map(Action::to_string).collect();
+                write!(f, "{} is not allowed (legal: {})", action, legal.join(", "))
+            }
+            PontoonError::InsufficientFunds { needed, available } => {
+                write!(f, "{} chips are needed but only {} are available", needed, available)
+            }
+            PontoonError::ParseError(message) => write!(f, "{}", message),
+            PontoonError::InvalidConfig(message) => write!(f, "{}", message),
+            PontoonError::Protocol(message) => write!(f, "protocol error: {}", message),
+            PontoonError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for PontoonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PontoonError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PontoonError {
+    fn from(error: io::Error) -> Self {
+        PontoonError::Io(error)
+    }
+}
+
+impl From<ParseHistoryError> for PontoonError {
+    fn from(error: ParseHistoryError) -> Self {
+        PontoonError::ParseError(error.to_string())
+    }
+}
+
+impl From<ParseCardError> for PontoonError {
+    fn from(error: ParseCardError) -> Self {
+        PontoonError::ParseError(error.to_string())
+    }
+}
+
+/// Shorthand for results whose error is a [`PontoonError`]
+pub type Result<T> = std::result::Result<T, PontoonError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_io_errors_keep_their_source() {
+        let error = PontoonError::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(error.to_string(), "no such file");
+        assert!(error.source().is_some());
+        assert!(PontoonError::DeckExhausted.source().is_none());
+    }
+
+    #[test]
+    fn test_illegal_action_lists_the_legal_moves() {
+        let error = PontoonError::IllegalAction {
+            action: Action::Stick,
+            legal: vec![Action::Twist],
+        };
+        assert_eq!(error.to_string(), "Stick is not allowed (legal: Twist)");
+        assert_eq!(error.exit_code(), 70);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::error::Result;
+use crate::game::freezeout::{Freezeout, FreezeoutConfig, Standing};
+use crate::game::strategy::{Action, PlayerStrategy};
+use crate::game::timer::{Clock, SystemClock, TimerEvent, TurnLimit, TurnTimer, CLOCK_CHECK};
+use crate::game::view::TableView;
+use crate::ui::display::Display;
+use crate::ui::input::{Command, TimedInput};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Seat of the human player in an interactive tournament
+pub const HUMAN_SEAT: usize = 1;
+
+/// Runs a freezeout tournament between the player and built-in strategies
+pub struct FreezeoutGame {
+    display: Display,
+    input: TimedInput,
+    freezeout: Freezeout,
+    /// The strategy at each seat after the player's, in seat order
+    opponents: Vec<Box<dyn PlayerStrategy>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl FreezeoutGame {
+    /// Seats the player at seat 1 and the opponents after them
+    pub fn new(
+        display: Display,
+        input: TimedInput,
+        config: FreezeoutConfig,
+        opponents: Vec<Box<dyn PlayerStrategy>>,
+    ) -> Result<Self> {
+        let names: Vec<String> = std::iter::once("You".to_string())
+            .chain(opponents.iter().map(|opponent| opponent.name()))
+            .collect();
+        Ok(FreezeoutGame {
+            display,
+            input,
+            freezeout: Freezeout::new(config, &names)?,
+            opponents,
+            clock: Arc::new(SystemClock::new()),
+        })
+    }
+
+    /// Times the player's turns by `clock` instead of the wall clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Plays until one player is left or the player quits; returns the standings
+    ///
+    /// Once the player is knocked out the others play on unseen to settle the places.
+    pub fn run(&mut self) -> Result<Vec<Standing>> {
+        let FreezeoutGame {
+            display,
+            input,
+            freezeout,
+            opponents,
+            clock,
+        } = self;
+        let config = freezeout.config();
+        let turn_limit = config.turn_limit.clone();
+        display.show_welcome();
+        display.show_message(&format!(
+            "Freezeout: everyone starts with {} chips. Minimum stakes: {}.",
+            config.starting_chips, config.schedule
+        ));
+
+        let mut level = None;
+        let mut quit = false;
+        while !freezeout.is_over() && !quit {
+            let watching = freezeout.entry(HUMAN_SEAT).is_some_and(|e| e.eliminated.is_none());
+            if watching && level != Some(freezeout.level()) {
+                if level.is_some() {
+                    display.show_standings(freezeout.rounds_played(), &freezeout.standings());
+                }
+                level = Some(freezeout.level());
+                display.show_message(&format!(
+                    "Level {}: minimum stake {}",
+                    freezeout.level() + 1,
+                    freezeout.minimum_stake()
+                ));
+            }
+            let names: Vec<String> = freezeout.entries().iter().map(|e| e.name.clone()).collect();
+            let chips = freezeout.entry(HUMAN_SEAT).map_or(0, |entry| entry.chips);
+            let stake = freezeout.stake_for(HUMAN_SEAT);
+            if watching {
+                display.show_separator();
+                display.show_message(&format!(
+                    "Round {}   Your chips: {}   Stake: {}",
+                    freezeout.rounds_played() + 1,
+                    chips,
+                    stake
+                ));
+            }
+
+            let results = freezeout.play_round(&mut |seat, view| {
+                if seat != HUMAN_SEAT {
+                    return view
+                        .with_situation(|situation| opponents[seat - 2].decide(situation))
+                        .expect("It is this seat's turn");
+                }
+                if quit {
+                    return fallback(view);
+                }
+                match ask(display, input, &names, view, turn_limit.as_ref(), &**clock) {
+                    Some(action) => action,
+                    None => {
+                        quit = true;
+                        fallback(view)
+                    }
+                }
+            })?;
+
+            if !watching {
+                continue;
+            }
+            for result in &results {
+                let name = &names[result.seat - 1];
+                if result.seat == HUMAN_SEAT {
+                    display.show_settlement(&result.settlement);
+                } else {
+                    display.show_message(&format!(
+                        "{}: {:+} ({} chips)",
+                        name, result.settlement.net, result.chips
+                    ));
+                }
+                if result.eliminated && result.seat == HUMAN_SEAT {
+                    display.show_message("You are out of chips. The others play on...");
+                } else if result.eliminated {
+                    display.show_message(&format!("{} is out of chips.", name));
+                }
+            }
+        }
+
+        let standings = freezeout.standings();
+        display.show_standings(freezeout.rounds_played(), &standings);
+        if let Some(own) = standings.iter().find(|standing| standing.seat == HUMAN_SEAT) {
+            display.show_message(&format!("You finished in place {}.", own.place));
+        }
+        Ok(standings)
+    }
+}
+
+/// Shows the table to the player and reads a move; None if they quit
+///
+/// With a turn limit, the move is made for the player if their time runs out.
+fn ask(
+    display: &Display,
+    input: &TimedInput,
+    names: &[String],
+    view: &TableView,
+    turn_limit: Option<&TurnLimit>,
+    clock: &dyn Clock,
+) -> Option<Action> {
+    for seat in &view.seats {
+        if seat.seat != HUMAN_SEAT {
+            display.show_other_hand(&names[seat.seat - 1], &seat.hand);
+        }
+    }
+    display.show_banker_view(&view.banker);
+    let hand = view.own_seat()?.hand.hand()?;
+    display.show_player_hand(&hand);
+    // Times the whole decision; a refused answer does not restart it
+    let mut timer = turn_limit.map(|limit| TurnTimer::start(limit, clock));
+    loop {
+        let choices = if view.legal_actions.contains(&Action::Stick) {
+            "(t)wist or (s)tick"
+        } else {
+            "(t)wist"
+        };
+        display.show_prompt(&format!("Your turn: {}, or (q)uit?", choices));
+        let command = match timer.as_mut() {
+            Some(timer) => read_timed(display, input, timer, clock),
+            None => Some(input.read_command()),
+        };
+        let Some(command) = command else {
+            let limit = turn_limit.expect("Only a timed turn can run out");
+            let action = limit.on_timeout.choose(view).expect("It is the player's turn");
+            display.show_timed_out(action);
+            return Some(action);
+        };
+        match command {
+            Command::Twist => return Some(Action::Twist),
+            Command::Stick if view.legal_actions.contains(&Action::Stick) => {
+                return Some(Action::Stick)
+            }
+            Command::Quit => return None,
+            _ => display.show_message("Please enter one of the choices shown."),
+        }
+    }
+}
+
+/// Waits for the player's next command, warning as time runs short; None once it is up
+fn read_timed(
+    display: &Display,
+    input: &TimedInput,
+    timer: &mut TurnTimer,
+    clock: &dyn Clock,
+) -> Option<Command> {
+    loop {
+        match timer.poll(clock) {
+            Some(TimerEvent::Expired) => return None,
+            Some(TimerEvent::Warning(left)) => display.show_turn_warning(left),
+            None => {}
+        }
+        let until_next = timer.until_next(clock).unwrap_or(Duration::ZERO);
+        if let Some(command) = input.read_command_within(until_next.min(CLOCK_CHECK)) {
+            return Some(command);
+        }
+    }
+}
+
+/// Stick if allowed, otherwise Twist
+fn fallback(view: &TableView) -> Action {
+    if view.legal_actions.contains(&Action::Stick) {
+        Action::Stick
+    } else {
+        Action::Twist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::freezeout::StakeSchedule;
+    use crate::game::strategy::strategy_by_name;
+    use crate::game::timer::{ManualClock, TimeoutAction};
+    use std::io::Cursor;
+    use std::sync::mpsc;
+
+    /// A clock that moves on a second each time it is read, so a stalled player soon runs out
+    struct TickingClock(ManualClock);
+
+    impl Clock for TickingClock {
+        fn now(&self) -> Duration {
+            self.0.advance(Duration::from_secs(1));
+            self.0.now()
+        }
+    }
+
+    fn game(script: &str, max_rounds: u32) -> FreezeoutGame {
+        let input = TimedInput::spawn(Cursor::new(script.to_string()));
+        timed_game(input, max_rounds, None)
+    }
+
+    fn timed_game(
+        input: TimedInput,
+        max_rounds: u32,
+        turn_limit: Option<TurnLimit>,
+    ) -> FreezeoutGame {
+        let config = FreezeoutConfig {
+            seed: Some(6),
+            starting_chips: 100,
+            schedule: StakeSchedule::new(vec![10, 20, 50], 2).unwrap(),
+            max_rounds: Some(max_rounds),
+            turn_limit,
+            ..FreezeoutConfig::default()
+        };
+        let opponents = ["basic", "stick-on-17"]
+            .iter()
+            .map(|name| strategy_by_name(name).unwrap())
+            .collect();
+        FreezeoutGame::new(Display::new(), input, config, opponents).unwrap()
+    }
+
+    #[test]
+    fn test_tournament_is_played_out_after_the_player_is_knocked_out() {
+        // Always twisting soon loses the player's chips; the others then finish the tournament
+        let mut game = game(&"t\n".repeat(500), 200);
+        let standings = game.run().unwrap();
+        assert_eq!(standings.len(), 3);
+        assert!(game.freezeout.is_over());
+        let own = standings.iter().find(|standing| standing.seat == HUMAN_SEAT).unwrap();
+        assert!(own.eliminated.is_some() || game.freezeout.rounds_played() == 200);
+    }
+
+    #[test]
+    fn test_quitting_ends_the_tournament() {
+        let mut game = game("q\n", 200);
+        let standings = game.run().unwrap();
+        assert_eq!(game.freezeout.rounds_played(), 1);
+        assert_eq!(standings.len(), 3);
+    }
+
+    #[test]
+    fn test_stalled_player_is_played_for() {
+        // Nothing is ever typed, yet the input stays open: only the clock moves the game on
+        let (_keyboard, lines) = mpsc::channel();
+        let limit = TurnLimit::new(Duration::from_secs(5), TimeoutAction::Stick);
+        let mut game = timed_game(TimedInput::new(lines), 3, Some(limit))
+            .with_clock(Arc::new(TickingClock(ManualClock::new())));
+        let standings = game.run().unwrap();
+        assert_eq!(game.freezeout.rounds_played(), 3);
+        assert_eq!(standings.len(), 3);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::game::event::{EventListener, GameEvent};
+use crate::game::rules::Rules;
+use crate::game::strategy::{Action, BasicStrategy, PlayerStrategy, Situation};
+use crate::models::card::Card;
+use crate::models::composition::Composition;
+use crate::models::hand::Hand;
+
+/// A recommendation for the player's next decision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Advice {
+    pub action: Action,
+    /// Chance that the next card dealt would bust the hand, from the cards not yet seen
+    pub bust_probability: f64,
+}
+
+/// Recommends actions using a player strategy and the cards the player has seen
+pub struct Advisor {
+    strategy: Box<dyn PlayerStrategy>,
+}
+
+impl Advisor {
+    /// Creates an advisor backed by the given strategy
+    pub fn new(strategy: Box<dyn PlayerStrategy>) -> Self {
+        Advisor { strategy }
+    }
+
+    /// Advises on `hand` given the banker's upcard and every card seen since the last shuffle
+    ///
+    /// `shoe` is every card in the shoe being dealt from (`Deck::shoe_composition`), so
+    /// the odds allow for Spanish or joker decks. `seen` must include the player's own
+    /// cards and the banker's upcard.
+    pub fn advise(
+        &self,
+        hand: &Hand,
+        banker_upcard: Card,
+        rules: &Rules,
+        legal_actions: &[Action],
+        shoe: &Composition,
+        seen: &[Card],
+    ) -> Advice {
+        let action = self.recommend(&Situation {
+            hand,
+            banker_upcard,
+            rules,
+            legal_actions,
+        });
+        Advice {
+            action,
+            bust_probability: bust_probability(hand, shoe, seen),
+        }
+    }
+
+    /// Returns the recommended action alone, which does not depend on the cards seen
+    pub fn recommend(&self, situation: &Situation) -> Action {
+        self.strategy.decide(situation)
+    }
+}
+
+impl Default for Advisor {
+    fn default() -> Self {
+        Self::new(Box::new(BasicStrategy))
+    }
+}
+
+/// Collects the cards the player has seen since the last shuffle, for [`Advisor::advise`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeenCards {
+    cards: Vec<Card>,
+    /// How many of `cards` were seen before the current round
+    before_round: usize,
+}
+
+impl SeenCards {
+    /// Returns the cards seen so far, in the order they were shown
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+}
+
+impl EventListener for SeenCards {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::DeckReshuffled { .. } => {
+                self.cards.clear();
+                self.before_round = 0;
+            }
+            GameEvent::RoundStarted { .. } => self.before_round = self.cards.len(),
+            // Earlier rounds' cards are back in the shoe; this round's are still on the table
+            GameEvent::ShoeReplenished { .. } => {
+                self.cards.drain(..self.before_round);
+                self.before_round = 0;
+            }
+            GameEvent::CardDealt {
+                card,
+                face_up: true,
+                ..
+            }
+            | GameEvent::CardRevealed { card, .. } => self.cards.push(card),
+            // Cards put back by an undo are unseen again; a redo deals them once more
+            GameEvent::ActionUndone { ref returned, .. } => {
+                for card in returned {
+                    if let Some(index) = self.cards.iter().rposition(|seen| seen == card) {
+                        self.cards.remove(index);
+                    }
+                }
+            }
+            GameEvent::ActionRedone { ref dealt, .. } => self.cards.extend_from_slice(dealt),
+            _ => {}
+        }
+    }
+}
+
+/// Probability that one more card busts `hand`, drawing from `shoe` minus `seen`
+pub fn bust_probability(hand: &Hand, shoe: &Composition, seen: &[Card]) -> f64 {
+    shoe.clone().without(seen).bust_probability(hand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::round::Seat;
+    use crate::models::card::{Rank, Suit};
+    use crate::models::deck_type::DeckType;
+    use proptest::prelude::*;
+
+    fn hand_of(ranks: &[Rank]) -> Hand {
+        let mut hand = Hand::new();
+        for rank in ranks {
+            hand.add_card(Card::new(*rank, Suit::Hearts));
+        }
+        hand
+    }
+
+    fn standard() -> Composition {
+        Composition::full(1)
+    }
+
+    fn any_rank() -> impl Strategy<Value = Rank> {
+        (0..13usize).prop_map(|i| Rank::all()[i])
+    }
+
+    // Property: Bust probability is always a probability
+    proptest! {
+        #[test]
+        fn prop_bust_probability_in_unit_range(ranks in prop::collection::vec(any_rank(), 1..6)) {
+            let hand = hand_of(&ranks);
+            let p = bust_probability(&hand, &standard(), hand.cards());
+            prop_assert!((0.0..=1.0).contains(&p));
+        }
+    }
+
+    // Property: A hard total of 11 or less can never bust on one card
+    proptest! {
+        #[test]
+        fn prop_low_hand_cannot_bust(ranks in prop::collection::vec(any_rank(), 1..3)) {
+            let hand = hand_of(&ranks);
+            let hard: u32 = ranks.iter().map(|r| u32::from(r.base_value())).sum();
+            prop_assume!(hard <= 11);
+            prop_assert_eq!(bust_probability(&hand, &standard(), hand.cards()), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_bust_probability_on_hard_20() {
+        // Only an Ace avoids busting a hard 20; none of the four have been seen
+        let hand = hand_of(&[Rank::King, Rank::Queen]);
+        let p = bust_probability(&hand, &standard(), hand.cards());
+        assert!((p - 46.0 / 50.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_seen_cards_change_the_odds() {
+        let hand = hand_of(&[Rank::King, Rank::Two]);
+        let mut seen = hand.cards().to_vec();
+        let before = bust_probability(&hand, &standard(), &seen);
+        for suit in Suit::all() {
+            seen.push(Card::new(Rank::Queen, suit));
+        }
+        assert!(bust_probability(&hand, &standard(), &seen) < before);
+    }
+
+    #[test]
+    fn test_spanish_deck_busts_hard_hands_less_often() {
+        let spanish = Composition::of_cards(&DeckType::spanish().cards());
+        // Hard 12, 15 and 18
+        let hands = [[Rank::Seven, Rank::Five], [Rank::Nine, Rank::Six], [Rank::Eight, Rank::Jack]];
+        for ranks in hands {
+            let hand = hand_of(&ranks);
+            let on_spanish = bust_probability(&hand, &spanish, hand.cards());
+            assert!(on_spanish < bust_probability(&hand, &standard(), hand.cards()));
+        }
+    }
+
+    #[test]
+    fn test_advice_is_legal() {
+        let hand = hand_of(&[Rank::Two, Rank::Three]);
+        let upcard = Card::new(Rank::Ten, Suit::Spades);
+        let rules = Rules::standard();
+        let legal = [Action::Twist];
+        let advice = Advisor::default().advise(&hand, upcard, &rules, &legal, &standard(), &[]);
+        assert_eq!(advice.action, Action::Twist);
+        assert_eq!(advice.bust_probability, 0.0);
+    }
+
+    #[test]
+    fn test_replenishing_forgets_earlier_rounds() {
+        let mut seen = SeenCards::default();
+        let dealt = |card| GameEvent::CardDealt {
+            seat: Seat::Player,
+            card,
+            face_up: true,
+        };
+        let earlier = Card::new(Rank::Two, Suit::Clubs);
+        let current = Card::new(Rank::King, Suit::Spades);
+        seen.on_event(&GameEvent::RoundStarted { stake: 10, practice: false });
+        seen.on_event(&dealt(earlier));
+        seen.on_event(&GameEvent::RoundStarted { stake: 10, practice: false });
+        seen.on_event(&dealt(current));
+        seen.on_event(&GameEvent::ShoeReplenished { cards: 50 });
+        assert_eq!(seen.cards(), &[current]);
+    }
+}
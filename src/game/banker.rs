@@ -0,0 +1,140 @@
+use crate::game::rules::{HandValue, Rules};
+use crate::game::strategy::Action;
+use crate::models::hand::Hand;
+
+/// Chooses the banker's actions once the player has finished
+pub trait BankerStrategy: Send + Sync {
+    /// Short name used to select the strategy on the command line
+    fn name(&self) -> String;
+
+    /// Decides whether the banker twists or sticks on this hand
+    fn decide(&self, hand: &Hand, rules: &Rules) -> Action;
+}
+
+/// Twists below a fixed total and sticks on or above it (the house rule is 17)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StandOn {
+    pub total: u8,
+}
+
+impl StandOn {
+    /// The house banker for a set of rules, standing on its `banker_stands_on` total
+    pub fn house(rules: &Rules) -> Self {
+        StandOn {
+            total: rules.banker_stands_on,
+        }
+    }
+}
+
+impl Default for StandOn {
+    fn default() -> Self {
+        StandOn { total: 17 }
+    }
+}
+
+impl BankerStrategy for StandOn {
+    fn name(&self) -> String {
+        format!("stand-on-{}", self.total)
+    }
+
+    fn decide(&self, hand: &Hand, _rules: &Rules) -> Action {
+        if HandValue::of(hand).total >= self.total {
+            Action::Stick
+        } else {
+            Action::Twist
+        }
+    }
+}
+
+/// Like the house rule, but twists on a soft 17
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HitSoft17;
+
+impl BankerStrategy for HitSoft17 {
+    fn name(&self) -> String {
+        "hit-soft-17".to_string()
+    }
+
+    fn decide(&self, hand: &Hand, _rules: &Rules) -> Action {
+        let value = HandValue::of(hand);
+        if value.total > 17 || (value.total == 17 && !value.soft) {
+            Action::Stick
+        } else {
+            Action::Twist
+        }
+    }
+}
+
+/// Returns the names of the built-in banker strategies
+pub fn banker_names() -> Vec<String> {
+    vec!["stand-on-<12..21>".to_string(), "hit-soft-17".to_string()]
+}
+
+/// Looks up a built-in banker strategy by name
+pub fn banker_by_name(name: &str) -> Option<Box<dyn BankerStrategy>> {
+    if name == "hit-soft-17" {
+        return Some(Box::new(HitSoft17));
+    }
+    let total = name.strip_prefix("stand-on-")?.parse::<u8>().ok()?;
+    if (12..=21).contains(&total) {
+        Some(Box::new(StandOn { total }))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::{Card, Rank, Suit};
+    use proptest::prelude::*;
+
+    fn hand_of(ranks: &[Rank]) -> Hand {
+        let mut hand = Hand::new();
+        for rank in ranks {
+            hand.add_card(Card::new(*rank, Suit::Diamonds));
+        }
+        hand
+    }
+
+    fn any_rank() -> impl Strategy<Value = Rank> {
+        (0..13usize).prop_map(|i| Rank::all()[i])
+    }
+
+    // Property: Banker always twists when hand value ≤ 16 and sticks when ≥ 17
+    proptest! {
+        #[test]
+        fn prop_banker_twists_on_16_sticks_on_17(ranks in prop::collection::vec(any_rank(), 2..6)) {
+            let hand = hand_of(&ranks);
+            let total = HandValue::of(&hand).total;
+            let action = StandOn::default().decide(&hand, &Rules::standard());
+            if total <= 16 {
+                prop_assert_eq!(action, Action::Twist);
+            } else {
+                prop_assert_eq!(action, Action::Stick);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hit_soft_17() {
+        let rules = Rules::standard();
+        assert_eq!(HitSoft17.decide(&hand_of(&[Rank::Ace, Rank::Six]), &rules), Action::Twist);
+        assert_eq!(HitSoft17.decide(&hand_of(&[Rank::Ten, Rank::Seven]), &rules), Action::Stick);
+    }
+
+    #[test]
+    fn test_house_banker_follows_the_rules() {
+        assert_eq!(StandOn::house(&Rules::standard()), StandOn::default());
+        let van_john = Rules::preset("van-john").unwrap();
+        let sixteen = hand_of(&[Rank::Ten, Rank::Six]);
+        assert_eq!(StandOn::house(&van_john).decide(&sixteen, &van_john), Action::Stick);
+    }
+
+    #[test]
+    fn test_banker_names_round_trip() {
+        assert_eq!(banker_by_name("stand-on-17").unwrap().name(), "stand-on-17");
+        assert_eq!(banker_by_name("hit-soft-17").unwrap().name(), "hit-soft-17");
+        assert!(banker_by_name("stand-on-5").is_none());
+    }
+}
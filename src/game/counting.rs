@@ -0,0 +1,334 @@
+use crate::game::event::{EventListener, GameEvent};
+use crate::models::card::{Card, Rank};
+
+/// A card-counting system: a tag for each rank and where the count starts
+pub trait CountingSystem: Send + Sync {
+    /// Short name used to select the system on the command line
+    fn name(&self) -> &'static str;
+
+    /// Count value of a rank
+    fn tag(&self, rank: Rank) -> i32;
+
+    /// Whether a full deck sums to zero; only balanced systems convert to a true count
+    fn is_balanced(&self) -> bool;
+
+    /// Running count at the start of a fresh shoe of `decks` decks
+    fn initial_running_count(&self, _decks: usize) -> i32 {
+        0
+    }
+}
+
+/// Hi-Lo: 2-6 count +1, 7-9 count 0, tens and Aces count -1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HiLo;
+
+impl CountingSystem for HiLo {
+    fn name(&self) -> &'static str {
+        "hi-lo"
+    }
+
+    fn tag(&self, rank: Rank) -> i32 {
+        match rank.base_value() {
+            2..=6 => 1,
+            7..=9 => 0,
+            _ => -1,
+        }
+    }
+
+    fn is_balanced(&self) -> bool {
+        true
+    }
+}
+
+/// Knock-Out: like Hi-Lo but 7 counts +1, so a deck sums to +4 and no true count is needed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KnockOut;
+
+impl CountingSystem for KnockOut {
+    fn name(&self) -> &'static str {
+        "ko"
+    }
+
+    fn tag(&self, rank: Rank) -> i32 {
+        match rank.base_value() {
+            2..=7 => 1,
+            8 | 9 => 0,
+            _ => -1,
+        }
+    }
+
+    fn is_balanced(&self) -> bool {
+        false
+    }
+
+    /// The standard starting count, which makes the pivot land on +4 whatever the shoe size
+    fn initial_running_count(&self, decks: usize) -> i32 {
+        4 - 4 * decks as i32
+    }
+}
+
+/// Omega II: a level-two balanced count with Aces counted as neutral
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OmegaII;
+
+impl CountingSystem for OmegaII {
+    fn name(&self) -> &'static str {
+        "omega-ii"
+    }
+
+    fn tag(&self, rank: Rank) -> i32 {
+        match rank.base_value() {
+            2 | 3 | 7 => 1,
+            4..=6 => 2,
+            9 => -1,
+            10 => -2,
+            _ => 0,
+        }
+    }
+
+    fn is_balanced(&self) -> bool {
+        true
+    }
+}
+
+/// Returns the built-in counting systems
+pub fn systems() -> Vec<Box<dyn CountingSystem>> {
+    vec![Box::new(HiLo), Box::new(KnockOut), Box::new(OmegaII)]
+}
+
+/// Looks up a built-in counting system by name
+pub fn system_by_name(name: &str) -> Option<Box<dyn CountingSystem>> {
+    systems().into_iter().find(|system| system.name() == name)
+}
+
+/// Keeps the running count for a shoe as cards are dealt
+///
+/// Cards can be passed to `observe` one at a time, or the counter can subscribe to a
+/// table and count every card it shows.
+pub struct Counter {
+    system: Box<dyn CountingSystem>,
+    decks: usize,
+    running_count: i32,
+    cards_seen: usize,
+    /// Cards counted since the current round began, still out of the shoe
+    round: Vec<Card>,
+}
+
+impl Counter {
+    /// Starts counting a fresh shoe of `decks` decks
+    pub fn new(system: Box<dyn CountingSystem>, decks: usize) -> Self {
+        let running_count = system.initial_running_count(decks);
+        Counter {
+            system,
+            decks,
+            running_count,
+            cards_seen: 0,
+            round: Vec::new(),
+        }
+    }
+
+    /// Returns the counting system in use
+    pub fn system(&self) -> &dyn CountingSystem {
+        self.system.as_ref()
+    }
+
+    /// Updates the count for a card that has been dealt
+    pub fn observe(&mut self, card: Card) {
+        self.running_count += self.system.tag(card.rank());
+        self.cards_seen += 1;
+        self.round.push(card);
+    }
+
+    /// Takes a card back out of the count, when it is returned to the shoe unplayed
+    fn forget(&mut self, card: Card) {
+        if let Some(index) = self.round.iter().rposition(|counted| *counted == card) {
+            self.round.remove(index);
+            self.running_count -= self.system.tag(card.rank());
+            self.cards_seen -= 1;
+        }
+    }
+
+    /// Starts again after the shoe is reshuffled
+    pub fn reset(&mut self) {
+        self.running_count = self.system.initial_running_count(self.decks);
+        self.cards_seen = 0;
+        self.round.clear();
+    }
+
+    /// Returns the running count
+    pub fn running_count(&self) -> i32 {
+        self.running_count
+    }
+
+    /// Returns the number of cards counted since the last reset
+    pub fn cards_seen(&self) -> usize {
+        self.cards_seen
+    }
+
+    /// Returns the running count per deck remaining, rounded to the nearest whole number
+    ///
+    /// `decks_remaining` comes from the shoe being counted (`Deck::decks_remaining`), so
+    /// decks of any size are allowed for. Unbalanced systems are played off the running
+    /// count, so they have no true count.
+    pub fn true_count(&self, decks_remaining: f64) -> Option<i32> {
+        if !self.system.is_balanced() {
+            return None;
+        }
+        // Never divide by less than half a deck, as counters do at the table
+        let decks = decks_remaining.max(0.5);
+        Some((f64::from(self.running_count) / decks).round() as i32)
+    }
+}
+
+impl EventListener for Counter {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::DeckReshuffled { .. } => self.reset(),
+            GameEvent::RoundStarted { .. } => self.round.clear(),
+            // Earlier rounds' cards are back in the shoe; this round's are still on the table
+            GameEvent::ShoeReplenished { .. } => {
+                let round = std::mem::take(&mut self.round);
+                self.reset();
+                round.into_iter().for_each(|card| self.observe(card));
+            }
+            GameEvent::CardDealt {
+                card,
+                face_up: true,
+                ..
+            }
+            | GameEvent::CardRevealed { card, .. } => self.observe(card),
+            // Cards put back by an undo are unseen again; a redo deals them once more
+            GameEvent::ActionUndone { ref returned, .. } => {
+                returned.iter().for_each(|card| self.forget(*card))
+            }
+            GameEvent::ActionRedone { ref dealt, .. } => {
+                dealt.iter().for_each(|card| self.observe(*card))
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::advisor::SeenCards;
+    use crate::game::round::Phase;
+    use crate::game::rules::{HandValue, Rules};
+    use crate::game::strategy::Action;
+    use crate::game::table::Table;
+    use crate::models::deck::Deck;
+    use crate::models::deck_type::DeckType;
+    use proptest::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    // Property: Balanced systems count back to zero over a whole shoe, KO to +4
+    proptest! {
+        #[test]
+        fn prop_full_shoe_returns_to_pivot(decks in 1usize..=8, seed in any::<u64>()) {
+            for system in systems() {
+                let balanced = system.is_balanced();
+                let mut counter = Counter::new(system, decks);
+                let mut shoe = Deck::new_shoe_seeded(decks, seed);
+                while let Ok(card) = shoe.deal() {
+                    counter.observe(card);
+                }
+                let expected = if balanced { 0 } else { 4 };
+                prop_assert_eq!(counter.running_count(), expected);
+                prop_assert_eq!(counter.cards_seen(), 52 * decks);
+            }
+        }
+    }
+
+    // Property: Reset returns the counter to its starting state
+    proptest! {
+        #[test]
+        fn prop_reset_restores_start(decks in 1usize..=8, deal in 0usize..52, seed in any::<u64>()) {
+            let mut counter = Counter::new(Box::new(KnockOut), decks);
+            let start = counter.running_count();
+            let mut shoe = Deck::new_shoe_seeded(decks, seed);
+            for _ in 0..deal {
+                counter.observe(shoe.deal().unwrap());
+            }
+            counter.reset();
+            prop_assert_eq!(counter.running_count(), start);
+            prop_assert_eq!(counter.cards_seen(), 0);
+        }
+    }
+
+    // Property: A counter subscribed to a table counts exactly the cards the player has seen
+    proptest! {
+        #[test]
+        fn prop_subscribed_counter_follows_the_table(
+            seed in any::<u64>(),
+            rounds in 1usize..30,
+            undos in prop::collection::vec(any::<bool>(), 8),
+        ) {
+            let counter = Arc::new(Mutex::new(Counter::new(Box::new(HiLo), 1)));
+            let seen = Arc::new(Mutex::new(SeenCards::default()));
+            let mut table = Table::new(Deck::new_seeded(seed), Rules::standard()).with_practice();
+            table.subscribe(Box::new(counter.clone()));
+            table.subscribe(Box::new(seen.clone()));
+            for _ in 0..rounds {
+                table.start_round(10).unwrap();
+                let mut undo = undos.iter().cycle();
+                while table.round().unwrap().phase() == Phase::PlayerTurn {
+                    let total = HandValue::of(table.round().unwrap().player_hand()).total;
+                    let action = if total >= 17 { Action::Stick } else { Action::Twist };
+                    table.act(action).unwrap();
+                    if *undo.next().unwrap() && table.undo() {
+                        table.redo();
+                    }
+                }
+                table.play_banker().unwrap();
+                let seen = seen.lock().unwrap();
+                let counter = counter.lock().unwrap();
+                let count: i32 = seen.cards().iter().map(|card| HiLo.tag(card.rank())).sum();
+                prop_assert_eq!(counter.running_count(), count);
+                prop_assert_eq!(counter.cards_seen(), seen.cards().len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_true_count_divides_by_decks_remaining() {
+        let mut counter = Counter::new(Box::new(HiLo), 6);
+        let mut shoe = Deck::new_shoe_seeded(6, 0);
+        for _ in 0..6 {
+            shoe.deal().unwrap();
+            counter.observe(Card::new(Rank::Five, crate::models::card::Suit::Clubs));
+        }
+        // 6 cards dealt from 312 leaves 5.88 decks: 6 / 5.88 rounds to 1
+        assert_eq!(counter.running_count(), 6);
+        assert_eq!(counter.true_count(shoe.decks_remaining()), Some(1));
+    }
+
+    #[test]
+    fn test_true_count_allows_for_short_decks() {
+        let mut counter = Counter::new(Box::new(HiLo), 2);
+        let mut shoe = Deck::of_type_seeded(&DeckType::spanish(), 2, 0);
+        for _ in 0..48 {
+            shoe.deal().unwrap();
+        }
+        for _ in 0..12 {
+            counter.observe(Card::new(Rank::Five, crate::models::card::Suit::Clubs));
+        }
+        // Half of two 48-card decks is one deck left, not the 1.08 of 52-card decks
+        assert_eq!(counter.true_count(shoe.decks_remaining()), Some(12));
+    }
+
+    #[test]
+    fn test_unbalanced_system_has_no_true_count() {
+        let counter = Counter::new(Box::new(KnockOut), 2);
+        assert_eq!(counter.running_count(), -4);
+        assert_eq!(counter.true_count(2.0), None);
+    }
+
+    #[test]
+    fn test_system_lookup() {
+        for name in ["hi-lo", "ko", "omega-ii"] {
+            assert_eq!(system_by_name(name).unwrap().name(), name);
+        }
+        assert!(system_by_name("zen").is_none());
+    }
+}
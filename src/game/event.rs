@@ -0,0 +1,354 @@
+use crate::game::round::{Phase, Seat};
+use crate::game::rules::{HandPlay, Settlement};
+use crate::game::strategy::Action;
+use crate::models::card::Card;
+use crate::models::hand::Hand;
+use std::sync::{Arc, Mutex};
+
+/// Something that happened at the table
+///
+/// The engine changes state only by emitting events, so replaying the same
+/// events always rebuilds the same state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The deck was gathered and shuffled; `cards` is how many it now holds
+    DeckReshuffled { cards: usize },
+    /// The deck ran out mid-round and `cards` discards were shuffled back in
+    ShoeReplenished { cards: usize },
+    /// A new round began with the player's stake; practice rounds allow undo and are not scored
+    RoundStarted { stake: u32, practice: bool },
+    /// A card was dealt to a seat, face up or face down
+    CardDealt {
+        seat: Seat,
+        card: Card,
+        face_up: bool,
+    },
+    /// It is now this seat's turn to act
+    TurnStarted { seat: Seat },
+    /// The player finished one hand of a split and moved on to the next
+    SplitHandStarted,
+    /// A seat made a decision
+    ActionTaken { seat: Seat, action: Action },
+    /// A face-down card was turned over
+    CardRevealed { seat: Seat, card: Card },
+    /// A seat's hand went over 21
+    HandBust { seat: Seat },
+    /// The round was settled
+    RoundSettled(Settlement),
+    /// A practice action was taken back; `returned` are the cards put back on the deck and
+    /// `rewound` every round event from the action on, which a fold takes back in reverse
+    ActionUndone {
+        seat: Seat,
+        action: Action,
+        returned: Vec<Card>,
+        rewound: Vec<GameEvent>,
+    },
+    /// An undone practice action was played again, dealing the same cards; `replayed` are
+    /// the round events it brings back, in order
+    ActionRedone {
+        seat: Seat,
+        action: Action,
+        dealt: Vec<Card>,
+        replayed: Vec<GameEvent>,
+    },
+}
+
+/// Receives events as the engine emits them
+pub trait EventListener: Send {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// Lets a listener be shared, so its owner can still read it after subscribing
+impl<L: EventListener> EventListener for Arc<Mutex<L>> {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.lock()
+            .expect("Event listener poisoned")
+            .on_event(event);
+    }
+}
+
+/// The state of one round, built purely by folding its events
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundState {
+    pub stake: u32,
+    pub practice: bool,
+    /// The hand the player is playing, or played last
+    pub player: Hand,
+    /// How `player` has been played so far
+    pub play: HandPlay,
+    /// Hands split off earlier and already played, in order
+    pub played: Vec<(Hand, HandPlay)>,
+    /// Hands split off and still to be played, next first
+    pub waiting: Vec<Hand>,
+    pub banker: Hand,
+    pub phase: Phase,
+    pub actions: Vec<(Seat, Action)>,
+    /// True while the banker has a card that has not been turned over
+    pub banker_hidden: bool,
+    pub settlement: Option<Settlement>,
+}
+
+impl RoundState {
+    /// Rebuilds a round's state from its events
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a GameEvent>) -> Self {
+        let mut state = RoundState::default();
+        for event in events {
+            state.apply(event);
+        }
+        state
+    }
+
+    /// Applies one event
+    pub fn apply(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::DeckReshuffled { .. } | GameEvent::ShoeReplenished { .. } => {}
+            GameEvent::ActionUndone { rewound, .. } => {
+                for event in rewound.iter().rev() {
+                    self.unapply(event);
+                }
+                // Only the player's actions are undone, and they are taken on the player's turn
+                self.phase = Phase::PlayerTurn;
+            }
+            GameEvent::ActionRedone { replayed, .. } => {
+                for event in replayed {
+                    self.apply(event);
+                }
+            }
+            GameEvent::RoundStarted { stake, practice } => {
+                *self = RoundState {
+                    stake: *stake,
+                    practice: *practice,
+                    ..RoundState::default()
+                }
+            }
+            GameEvent::CardDealt {
+                seat,
+                card,
+                face_up,
+            } => {
+                self.hand_mut(*seat).add_card(*card);
+                if *seat == Seat::Banker && !face_up {
+                    self.banker_hidden = true;
+                }
+            }
+            GameEvent::TurnStarted { seat } => {
+                self.phase = match seat {
+                    Seat::Player => Phase::PlayerTurn,
+                    Seat::Banker => Phase::BankerTurn,
+                }
+            }
+            GameEvent::SplitHandStarted => {
+                let next = self.waiting.remove(0);
+                let hand = std::mem::replace(&mut self.player, next);
+                self.played.push((hand, self.play));
+                self.play = HandPlay {
+                    split: true,
+                    ..HandPlay::default()
+                };
+            }
+            GameEvent::ActionTaken { seat, action } => {
+                self.actions.push((*seat, *action));
+                if *seat == Seat::Player {
+                    self.apply_player_action(*action);
+                }
+            }
+            GameEvent::CardRevealed {
+                seat: Seat::Banker, ..
+            } => self.banker_hidden = false,
+            GameEvent::CardRevealed { .. } | GameEvent::HandBust { .. } => {}
+            GameEvent::RoundSettled(settlement) => {
+                self.settlement = Some(*settlement);
+                self.phase = Phase::Finished;
+            }
+        }
+    }
+
+    /// Takes back one event that followed a player's action, undoing what `apply` did
+    fn unapply(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CardDealt { seat, face_up, .. } => {
+                self.hand_mut(*seat).take_last();
+                if *seat == Seat::Banker && !face_up {
+                    self.banker_hidden = false;
+                }
+            }
+            GameEvent::SplitHandStarted => {
+                let (hand, play) = self.played.pop().expect("A split hand was played before");
+                let next = std::mem::replace(&mut self.player, hand);
+                self.waiting.insert(0, next);
+                self.play = play;
+            }
+            GameEvent::ActionTaken { seat, action } => {
+                self.actions.pop();
+                if *seat == Seat::Player {
+                    self.unapply_player_action(*action);
+                }
+            }
+            GameEvent::CardRevealed {
+                seat: Seat::Banker, ..
+            } => self.banker_hidden = true,
+            GameEvent::RoundSettled(_) => self.settlement = None,
+            // The phase is put back once the whole action is undone
+            GameEvent::TurnStarted { .. }
+            | GameEvent::CardRevealed { .. }
+            | GameEvent::HandBust { .. }
+            | GameEvent::DeckReshuffled { .. }
+            | GameEvent::ShoeReplenished { .. }
+            | GameEvent::RoundStarted { .. }
+            | GameEvent::ActionUndone { .. }
+            | GameEvent::ActionRedone { .. } => {}
+        }
+    }
+
+    fn unapply_player_action(&mut self, action: Action) {
+        match action {
+            Action::Double => self.play.doubled = false,
+            Action::Surrender => self.play.surrendered = false,
+            Action::Split => {
+                let split_off = self.waiting.remove(0);
+                self.player.add_card(split_off.cards()[0]);
+                // The hand was itself a split hand only if there are others
+                self.play.split = !self.played.is_empty() || !self.waiting.is_empty();
+            }
+            Action::Twist | Action::Stick => {}
+        }
+    }
+
+    fn apply_player_action(&mut self, action: Action) {
+        match action {
+            Action::Double => self.play.doubled = true,
+            Action::Surrender => self.play.surrendered = true,
+            Action::Split => {
+                let (first, second) = (self.player.cards()[0], self.player.cards()[1]);
+                self.player.clear();
+                self.player.add_card(first);
+                let mut split_off = Hand::new();
+                split_off.add_card(second);
+                self.waiting.insert(0, split_off);
+                self.play.split = true;
+            }
+            Action::Twist | Action::Stick => {}
+        }
+    }
+
+    /// Returns every hand the player holds this round, with how each was played, in order
+    pub fn player_hands(&self) -> Vec<(&Hand, HandPlay)> {
+        let mut hands: Vec<(&Hand, HandPlay)> =
+            self.played.iter().map(|(hand, play)| (hand, *play)).collect();
+        hands.push((&self.player, self.play));
+        let waiting = HandPlay {
+            split: true,
+            ..HandPlay::default()
+        };
+        hands.extend(self.waiting.iter().map(|hand| (hand, waiting)));
+        hands
+    }
+
+    /// Returns the hand held by a seat
+    pub fn hand(&self, seat: Seat) -> &Hand {
+        match seat {
+            Seat::Player => &self.player,
+            Seat::Banker => &self.banker,
+        }
+    }
+
+    fn hand_mut(&mut self, seat: Seat) -> &mut Hand {
+        match seat {
+            Seat::Player => &mut self.player,
+            Seat::Banker => &mut self.banker,
+        }
+    }
+}
+
+impl Default for RoundState {
+    fn default() -> Self {
+        RoundState {
+            stake: 0,
+            practice: false,
+            player: Hand::new(),
+            play: HandPlay::default(),
+            played: Vec::new(),
+            waiting: Vec::new(),
+            banker: Hand::new(),
+            phase: Phase::Dealing,
+            actions: Vec::new(),
+            banker_hidden: false,
+            settlement: None,
+        }
+    }
+}
+
+/// A listener that keeps every event it receives
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventLog {
+    events: Vec<GameEvent>,
+}
+
+impl EventLog {
+    /// Returns every event received, oldest first
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+}
+
+impl EventListener for EventLog {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.events.push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::{Rank, Suit};
+
+    #[test]
+    fn test_round_started_clears_previous_round() {
+        let card = Card::new(Rank::Ace, Suit::Spades);
+        let events = [
+            GameEvent::RoundStarted {
+                stake: 5,
+                practice: true,
+            },
+            GameEvent::CardDealt {
+                seat: Seat::Player,
+                card,
+                face_up: true,
+            },
+            GameEvent::RoundStarted {
+                stake: 7,
+                practice: false,
+            },
+        ];
+        let state = RoundState::from_events(&events);
+        assert_eq!(state.stake, 7);
+        assert!(!state.practice);
+        assert_eq!(state.player.card_count(), 0);
+        assert_eq!(state.phase, Phase::Dealing);
+    }
+
+    #[test]
+    fn test_hole_card_hidden_until_revealed() {
+        let card = Card::new(Rank::Nine, Suit::Clubs);
+        let mut state = RoundState::default();
+        state.apply(&GameEvent::CardDealt {
+            seat: Seat::Banker,
+            card,
+            face_up: false,
+        });
+        assert!(state.banker_hidden);
+        state.apply(&GameEvent::CardRevealed {
+            seat: Seat::Banker,
+            card,
+        });
+        assert!(!state.banker_hidden);
+    }
+
+    #[test]
+    fn test_shared_listener_can_be_read_back() {
+        let log = Arc::new(Mutex::new(EventLog::default()));
+        let mut listener: Box<dyn EventListener> = Box::new(log.clone());
+        listener.on_event(&GameEvent::HandBust { seat: Seat::Player });
+        assert_eq!(log.lock().unwrap().events().len(), 1);
+    }
+}
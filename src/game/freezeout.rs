@@ -0,0 +1,543 @@
+use crate::error::{PontoonError, Result};
+use crate::game::banker::StandOn;
+use crate::game::rules::{settle, Rules, Settlement};
+use crate::game::shared::SharedRound;
+use crate::game::strategy::Action;
+use crate::game::timer::TurnLimit;
+use crate::game::view::{TableView, Viewer};
+use crate::models::deck::Deck;
+use std::cmp::Reverse;
+use std::fmt;
+
+/// Minimum stakes that rise as a tournament goes on, like the blinds in poker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeSchedule {
+    levels: Vec<u32>,
+    rounds_per_level: u32,
+}
+
+impl StakeSchedule {
+    /// Moves up one level every `rounds_per_level` rounds, staying on the last level
+    pub fn new(levels: Vec<u32>, rounds_per_level: u32) -> Result<Self> {
+        let invalid = |reason: &str| Err(PontoonError::InvalidConfig(reason.to_string()));
+        if levels.is_empty() {
+            return invalid("a schedule needs at least one level");
+        }
+        if levels.contains(&0) {
+            return invalid("minimum stakes must be at least 1");
+        }
+        if levels.windows(2).any(|pair| pair[1] < pair[0]) {
+            return invalid("minimum stakes cannot go down");
+        }
+        if rounds_per_level == 0 {
+            return invalid("each level must last at least one round");
+        }
+        Ok(StakeSchedule {
+            levels,
+            rounds_per_level,
+        })
+    }
+
+    pub fn levels(&self) -> &[u32] {
+        &self.levels
+    }
+
+    pub fn rounds_per_level(&self) -> u32 {
+        self.rounds_per_level
+    }
+
+    /// Returns the level, counting from 0, that round `round` (counting from 1) is played at
+    pub fn level(&self, round: u32) -> usize {
+        let level = round.saturating_sub(1) / self.rounds_per_level;
+        (level as usize).min(self.levels.len() - 1)
+    }
+
+    /// Returns the minimum stake for round `round`
+    pub fn minimum_stake(&self, round: u32) -> u32 {
+        self.levels[self.level(round)]
+    }
+}
+
+impl Default for StakeSchedule {
+    fn default() -> Self {
+        StakeSchedule {
+            levels: vec![10, 20, 30, 50, 75, 100, 150, 200, 300, 500],
+            rounds_per_level: 10,
+        }
+    }
+}
+
+impl fmt::Display for StakeSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<String> = self.levels.iter().map(u32::to_string).collect();
+        write!(f, "{} every {} rounds", levels.join(", "), self.rounds_per_level)
+    }
+}
+
+/// Settings for a freezeout tournament
+#[derive(Debug, Clone)]
+pub struct FreezeoutConfig {
+    pub rules: Rules,
+    /// Chips every player starts with
+    pub starting_chips: u32,
+    pub schedule: StakeSchedule,
+    pub decks: usize,
+    /// Seed for the shoe; None shuffles from entropy
+    pub seed: Option<u64>,
+    /// Stop after this many rounds even if several players are left
+    pub max_rounds: Option<u32>,
+    /// Time allowed for each of the player's decisions; None waits for ever
+    pub turn_limit: Option<TurnLimit>,
+}
+
+impl Default for FreezeoutConfig {
+    fn default() -> Self {
+        FreezeoutConfig {
+            rules: Rules::standard(),
+            starting_chips: 500,
+            schedule: StakeSchedule::default(),
+            decks: 1,
+            seed: None,
+            max_rounds: None,
+            turn_limit: None,
+        }
+    }
+}
+
+/// A player in a tournament
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub seat: usize,
+    pub name: String,
+    pub chips: u32,
+    /// The round the player went broke in
+    pub eliminated: Option<u32>,
+}
+
+/// How one seat fared in a round
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeatResult {
+    pub seat: usize,
+    /// Settled at the seat's own stake, which is less than the minimum when short of chips
+    pub settlement: Settlement,
+    pub chips: u32,
+    pub eliminated: bool,
+}
+
+/// A player's final place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    /// 1 for the winner; players who cannot be separated share a place
+    pub place: usize,
+    pub seat: usize,
+    pub name: String,
+    pub chips: u32,
+    pub eliminated: Option<u32>,
+}
+
+/// A tournament in which everyone starts with the same chips and plays until broke
+///
+/// Every round each player still in stakes the schedule's minimum, or all their chips
+/// if they have fewer. Players are out once they have no chips left; the tournament
+/// ends when one player is left or `max_rounds` have been played.
+pub struct Freezeout {
+    config: FreezeoutConfig,
+    deck: Deck,
+    entries: Vec<Entry>,
+    round: u32,
+}
+
+impl Freezeout {
+    /// Seats the named players at seats 1, 2, ... in order
+    ///
+    /// Fails with `InvalidConfig` if the rules have Blackjack options, which a shared table
+    /// does not offer, or `InsufficientFunds` if the starting chips do not cover the first
+    /// minimum stake.
+    pub fn new(config: FreezeoutConfig, names: &[String]) -> Result<Self> {
+        config.rules.check_shared()?;
+        let needed = config.schedule.minimum_stake(1);
+        if config.starting_chips < needed {
+            return Err(PontoonError::InsufficientFunds {
+                needed,
+                available: config.starting_chips,
+            });
+        }
+        let deck = match config.seed {
+            Some(seed) => Deck::new_shoe_seeded(config.decks, seed),
+            None => Deck::new_shoe(config.decks),
+        };
+        let entries = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Entry {
+                seat: index + 1,
+                name: name.clone(),
+                chips: config.starting_chips,
+                eliminated: None,
+            })
+            .collect();
+        Ok(Freezeout {
+            config,
+            deck,
+            entries,
+            round: 0,
+        })
+    }
+
+    pub fn config(&self) -> &FreezeoutConfig {
+        &self.config
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Returns the player at a seat
+    pub fn entry(&self, seat: usize) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.seat == seat)
+    }
+
+    /// Returns the number of rounds played so far
+    pub fn rounds_played(&self) -> u32 {
+        self.round
+    }
+
+    /// Returns the level, counting from 0, of the next round
+    pub fn level(&self) -> usize {
+        self.config.schedule.level(self.round + 1)
+    }
+
+    /// Returns the minimum stake for the next round
+    pub fn minimum_stake(&self) -> u32 {
+        self.config.schedule.minimum_stake(self.round + 1)
+    }
+
+    /// Returns the seats of the players still in, in seat order
+    pub fn active_seats(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.eliminated.is_none())
+            .map(|entry| entry.seat)
+            .collect()
+    }
+
+    /// Returns what a seat stakes on the next round: the minimum, or all-in when short
+    pub fn stake_for(&self, seat: usize) -> u32 {
+        self.entry(seat).map_or(0, |entry| entry.chips.min(self.minimum_stake()))
+    }
+
+    /// Returns true once one player is left or the round limit has been reached
+    pub fn is_over(&self) -> bool {
+        self.active_seats().len() <= 1
+            || self.config.max_rounds.is_some_and(|rounds| self.round >= rounds)
+    }
+
+    /// Plays one round, asking `decide` for each move of the seat whose turn it is
+    ///
+    /// `decide` is shown only that seat's view of the table. An illegal answer is
+    /// replaced by Stick if allowed, otherwise Twist.
+    pub fn play_round(
+        &mut self,
+        decide: &mut dyn FnMut(usize, &TableView) -> Action,
+    ) -> Result<Vec<SeatResult>> {
+        let mut round = self.deal()?;
+        while let Some(seat) = round.turn() {
+            let view = round.view(Viewer::Seat(seat));
+            let action = decide(seat, &view);
+            match round.apply(&mut self.deck, action) {
+                Err(PontoonError::IllegalAction { .. }) => {
+                    let fallback = if view.legal_actions.contains(&Action::Stick) {
+                        Action::Stick
+                    } else {
+                        Action::Twist
+                    };
+                    round.apply(&mut self.deck, fallback)?;
+                }
+                result => {
+                    result?;
+                }
+            }
+        }
+        round.play_banker(&mut self.deck, &StandOn::house(&self.config.rules))?;
+        Ok(self.settle(&round))
+    }
+
+    /// Deals the next round to every player still in, at the current minimum stake
+    ///
+    /// Fails with `InvalidConfig` once the tournament is over.
+    pub fn deal(&mut self) -> Result<SharedRound> {
+        if self.is_over() {
+            return Err(PontoonError::InvalidConfig("the tournament is over".to_string()));
+        }
+        let seats = self.active_seats();
+        if self.deck.needs_reshuffle() {
+            self.deck.reshuffle();
+        }
+        let stake = self.minimum_stake();
+        let round = SharedRound::deal(&mut self.deck, self.config.rules.clone(), stake, &seats)?;
+        self.round += 1;
+        Ok(round)
+    }
+
+    /// Pays out a round dealt by `deal` once the banker has played, knocking out broke players
+    pub fn settle(&mut self, round: &SharedRound) -> Vec<SeatResult> {
+        assert!(round.banker_revealed(), "The banker has not played");
+        let minimum = round.stake();
+        let rules = &self.config.rules;
+        let mut results = Vec::new();
+        for seat in round.seats() {
+            let hand = round.hand(seat).expect("The seat is in the round");
+            let Some(entry) = self.entries.iter_mut().find(|entry| entry.seat == seat) else {
+                continue;
+            };
+            let stake = entry.chips.min(minimum);
+            let mut settlement = settle(hand, round.banker_hand(), stake, rules);
+            // Some rules make a loss cost more than the stake, such as a banker's Pontoon
+            // collecting double, but a player can never lose more chips than they hold
+            settlement.net = settlement.net.max(-i64::from(entry.chips));
+            entry.chips = (i64::from(entry.chips) + settlement.net) as u32;
+            let eliminated = entry.chips == 0;
+            if eliminated {
+                entry.eliminated = Some(self.round);
+            }
+            results.push(SeatResult {
+                seat,
+                settlement,
+                chips: entry.chips,
+                eliminated,
+            });
+        }
+        results
+    }
+
+    /// Ranks the players: those still in by chips, then the rest by how long they lasted
+    pub fn standings(&self) -> Vec<Standing> {
+        let key = |entry: &Entry| {
+            (
+                Reverse(entry.eliminated.unwrap_or(u32::MAX)),
+                Reverse(entry.chips),
+            )
+        };
+        let mut entries = self.entries.clone();
+        entries.sort_by_key(|entry| (key(entry), entry.seat));
+        entries
+            .iter()
+            .map(|entry| Standing {
+                place: 1 + entries.iter().filter(|other| key(other) < key(entry)).count(),
+                seat: entry.seat,
+                name: entry.name.clone(),
+                chips: entry.chips,
+                eliminated: entry.eliminated,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::strategy::{strategy_by_name, PlayerStrategy};
+    use proptest::prelude::*;
+
+    fn strategies(names: &[&str]) -> Vec<Box<dyn PlayerStrategy>> {
+        names.iter().map(|name| strategy_by_name(name).unwrap()).collect()
+    }
+
+    /// Plays a whole tournament between built-in strategies
+    fn play_out(config: FreezeoutConfig, players: &[Box<dyn PlayerStrategy>]) -> Freezeout {
+        let names: Vec<String> = players.iter().map(|player| player.name()).collect();
+        let mut freezeout = Freezeout::new(config, &names).unwrap();
+        while !freezeout.is_over() {
+            freezeout
+                .play_round(&mut |seat, view| {
+                    view.with_situation(|situation| players[seat - 1].decide(situation))
+                        .expect("It is this seat's turn")
+                })
+                .unwrap();
+        }
+        freezeout
+    }
+
+    // Property: Chips move only by each seat's own settlement, and the broke stay out
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+        #[test]
+        fn prop_players_are_eliminated_when_broke(seed in any::<u64>(), players in 2usize..=5) {
+            let names: Vec<String> = (1..=players).map(|seat| format!("p{}", seat)).collect();
+            let config = FreezeoutConfig {
+                seed: Some(seed),
+                starting_chips: 100,
+                schedule: StakeSchedule::new(vec![10, 25, 50], 5).unwrap(),
+                max_rounds: Some(60),
+                ..FreezeoutConfig::default()
+            };
+            let mut freezeout = Freezeout::new(config, &names).unwrap();
+            while !freezeout.is_over() {
+                let before = freezeout.entries().to_vec();
+                let active = freezeout.active_seats();
+                let minimum = freezeout.minimum_stake();
+                let results = freezeout
+                    .play_round(&mut |_, view| {
+                        *view.legal_actions.last().expect("It is this seat's turn")
+                    })
+                    .unwrap();
+                prop_assert_eq!(results.iter().map(|r| r.seat).collect::<Vec<_>>(), active);
+                for result in &results {
+                    let was = &before[result.seat - 1];
+                    let stake = was.chips.min(minimum);
+                    prop_assert!(result.settlement.net >= -i64::from(stake));
+                    let chips = i64::from(was.chips) + result.settlement.net;
+                    prop_assert_eq!(i64::from(result.chips), chips);
+                    prop_assert_eq!(result.eliminated, result.chips == 0);
+                }
+                for (was, now) in before.iter().zip(freezeout.entries()) {
+                    if was.eliminated.is_some() {
+                        prop_assert_eq!(was, now);
+                    }
+                }
+            }
+            let standings = freezeout.standings();
+            prop_assert_eq!(standings.len(), players);
+            prop_assert_eq!(standings[0].place, 1);
+            prop_assert!(standings.windows(2).all(|pair| pair[0].place <= pair[1].place));
+        }
+    }
+
+    // Property: The minimum stake never falls, and holds at the last level
+    proptest! {
+        #[test]
+        fn prop_minimum_stake_rises(
+            mut levels in prop::collection::vec(1u32..1_000, 1..8),
+            every in 1u32..20,
+            round in 1u32..500
+        ) {
+            levels.sort_unstable();
+            let schedule = StakeSchedule::new(levels.clone(), every).unwrap();
+            prop_assert!(schedule.minimum_stake(round) <= schedule.minimum_stake(round + 1));
+            prop_assert_eq!(schedule.minimum_stake(1), levels[0]);
+            let last = every * levels.len() as u32;
+            prop_assert_eq!(schedule.minimum_stake(last + round), *levels.last().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_schedule_levels() {
+        let schedule = StakeSchedule::new(vec![10, 20, 40], 3).unwrap();
+        let stakes: Vec<u32> = (1..=10).map(|round| schedule.minimum_stake(round)).collect();
+        assert_eq!(stakes, vec![10, 10, 10, 20, 20, 20, 40, 40, 40, 40]);
+        assert_eq!(schedule.to_string(), "10, 20, 40 every 3 rounds");
+        assert!(StakeSchedule::new(vec![20, 10], 3).is_err());
+        assert!(StakeSchedule::new(vec![10], 0).is_err());
+        assert!(StakeSchedule::new(Vec::new(), 3).is_err());
+    }
+
+    #[test]
+    fn test_rules_with_blackjack_options_are_refused() {
+        for name in ["blackjack", "australian"] {
+            let config = FreezeoutConfig {
+                rules: Rules::preset(name).unwrap(),
+                ..FreezeoutConfig::default()
+            };
+            let entered = Freezeout::new(config, &["ann".to_string()]);
+            assert!(matches!(entered, Err(PontoonError::InvalidConfig(_))));
+        }
+    }
+
+    #[test]
+    fn test_starting_chips_must_cover_the_first_stake() {
+        let config = FreezeoutConfig {
+            starting_chips: 5,
+            ..FreezeoutConfig::default()
+        };
+        let entered = Freezeout::new(config, &["ann".to_string()]);
+        assert!(matches!(
+            entered,
+            Err(PontoonError::InsufficientFunds {
+                needed: 10,
+                available: 5
+            })
+        ));
+    }
+
+    #[test]
+    fn test_standings_rank_survivors_then_the_last_out() {
+        let names: Vec<String> = ["ann", "bo", "cy", "di"].iter().map(|n| n.to_string()).collect();
+        let mut freezeout = Freezeout::new(FreezeoutConfig::default(), &names).unwrap();
+        let chips = [(1, 0, Some(4)), (2, 300, None), (3, 0, Some(7)), (4, 0, Some(7))];
+        for (seat, left, out) in chips {
+            let entry = freezeout.entries.iter_mut().find(|e| e.seat == seat).unwrap();
+            entry.chips = left;
+            entry.eliminated = out;
+        }
+        let standings = freezeout.standings();
+        let order: Vec<(usize, &str)> =
+            standings.iter().map(|s| (s.place, s.name.as_str())).collect();
+        assert_eq!(order, vec![(1, "bo"), (2, "cy"), (2, "di"), (4, "ann")]);
+        assert!(freezeout.is_over());
+    }
+
+    #[test]
+    fn test_short_stack_goes_all_in() {
+        let names = vec!["short".to_string(), "deep".to_string()];
+        let config = FreezeoutConfig {
+            seed: Some(3),
+            ..FreezeoutConfig::default()
+        };
+        let mut freezeout = Freezeout::new(config, &names).unwrap();
+        freezeout.entries[0].chips = 4;
+        assert_eq!(freezeout.stake_for(1), 4);
+        assert_eq!(freezeout.stake_for(2), 10);
+        let results = freezeout.play_round(&mut |_, _| Action::Twist).unwrap();
+        assert!(results[0].settlement.net.abs() <= 8);
+    }
+
+    #[test]
+    fn test_loss_beyond_the_stake_is_capped_at_the_chips_held() {
+        let names = vec!["short".to_string(), "deep".to_string()];
+        let config = FreezeoutConfig {
+            rules: Rules::preset("vingt-et-un").unwrap(),
+            ..FreezeoutConfig::default()
+        };
+        let mut freezeout = Freezeout::new(config, &names).unwrap();
+        freezeout.entries[0].chips = 10;
+        freezeout.entries[1].chips = 30;
+        // Both players hold 19 against the banker's natural, which collects double
+        let cards = ["Th", "Tc", "Ah", "9h", "9c", "Kh"];
+        freezeout.deck =
+            Deck::stacked(cards.iter().map(|card| card.parse().unwrap()).collect(), 0);
+        let results = freezeout.play_round(&mut |_, _| Action::Stick).unwrap();
+        assert_eq!((results[0].settlement.net, results[0].chips), (-10, 0));
+        assert!(results[0].eliminated);
+        assert_eq!((results[1].settlement.net, results[1].chips), (-20, 10));
+    }
+
+    #[test]
+    fn test_strategies_play_a_tournament_to_the_end() {
+        let players = strategies(&["basic", "stick-on-15", "stick-on-19"]);
+        let config = FreezeoutConfig {
+            seed: Some(11),
+            starting_chips: 200,
+            max_rounds: Some(1_000),
+            ..FreezeoutConfig::default()
+        };
+        let freezeout = play_out(config, &players);
+        assert!(freezeout.is_over());
+        let standings = freezeout.standings();
+        assert_eq!(standings.len(), 3);
+        assert_eq!(standings[0].place, 1);
+        assert!(standings[0].chips >= standings[1].chips);
+    }
+
+    #[test]
+    fn test_no_round_is_dealt_once_the_tournament_is_over() {
+        let config = FreezeoutConfig {
+            seed: Some(2),
+            max_rounds: Some(1),
+            ..FreezeoutConfig::default()
+        };
+        let mut freezeout = play_out(config, &strategies(&["basic", "stick-on-17"]));
+        assert_eq!(freezeout.rounds_played(), 1);
+        assert!(matches!(freezeout.deal(), Err(PontoonError::InvalidConfig(_))));
+        assert_eq!(freezeout.rounds_played(), 1);
+    }
+}
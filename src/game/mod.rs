@@ -1 +1,13 @@
-// Game logic modules - coming in later tasks
+pub mod advisor;
+pub mod banker;
+pub mod counting;
+pub mod event;
+pub mod freezeout;
+pub mod round;
+pub mod rules;
+pub mod shared;
+pub mod stats;
+pub mod strategy;
+pub mod table;
+pub mod timer;
+pub mod view;
@@ -0,0 +1,682 @@
+use crate::error::{PontoonError, Result};
+use crate::game::banker::BankerStrategy;
+use crate::game::event::{GameEvent, RoundState};
+use crate::game::rules::{settle_hand, HandRank, HandValue, Outcome, Rules, Settlement};
+use crate::game::strategy::{Action, PlayerStrategy, Situation};
+use crate::game::view::{HandView, SeatView, TableView, Viewer, PLAYER_SEAT};
+use crate::models::card::{Card, Rank};
+use crate::models::deck::Deck;
+use crate::models::hand::Hand;
+
+/// Which part of the round is being played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Dealing,
+    PlayerTurn,
+    BankerTurn,
+    Finished,
+}
+
+/// Most hands a player may hold by splitting and splitting again
+pub const MAX_SPLIT_HANDS: usize = 4;
+
+/// Who is holding a hand at the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Seat {
+    Player,
+    Banker,
+}
+
+/// A single round between one player and the banker
+///
+/// Every change is made by emitting a [`GameEvent`] and folding it into the
+/// round's [`RoundState`], so `RoundState::from_events(round.events())` always
+/// equals `round.state()`.
+#[derive(Debug, Clone)]
+pub struct Round {
+    rules: Rules,
+    state: RoundState,
+    events: Vec<GameEvent>,
+}
+
+impl Round {
+    /// Starts a round by dealing two cards each, alternating player then banker
+    ///
+    /// The banker's second card is dealt face down. A deck that runs out is
+    /// replenished from its discards. Where the dealer peeks, a banker natural is
+    /// turned over at once and the player does not act.
+    pub fn deal(deck: &mut Deck, rules: Rules, stake: u32) -> Result<Self> {
+        Self::deal_round(deck, rules, stake, false)
+    }
+
+    /// Starts a practice round, which is dealt the same way but is not scored
+    pub fn deal_practice(deck: &mut Deck, rules: Rules, stake: u32) -> Result<Self> {
+        Self::deal_round(deck, rules, stake, true)
+    }
+
+    fn deal_round(deck: &mut Deck, rules: Rules, stake: u32, practice: bool) -> Result<Self> {
+        let mut round = Round {
+            rules,
+            state: RoundState::default(),
+            events: Vec::new(),
+        };
+        round.emit(GameEvent::RoundStarted { stake, practice });
+        for face_up in [true, false] {
+            let card = round.draw(deck)?;
+            round.emit(GameEvent::CardDealt {
+                seat: Seat::Player,
+                card,
+                face_up: true,
+            });
+            let card = round.draw(deck)?;
+            round.emit(GameEvent::CardDealt {
+                seat: Seat::Banker,
+                card,
+                face_up,
+            });
+        }
+        round.emit(GameEvent::TurnStarted { seat: Seat::Player });
+        let banker_natural = HandRank::of(round.banker_hand(), &round.rules) == HandRank::Pontoon;
+        if round.rules.dealer_peek && banker_natural {
+            round.start_banker_turn();
+        }
+        round.end_player_hand_if_done(deck)?;
+        Ok(round)
+    }
+
+    /// Returns the rules this round is played under
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Returns the player's stake
+    pub fn stake(&self) -> u32 {
+        self.state.stake
+    }
+
+    /// Returns the player's hand; after a split, the one being played or played last
+    pub fn player_hand(&self) -> &Hand {
+        &self.state.player
+    }
+
+    /// Returns every hand the player holds, more than one only after a split
+    pub fn player_hands(&self) -> Vec<&Hand> {
+        self.state.player_hands().into_iter().map(|(hand, _)| hand).collect()
+    }
+
+    /// Returns the banker's hand
+    pub fn banker_hand(&self) -> &Hand {
+        &self.state.banker
+    }
+
+    /// Returns the banker's face-up card
+    pub fn banker_upcard(&self) -> Card {
+        self.state.banker.cards()[0]
+    }
+
+    /// Returns true for a practice round
+    pub fn is_practice(&self) -> bool {
+        self.state.practice
+    }
+
+    /// Returns the current phase of the round
+    pub fn phase(&self) -> Phase {
+        self.state.phase
+    }
+
+    /// Returns every action taken so far, in order
+    pub fn actions(&self) -> &[(Seat, Action)] {
+        &self.state.actions
+    }
+
+    /// Returns the state built from this round's events
+    pub fn state(&self) -> &RoundState {
+        &self.state
+    }
+
+    /// Returns every event emitted so far, oldest first
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Returns what `viewer` may see of the round; the player sits at `PLAYER_SEAT`
+    pub fn view(&self, viewer: Viewer) -> TableView {
+        let turn = (self.phase() == Phase::PlayerTurn).then_some(PLAYER_SEAT);
+        let banker = if self.state.banker_hidden {
+            HandView::with_hole_card(self.banker_hand())
+        } else {
+            HandView::open(self.banker_hand())
+        };
+        TableView {
+            viewer,
+            rules: self.rules.clone(),
+            stake: self.stake(),
+            seats: vec![SeatView {
+                seat: PLAYER_SEAT,
+                hand: HandView::open(self.player_hand()),
+                hands: self.player_hands().into_iter().map(HandView::open).collect(),
+                settlement: self.state.settlement,
+            }],
+            banker,
+            turn,
+            legal_actions: if viewer == Viewer::Seat(PLAYER_SEAT) {
+                self.legal_actions()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Returns the actions the player may take now (empty once their turn is over)
+    ///
+    /// Doubling, splitting and surrendering are offered on two-card hands where the rules allow.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if self.phase() != Phase::PlayerTurn {
+            return Vec::new();
+        }
+        let hand = self.player_hand();
+        let mut actions = legal_actions_for(hand, &self.rules);
+        if hand.card_count() != 2 {
+            return actions;
+        }
+        let cards = hand.cards();
+        if self.rules.double_down {
+            actions.push(Action::Double);
+        }
+        let hands = self.state.played.len() + 1 + self.state.waiting.len();
+        if self.rules.split && cards[0].rank() == cards[1].rank() && hands < MAX_SPLIT_HANDS {
+            actions.push(Action::Split);
+        }
+        if self.rules.surrender && hands == 1 {
+            actions.push(Action::Surrender);
+        }
+        actions
+    }
+
+    /// Applies a player action, dealing from the deck on a Twist, Double or Split
+    ///
+    /// Changes nothing if the action is not legal.
+    pub fn apply(&mut self, deck: &mut Deck, action: Action) -> Result<()> {
+        let legal = self.legal_actions();
+        if !legal.contains(&action) {
+            return Err(PontoonError::IllegalAction { action, legal });
+        }
+        let card = match action {
+            Action::Twist | Action::Double | Action::Split => Some(self.draw(deck)?),
+            Action::Stick | Action::Surrender => None,
+        };
+        self.emit(GameEvent::ActionTaken {
+            seat: Seat::Player,
+            action,
+        });
+        match card {
+            Some(card) => self.deal_face_up(card, Seat::Player),
+            None => self.next_player_hand(deck)?,
+        }
+        self.end_player_hand_if_done(deck)
+    }
+
+    /// Plays out the banker's hand and settles; the banker does not draw when every player
+    /// hand is bust or surrendered
+    ///
+    /// Fails with `DeckExhausted` only if every card in the shoe is already in play.
+    pub fn play_banker(&mut self, deck: &mut Deck, strategy: &dyn BankerStrategy) -> Result<()> {
+        if self.phase() != Phase::BankerTurn {
+            return Ok(());
+        }
+        let live = self.state.player_hands().into_iter().any(|(hand, play)| {
+            !play.surrendered && HandRank::of(hand, &self.rules) != HandRank::Bust
+        });
+        if live {
+            while !hand_is_finished(self.banker_hand(), &self.rules) {
+                let action = strategy.decide(self.banker_hand(), &self.rules);
+                let card = match action {
+                    Action::Stick => None,
+                    _ => Some(self.draw(deck)?),
+                };
+                self.emit(GameEvent::ActionTaken {
+                    seat: Seat::Banker,
+                    action,
+                });
+                match card {
+                    Some(card) => self.deal_face_up(card, Seat::Banker),
+                    None => break,
+                }
+            }
+        }
+        let settlement = self.settle();
+        self.emit(GameEvent::RoundSettled(settlement));
+        Ok(())
+    }
+
+    /// Settles the round; only meaningful once the banker has played
+    ///
+    /// After a split the hands are settled together: the net is their total, the outcome
+    /// follows from it, and the player's rank is the first hand's.
+    pub fn settle(&self) -> Settlement {
+        let hands = self.hand_settlements();
+        if hands.len() == 1 {
+            return hands[0];
+        }
+        let net: i64 = hands.iter().map(|settlement| settlement.net).sum();
+        Settlement {
+            outcome: match net {
+                1.. => Outcome::Win,
+                0 => Outcome::Push,
+                _ => Outcome::Lose,
+            },
+            net,
+            ..hands[0]
+        }
+    }
+
+    /// Settles each of the player's hands on its own, in order
+    pub fn hand_settlements(&self) -> Vec<Settlement> {
+        self.state
+            .player_hands()
+            .into_iter()
+            .map(|(hand, play)| {
+                settle_hand(hand, play, self.banker_hand(), self.stake(), &self.rules)
+            })
+            .collect()
+    }
+
+    /// Records an event and folds it into the state
+    fn emit(&mut self, event: GameEvent) {
+        self.state.apply(&event);
+        self.events.push(event);
+    }
+
+    /// Takes the next card, first shuffling the discards back in if the deck is empty
+    fn draw(&mut self, deck: &mut Deck) -> Result<Card> {
+        if deck.cards_remaining() == 0 {
+            // Every split hand is still in play, not only the one being played
+            let in_play: Vec<Card> = self
+                .player_hands()
+                .into_iter()
+                .chain([self.banker_hand()])
+                .flat_map(|hand| hand.cards())
+                .copied()
+                .collect();
+            let cards = deck.replenish(&in_play);
+            if cards > 0 {
+                self.emit(GameEvent::ShoeReplenished { cards });
+            }
+        }
+        deck.deal()
+    }
+
+    fn deal_face_up(&mut self, card: Card, seat: Seat) {
+        self.emit(GameEvent::CardDealt {
+            seat,
+            card,
+            face_up: true,
+        });
+        if HandValue::of(self.state.hand(seat)).is_bust() {
+            self.emit(GameEvent::HandBust { seat });
+        }
+    }
+
+    /// Moves on once the player's hand can take no more cards, perhaps through several
+    /// split hands in a row
+    fn end_player_hand_if_done(&mut self, deck: &mut Deck) -> Result<()> {
+        while self.phase() == Phase::PlayerTurn && self.player_hand_is_finished() {
+            self.next_player_hand(deck)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the hand being played is over: as for any hand, or once doubled,
+    /// or once a split Ace has its second card
+    fn player_hand_is_finished(&self) -> bool {
+        let (hand, play) = (self.player_hand(), self.state.play);
+        hand_is_finished(hand, &self.rules)
+            || play.doubled
+            || play.surrendered
+            || (play.split && hand.cards()[0].rank() == Rank::Ace && hand.card_count() >= 2)
+    }
+
+    /// Moves to the player's next split hand, dealing its second card, or else to the banker
+    fn next_player_hand(&mut self, deck: &mut Deck) -> Result<()> {
+        if self.state.waiting.is_empty() {
+            self.start_banker_turn();
+            return Ok(());
+        }
+        let card = self.draw(deck)?;
+        self.emit(GameEvent::SplitHandStarted);
+        self.deal_face_up(card, Seat::Player);
+        Ok(())
+    }
+
+    /// Hands over to the banker, turning over the hole card
+    fn start_banker_turn(&mut self) {
+        self.emit(GameEvent::TurnStarted { seat: Seat::Banker });
+        let hole_card = self.banker_hand().cards()[1];
+        self.emit(GameEvent::CardRevealed {
+            seat: Seat::Banker,
+            card: hole_card,
+        });
+    }
+}
+
+/// Returns the actions open to a player whose turn it is: Twist always, Stick from `min_stick`
+pub fn legal_actions_for(hand: &Hand, rules: &Rules) -> Vec<Action> {
+    let mut actions = vec![Action::Twist];
+    if HandValue::of(hand).total >= rules.min_stick {
+        actions.push(Action::Stick);
+    }
+    actions
+}
+
+/// Returns true if a hand can take no more cards (bust, 21, Pontoon or the most cards allowed)
+pub fn hand_is_finished(hand: &Hand, rules: &Rules) -> bool {
+    let rank = HandRank::of(hand, rules);
+    matches!(rank, HandRank::Bust | HandRank::Pontoon | HandRank::FiveCardTrick)
+        || HandValue::of(hand).total == 21
+        || rules.max_cards.is_some_and(|max| hand.card_count() >= max)
+}
+
+/// Plays a whole round with the given strategies and returns how it settled
+pub fn play_round(
+    deck: &mut Deck,
+    rules: &Rules,
+    stake: u32,
+    player: &dyn PlayerStrategy,
+    banker: &dyn BankerStrategy,
+) -> Result<(Round, Settlement)> {
+    let mut round = Round::deal(deck, rules.clone(), stake)?;
+    while round.phase() == Phase::PlayerTurn {
+        let legal = round.legal_actions();
+        let action = player.decide(&Situation {
+            hand: round.player_hand(),
+            banker_upcard: round.banker_upcard(),
+            rules: round.rules(),
+            legal_actions: &legal,
+        });
+        match round.apply(deck, action) {
+            // Strategies are expected to choose from the legal actions; fall back to the first
+            Err(PontoonError::IllegalAction { .. }) => round.apply(deck, legal[0])?,
+            other => other?,
+        }
+    }
+    round.play_banker(deck, banker)?;
+    let settlement = round.settle();
+    Ok((round, settlement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::banker::StandOn;
+    use crate::game::rules::Outcome;
+    use crate::game::strategy::{BasicStrategy, StickOn};
+    use proptest::prelude::*;
+
+    // Property: A played round always finishes with sensible hands
+    proptest! {
+        #[test]
+        fn prop_round_finishes(seed in any::<u64>(), stick in 15u8..=21) {
+            let mut deck = Deck::new_seeded(seed);
+            let (round, settlement) = play_round(
+                &mut deck,
+                &Rules::standard(),
+                10,
+                &StickOn { total: stick },
+                &StandOn::default(),
+            )
+            .unwrap();
+            prop_assert_eq!(round.phase(), Phase::Finished);
+            prop_assert!(round.player_hand().card_count() <= 5);
+            prop_assert_eq!(
+                52 - deck.cards_remaining(),
+                round.player_hand().card_count() + round.banker_hand().card_count()
+            );
+            prop_assert!(settlement.net >= -10 && settlement.net <= 20);
+        }
+    }
+
+    // Property: The banker never draws against a bust player
+    proptest! {
+        #[test]
+        fn prop_banker_idle_when_player_busts(seed in any::<u64>()) {
+            let mut deck = Deck::new_seeded(seed);
+            let (round, settlement) = play_round(
+                &mut deck,
+                &Rules::standard(),
+                1,
+                &BasicStrategy,
+                &StandOn::default(),
+            )
+            .unwrap();
+            if settlement.player_rank == HandRank::Bust {
+                prop_assert_eq!(round.banker_hand().card_count(), 2);
+                prop_assert_eq!(settlement.outcome, Outcome::Lose);
+            }
+        }
+    }
+
+    // Property: Sticking below the minimum is refused
+    proptest! {
+        #[test]
+        fn prop_cannot_stick_below_minimum(seed in any::<u64>()) {
+            let mut deck = Deck::new_seeded(seed);
+            let mut round = Round::deal(&mut deck, Rules::standard(), 1).unwrap();
+            if round.phase() == Phase::PlayerTurn
+                && HandValue::of(round.player_hand()).total < 15
+            {
+                let refused = round.apply(&mut deck, Action::Stick);
+                let illegal = matches!(refused, Err(PontoonError::IllegalAction { .. }));
+                prop_assert!(illegal);
+                prop_assert_eq!(round.phase(), Phase::PlayerTurn);
+            }
+        }
+    }
+
+    // Property: Folding a round's events rebuilds exactly its state
+    proptest! {
+        #[test]
+        fn prop_state_is_fold_of_events(seed in any::<u64>(), stick in 15u8..=21) {
+            let mut deck = Deck::new_seeded(seed);
+            let (round, settlement) = play_round(
+                &mut deck,
+                &Rules::standard(),
+                10,
+                &StickOn { total: stick },
+                &StandOn::default(),
+            )
+            .unwrap();
+            let rebuilt = RoundState::from_events(round.events());
+            prop_assert_eq!(&rebuilt, round.state());
+            prop_assert_eq!(rebuilt.settlement, Some(settlement));
+            prop_assert!(!rebuilt.banker_hidden);
+        }
+    }
+
+    // Property: A bust is announced exactly when a hand goes over 21
+    proptest! {
+        #[test]
+        fn prop_bust_event_matches_rank(seed in any::<u64>()) {
+            let mut deck = Deck::new_seeded(seed);
+            let (round, settlement) = play_round(
+                &mut deck,
+                &Rules::standard(),
+                10,
+                &BasicStrategy,
+                &StandOn::default(),
+            )
+            .unwrap();
+            let player_bust = round.events().contains(&GameEvent::HandBust { seat: Seat::Player });
+            prop_assert_eq!(player_bust, settlement.player_rank == HandRank::Bust);
+            let banker_bust = round.events().contains(&GameEvent::HandBust { seat: Seat::Banker });
+            prop_assert_eq!(banker_bust, settlement.banker_rank == HandRank::Bust);
+        }
+    }
+
+    // Property: Blackjack rounds, splits and all, rebuild from their events and keep every card
+    proptest! {
+        #[test]
+        fn prop_blackjack_rounds_fold_and_keep_cards(seed in any::<u64>()) {
+            let mut deck = Deck::new_seeded(seed);
+            let rules = Rules::blackjack();
+            let (round, settlement) =
+                play_round(&mut deck, &rules, 10, &BasicStrategy, &StandOn::default()).unwrap();
+            prop_assert_eq!(RoundState::from_events(round.events()), round.state().clone());
+            let held: usize = round.player_hands().iter().map(|hand| hand.card_count()).sum();
+            prop_assert_eq!(52 - deck.cards_remaining(), held + round.banker_hand().card_count());
+            let total: i64 = round.hand_settlements().iter().map(|hand| hand.net).sum();
+            prop_assert_eq!(settlement.net, total);
+        }
+    }
+
+    fn stacked(notations: &[&str]) -> Deck {
+        let mut cards: Vec<Card> = notations.iter().map(|card| card.parse().unwrap()).collect();
+        // Fill the rest of the shoe with small cards so draws never run out
+        cards.extend(Deck::create_standard_deck().into_iter().filter(|c| c.base_value() == 2));
+        Deck::stacked(cards, 0)
+    }
+
+    #[test]
+    fn test_dealer_peeks_for_a_natural() {
+        // Deal order is player, banker, player, banker's hole card
+        let mut deck = stacked(&["9h", "Ah", "7c", "Kd"]);
+        let mut round = Round::deal(&mut deck, Rules::blackjack(), 10).unwrap();
+        assert_eq!(round.phase(), Phase::BankerTurn);
+        round.play_banker(&mut deck, &StandOn::default()).unwrap();
+        assert_eq!(round.settle().net, -10);
+
+        let mut deck = stacked(&["9h", "Ah", "7c", "Kd"]);
+        let round = Round::deal(&mut deck, Rules::standard(), 10).unwrap();
+        assert_eq!(round.phase(), Phase::PlayerTurn);
+    }
+
+    #[test]
+    fn test_split_then_double() {
+        let mut deck = stacked(&["8h", "9c", "8d", "7s", "3c", "Td", "Kc"]);
+        let mut round = Round::deal(&mut deck, Rules::blackjack(), 10).unwrap();
+        assert!(round.legal_actions().contains(&Action::Split));
+        round.apply(&mut deck, Action::Split).unwrap();
+        assert_eq!(round.player_hands().len(), 2);
+        // 8 and 3 make 11; doubling takes the ten and moves on to the second hand
+        round.apply(&mut deck, Action::Double).unwrap();
+        assert_eq!(round.player_hand().cards()[1], "Kc".parse().unwrap());
+        assert!(!round.legal_actions().contains(&Action::Surrender));
+        round.apply(&mut deck, Action::Stick).unwrap();
+        round.play_banker(&mut deck, &StandOn::default()).unwrap();
+        // The banker's 16 draws a two and stands on 18: 21 doubled wins 20, 18 pushes
+        let nets: Vec<i64> = round.hand_settlements().iter().map(|hand| hand.net).collect();
+        assert_eq!(nets, vec![20, 0]);
+        assert_eq!(round.settle().outcome, Outcome::Win);
+        let view = round.view(Viewer::Seat(PLAYER_SEAT));
+        let hands: Vec<usize> = view.seats[0].hands.iter().map(|hand| hand.cards().len()).collect();
+        assert_eq!(hands, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_replenishing_keeps_every_split_hand_out_of_the_shoe() {
+        let cards = ["Kc", "Qc", "8h", "9d", "8c", "8s", "2c"];
+        let mut deck = Deck::stacked(cards.iter().map(|card| card.parse().unwrap()).collect(), 0);
+        // The King and Queen go to the discards, so they are all a top-up can bring back
+        deck.deal().unwrap();
+        deck.deal().unwrap();
+        let mut round = Round::deal(&mut deck, Rules::blackjack(), 10).unwrap();
+        round.apply(&mut deck, Action::Split).unwrap();
+        round.apply(&mut deck, Action::Twist).unwrap();
+        assert!(round.events().contains(&GameEvent::ShoeReplenished { cards: 2 }));
+        let mut held: Vec<Card> =
+            round.player_hands().iter().flat_map(|hand| hand.cards()).copied().collect();
+        let count = held.len();
+        held.sort_by_key(Card::notation);
+        held.dedup();
+        assert_eq!(held.len(), count);
+    }
+
+    #[test]
+    fn test_surrender_loses_half_and_banker_stands_pat() {
+        let mut deck = stacked(&["Th", "Tc", "6d", "7s"]);
+        let mut round = Round::deal(&mut deck, Rules::blackjack(), 10).unwrap();
+        round.apply(&mut deck, Action::Surrender).unwrap();
+        round.play_banker(&mut deck, &StandOn::default()).unwrap();
+        assert_eq!(round.banker_hand().card_count(), 2);
+        assert_eq!(round.settle().net, -5);
+    }
+
+    #[test]
+    fn test_same_seed_same_round() {
+        let play = |seed| {
+            let mut deck = Deck::new_seeded(seed);
+            play_round(&mut deck, &Rules::standard(), 5, &BasicStrategy, &StandOn::default())
+                .unwrap()
+                .1
+        };
+        assert_eq!(play(42), play(42));
+    }
+
+    #[test]
+    fn test_short_deck_is_replenished_from_the_discards() {
+        let mut deck = Deck::new_seeded(3);
+        while deck.cards_remaining() > 3 {
+            deck.deal().unwrap();
+        }
+        let round = Round::deal(&mut deck, Rules::standard(), 10).unwrap();
+        assert_eq!(round.player_hand().cards().len() + round.banker_hand().cards().len(), 4);
+        assert!(round.events().contains(&GameEvent::ShoeReplenished { cards: 49 }));
+        assert_eq!(deck.cards_remaining(), 48);
+    }
+
+    // Property: Playing on from one deck without ever gathering it, no card is in two
+    // hands at once, split hands included, and every card is either in the shoe or dealt
+    proptest! {
+        #[test]
+        fn prop_replenishing_never_duplicates_or_loses_cards(
+            seed in any::<u64>(),
+            stick in 12u8..=21,
+            rounds in 1usize..40,
+            blackjack in any::<bool>(),
+        ) {
+            let mut deck = Deck::new_seeded(seed);
+            let mut replenished = 0;
+            // Basic strategy splits Aces and Eights under the Blackjack rules
+            let (rules, player): (Rules, Box<dyn PlayerStrategy>) = if blackjack {
+                (Rules::blackjack(), Box::new(BasicStrategy))
+            } else {
+                (Rules::standard(), Box::new(StickOn { total: stick }))
+            };
+            for _ in 0..rounds {
+                let (round, _) =
+                    play_round(&mut deck, &rules, 10, player.as_ref(), &StandOn::default())
+                        .unwrap();
+                let mut in_play: Vec<String> = round
+                    .player_hands()
+                    .into_iter()
+                    .chain([round.banker_hand()])
+                    .flat_map(|hand| hand.cards())
+                    .map(Card::notation)
+                    .collect();
+                let held = in_play.len();
+                in_play.sort();
+                in_play.dedup();
+                prop_assert_eq!(in_play.len(), held);
+
+                let mut every: Vec<String> = deck
+                    .cards_in_deal_order()
+                    .iter()
+                    .chain(deck.cards_dealt())
+                    .map(Card::notation)
+                    .collect();
+                every.sort();
+                every.dedup();
+                prop_assert_eq!(every.len(), 52);
+                prop_assert_eq!(deck.cards_remaining() + deck.cards_dealt().len(), 52);
+                replenished += round
+                    .events()
+                    .iter()
+                    .filter(|event| matches!(event, GameEvent::ShoeReplenished { .. }))
+                    .count();
+            }
+            // Rounds take at least four cards, so the shoe must have been topped up
+            if rounds > 13 {
+                prop_assert!(replenished > 0);
+            }
+        }
+    }
+}
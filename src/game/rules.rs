@@ -0,0 +1,752 @@
+use crate::error::PontoonError;
+use crate::models::card::Rank;
+use crate::models::hand::Hand;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// A payout ratio applied to the stake on a win (e.g. 2:1 pays twice the stake)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Payout {
+    numerator: u32,
+    denominator: u32,
+}
+
+impl Payout {
+    /// Creates a payout of `numerator`:`denominator`
+    pub fn new(numerator: u32, denominator: u32) -> Self {
+        assert!(denominator > 0, "Payout denominator must be positive");
+        Payout { numerator, denominator }
+    }
+
+    /// Creates a payout of `multiple`:1
+    pub fn to_one(multiple: u32) -> Self {
+        Self::new(multiple, 1)
+    }
+
+    /// Returns the winnings for the given stake, rounded down to whole chips
+    pub fn winnings(&self, stake: u32) -> i64 {
+        i64::from(stake) * i64::from(self.numerator) / i64::from(self.denominator)
+    }
+}
+
+impl fmt::Display for Payout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.numerator, self.denominator)
+    }
+}
+
+impl FromStr for Payout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected a payout like 3:2, found '{}'", s);
+        let (numerator, denominator) = s.split_once(':').ok_or_else(invalid)?;
+        let numerator = numerator.trim().parse().map_err(|_| invalid())?;
+        let denominator: u32 = denominator.trim().parse().map_err(|_| invalid())?;
+        if denominator == 0 {
+            return Err(invalid());
+        }
+        Ok(Payout::new(numerator, denominator))
+    }
+}
+
+/// The table rules that decide how hands are ranked and paid
+///
+/// The built-in presets are data, read from `presets.rules`, so a regional variant is a new
+/// block in that file rather than new code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Short name used to select the preset on the command line
+    pub name: &'static str,
+    /// One line on where the variant comes from and how it differs, shown in the CLI
+    pub description: &'static str,
+    /// Lowest total the player may stick on
+    pub min_stick: u8,
+    /// Special hands that beat every plain total, strongest first
+    pub hierarchy: Vec<HandRank>,
+    /// Whether equal hands go to the banker (otherwise they push)
+    pub ties_to_banker: bool,
+    /// Payout for winning with a Pontoon
+    pub pontoon_payout: Payout,
+    /// Payout for winning with a Five Card Trick
+    pub five_card_trick_payout: Payout,
+    /// Payout for any other winning hand
+    pub standard_payout: Payout,
+    /// What a player who has not bust loses to the banker's Pontoon, as a multiple of the stake
+    pub banker_pontoon_collects: Payout,
+    /// Total the house banker stands on
+    pub banker_stands_on: u8,
+    /// Most cards a hand may hold before its turn ends, if there is a limit
+    pub max_cards: Option<usize>,
+    /// Whether the banker checks for a natural before the player acts, ending the round at once
+    pub dealer_peek: bool,
+    /// Whether a two-card hand may be doubled down
+    pub double_down: bool,
+    /// Whether a pair may be split into two hands
+    pub split: bool,
+    /// Whether the player may give up their first two cards for half the stake
+    pub surrender: bool,
+}
+
+impl Rules {
+    /// Traditional British Pontoon: Pontoon and Five Card Trick pay 2:1, ties go to the banker
+    pub fn standard() -> Self {
+        Self::builtin("standard")
+    }
+
+    /// Standard rules without the Five Card Trick
+    pub fn no_five_card_trick() -> Self {
+        Self::builtin("no-trick")
+    }
+
+    /// Casino Blackjack on the same engine: the dealer peeks for a natural, naturals pay 3:2,
+    /// ties push, any total may stand, and the player may double, split and surrender
+    pub fn blackjack() -> Self {
+        Self::builtin("blackjack")
+    }
+
+    /// Returns all built-in rule presets
+    pub fn presets() -> Vec<Rules> {
+        static PRESETS: OnceLock<Vec<Rules>> = OnceLock::new();
+        PRESETS
+            .get_or_init(|| {
+                parse_presets(include_str!("presets.rules"))
+                    .unwrap_or_else(|error| panic!("Invalid presets.rules: {}", error))
+            })
+            .clone()
+    }
+
+    /// Looks up a built-in preset by name
+    pub fn preset(name: &str) -> Option<Rules> {
+        Self::presets().into_iter().find(|rules| rules.name == name)
+    }
+
+    fn builtin(name: &str) -> Self {
+        Self::preset(name).unwrap_or_else(|| panic!("presets.rules has no [{}] preset", name))
+    }
+
+    /// Returns true if the rules use a Blackjack option: a dealer peek, doubling, splitting or
+    /// surrender
+    pub fn has_blackjack_options(&self) -> bool {
+        self.dealer_peek || self.double_down || self.split || self.surrender
+    }
+
+    /// Fails unless a shared table can play these rules; shared tables offer only Twist and
+    /// Stick, so they refuse presets with Blackjack options
+    pub fn check_shared(&self) -> crate::error::Result<()> {
+        if !self.has_blackjack_options() {
+            return Ok(());
+        }
+        let shared: Vec<_> = Self::presets()
+            .into_iter()
+            .filter(|rules| !rules.has_blackjack_options())
+            .map(|rules| rules.name)
+            .collect();
+        Err(PontoonError::InvalidConfig(format!(
+            "the {} rules need a dealer peek, doubling, splitting or surrender, which shared \
+             tables do not offer; use one of {}",
+            self.name,
+            shared.join(", ")
+        )))
+    }
+
+    /// Returns true if five cards without busting make a Five Card Trick
+    pub fn five_card_trick(&self) -> bool {
+        self.hierarchy.contains(&HandRank::FiveCardTrick)
+    }
+
+    /// Orders two hand ranks by this variant's hierarchy: bust, then plain totals, then the
+    /// special hands from weakest to strongest
+    pub fn compare(&self, a: HandRank, b: HandRank) -> Ordering {
+        let strength = |rank: HandRank| match rank {
+            HandRank::Bust => (0, 0),
+            HandRank::Total(total) => (1, usize::from(total)),
+            special => match self.hierarchy.iter().position(|r| *r == special) {
+                Some(place) => (2, self.hierarchy.len() - place),
+                None => (1, 21),
+            },
+        };
+        strength(a).cmp(&strength(b))
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Reads preset blocks in the `presets.rules` format, the first of which sets every key
+fn parse_presets(text: &'static str) -> Result<Vec<Rules>, String> {
+    let mut presets: Vec<Rules> = Vec::new();
+    let mut keys: Vec<&str> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: String| format!("line {}: {}", number + 1, message);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            if presets.iter().any(|rules| rules.name == name) {
+                return Err(error(format!("preset [{}] is defined twice", name)));
+            }
+            if presets.len() == 1 && keys.len() < KEYS.len() {
+                return Err(error(format!("[{}] must set every key", presets[0].name)));
+            }
+            let base = presets.first().cloned().unwrap_or_else(|| unset(name));
+            presets.push(Rules { name, ..base });
+            keys.clear();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| error(format!("expected `key = value`, found '{}'", line)))?;
+        let rules = presets
+            .last_mut()
+            .ok_or_else(|| error("a key comes before the first [preset]".to_string()))?;
+        if keys.contains(&key) {
+            return Err(error(format!("{} is set twice", key)));
+        }
+        keys.push(key);
+        set_key(rules, key, value).map_err(error)?;
+    }
+    if presets.len() == 1 && keys.len() < KEYS.len() {
+        return Err(format!("[{}] must set every key", presets[0].name));
+    }
+    Ok(presets)
+}
+
+/// A preset with nothing set yet, for the first block to fill in
+fn unset(name: &'static str) -> Rules {
+    Rules {
+        name,
+        description: "",
+        min_stick: 0,
+        hierarchy: Vec::new(),
+        ties_to_banker: false,
+        pontoon_payout: Payout::to_one(1),
+        five_card_trick_payout: Payout::to_one(1),
+        standard_payout: Payout::to_one(1),
+        banker_pontoon_collects: Payout::to_one(1),
+        banker_stands_on: 17,
+        max_cards: None,
+        dealer_peek: false,
+        double_down: false,
+        split: false,
+        surrender: false,
+    }
+}
+
+/// Every key a preset may set
+const KEYS: [&str; 14] = [
+    "description",
+    "min_stick",
+    "hierarchy",
+    "ties",
+    "pontoon_payout",
+    "five_card_trick_payout",
+    "standard_payout",
+    "banker_pontoon_collects",
+    "banker_stands_on",
+    "max_cards",
+    "dealer_peek",
+    "double_down",
+    "split",
+    "surrender",
+];
+
+fn set_key(rules: &mut Rules, key: &str, value: &'static str) -> Result<(), String> {
+    fn number<T: FromStr>(value: &str) -> Result<T, String> {
+        value.parse().map_err(|_| format!("invalid number '{}'", value))
+    }
+    let on_off = |value: &str| match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected on or off for {}, found '{}'", key, value)),
+    };
+    match key {
+        "description" => rules.description = value,
+        "min_stick" => rules.min_stick = number(value)?,
+        "hierarchy" => {
+            rules.hierarchy = match value {
+                "none" => Vec::new(),
+                _ => value
+                    .split(',')
+                    .map(|name| special_hand(name.trim()))
+                    .collect::<Result<_, _>>()?,
+            }
+        }
+        "ties" => {
+            rules.ties_to_banker = match value {
+                "banker" => true,
+                "push" => false,
+                _ => return Err(format!("expected banker or push for ties, found '{}'", value)),
+            }
+        }
+        "pontoon_payout" => rules.pontoon_payout = value.parse()?,
+        "five_card_trick_payout" => rules.five_card_trick_payout = value.parse()?,
+        "standard_payout" => rules.standard_payout = value.parse()?,
+        "banker_pontoon_collects" => rules.banker_pontoon_collects = value.parse()?,
+        "banker_stands_on" => rules.banker_stands_on = number(value)?,
+        "max_cards" => {
+            rules.max_cards = match value {
+                "none" => None,
+                _ => Some(number(value)?),
+            }
+        }
+        "dealer_peek" => rules.dealer_peek = on_off(value)?,
+        "double_down" => rules.double_down = on_off(value)?,
+        "split" => rules.split = on_off(value)?,
+        "surrender" => rules.surrender = on_off(value)?,
+        _ => return Err(format!("unknown key '{}'", key)),
+    }
+    Ok(())
+}
+
+fn special_hand(name: &str) -> Result<HandRank, String> {
+    match name {
+        "pontoon" => Ok(HandRank::Pontoon),
+        "five-card-trick" => Ok(HandRank::FiveCardTrick),
+        _ => Err(format!("unknown special hand '{}'", name)),
+    }
+}
+
+/// The best total of a hand, counting one Ace as 11 where that does not bust
+///
+/// A Joker is wild: it stands in for whichever card, from Ace to ten, gives the best total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandValue {
+    pub total: u8,
+    /// True when an Ace or a Joker is counted above its lowest value, so another card
+    /// can lower it rather than bust the hand
+    pub soft: bool,
+}
+
+impl HandValue {
+    /// Computes the value of a hand
+    pub fn of(hand: &Hand) -> Self {
+        let hard: u32 = hand.cards().iter().map(|card| u32::from(card.base_value())).sum();
+        let has_ace = hand.cards().iter().any(|card| card.rank() == Rank::Ace);
+        let jokers = hand.cards().iter().filter(|card| card.rank().is_wild()).count() as u32;
+        let total = if jokers > 0 && hard <= 21 {
+            // Each Joker can add anything up to 10 over its base value of 1, so with an Ace's
+            // extra 10 on top every total up to the highest is reachable
+            (hard + 10 * (jokers + u32::from(has_ace))).min(21)
+        } else if has_ace && hard + 10 <= 21 {
+            hard + 10
+        } else {
+            hard
+        };
+        HandValue {
+            total: total.min(u32::from(u8::MAX)) as u8,
+            soft: total > hard,
+        }
+    }
+
+    /// Returns true if the total is over 21
+    pub fn is_bust(&self) -> bool {
+        self.total > 21
+    }
+}
+
+/// Where a finished hand sits in the Pontoon hierarchy
+///
+/// Variants are ordered weakest first as in standard Pontoon, so hands compare with `<` and
+/// `>`; `Rules::compare` orders them by a variant's own hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandRank {
+    Bust,
+    Total(u8),
+    FiveCardTrick,
+    Pontoon,
+}
+
+impl HandRank {
+    /// Ranks a hand under the given rules
+    pub fn of(hand: &Hand, rules: &Rules) -> Self {
+        let value = HandValue::of(hand);
+        if value.is_bust() {
+            HandRank::Bust
+        } else if hand.card_count() == 2
+            && value.total == 21
+            && rules.hierarchy.contains(&HandRank::Pontoon)
+        {
+            HandRank::Pontoon
+        } else if rules.five_card_trick() && hand.card_count() >= 5 {
+            HandRank::FiveCardTrick
+        } else {
+            HandRank::Total(value.total)
+        }
+    }
+}
+
+impl fmt::Display for HandRank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandRank::Bust => write!(f, "Bust"),
+            HandRank::Total(total) => write!(f, "{}", total),
+            HandRank::FiveCardTrick => write!(f, "Five Card Trick"),
+            HandRank::Pontoon => write!(f, "Pontoon"),
+        }
+    }
+}
+
+/// The result of a round from the player's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Win,
+    Lose,
+    Push,
+}
+
+/// How a round was settled between the player and the banker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settlement {
+    pub outcome: Outcome,
+    pub player_rank: HandRank,
+    pub banker_rank: HandRank,
+    /// Chips won (positive) or lost (negative) by the player
+    pub net: i64,
+}
+
+/// How the player staked and played one hand, beyond the cards in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HandPlay {
+    /// The stake on the hand was doubled
+    pub doubled: bool,
+    /// The hand was given up for half the stake
+    pub surrendered: bool,
+    /// The hand came from a split, so two cards making 21 count only as a total
+    pub split: bool,
+}
+
+/// Settles a player's hand against the banker's hand
+pub fn settle(player: &Hand, banker: &Hand, stake: u32, rules: &Rules) -> Settlement {
+    settle_hand(player, HandPlay::default(), banker, stake, rules)
+}
+
+/// Settles one of the player's hands, allowing for a doubled stake, a split or a surrender
+///
+/// A surrender loses half the stake, rounded against the player to whole chips.
+pub fn settle_hand(
+    player: &Hand,
+    play: HandPlay,
+    banker: &Hand,
+    stake: u32,
+    rules: &Rules,
+) -> Settlement {
+    let stake = if play.doubled { stake * 2 } else { stake };
+    let player_rank = match HandRank::of(player, rules) {
+        HandRank::Pontoon if play.split => HandRank::Total(21),
+        rank => rank,
+    };
+    let banker_rank = HandRank::of(banker, rules);
+    if play.surrendered {
+        return Settlement {
+            outcome: Outcome::Lose,
+            player_rank,
+            banker_rank,
+            net: -i64::from(stake.div_ceil(2)),
+        };
+    }
+
+    // A bust player loses even if the banker later busts too
+    let outcome = if player_rank == HandRank::Bust {
+        Outcome::Lose
+    } else if banker_rank == HandRank::Bust
+        || rules.compare(player_rank, banker_rank) == Ordering::Greater
+    {
+        Outcome::Win
+    } else if player_rank == banker_rank && !rules.ties_to_banker {
+        Outcome::Push
+    } else {
+        Outcome::Lose
+    };
+
+    let net = match outcome {
+        Outcome::Win => match player_rank {
+            HandRank::Pontoon => rules.pontoon_payout.winnings(stake),
+            HandRank::FiveCardTrick => rules.five_card_trick_payout.winnings(stake),
+            _ => rules.standard_payout.winnings(stake),
+        },
+        Outcome::Lose if banker_rank == HandRank::Pontoon && player_rank != HandRank::Bust => {
+            -rules.banker_pontoon_collects.winnings(stake)
+        }
+        Outcome::Lose => -i64::from(stake),
+        Outcome::Push => 0,
+    };
+
+    Settlement {
+        outcome,
+        player_rank,
+        banker_rank,
+        net,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::{Card, Suit};
+    use proptest::prelude::*;
+
+    fn hand_of(ranks: &[Rank]) -> Hand {
+        let mut hand = Hand::new();
+        for rank in ranks {
+            hand.add_card(Card::new(*rank, Suit::Hearts));
+        }
+        hand
+    }
+
+    fn any_rank() -> impl Strategy<Value = Rank> {
+        (0..13usize).prop_map(|i| Rank::all()[i])
+    }
+
+    // Property: Hand value never exceeds the hard total plus 10
+    proptest! {
+        #[test]
+        fn prop_value_at_most_hard_plus_ten(ranks in prop::collection::vec(any_rank(), 0..8)) {
+            let hand = hand_of(&ranks);
+            let hard: u32 = ranks.iter().map(|r| u32::from(r.base_value())).sum();
+            let value = HandValue::of(&hand);
+            prop_assert!(u32::from(value.total) == hard || u32::from(value.total) == hard + 10);
+        }
+    }
+
+    // Property: A Joker scores at least as well as any natural card in its place
+    proptest! {
+        #[test]
+        fn prop_joker_beats_any_replacement(
+            ranks in prop::collection::vec(any_rank(), 1..5),
+            replacement in any_rank(),
+        ) {
+            let mut with_joker = ranks.clone();
+            with_joker.push(Rank::Joker);
+            let mut with_card = ranks;
+            with_card.push(replacement);
+            let joker = HandValue::of(&hand_of(&with_joker));
+            let card = HandValue::of(&hand_of(&with_card));
+            prop_assert!(!joker.is_bust() || card.is_bust());
+            if !card.is_bust() {
+                prop_assert!(joker.total >= card.total);
+            }
+        }
+    }
+
+    // Property: A soft hand is never bust
+    proptest! {
+        #[test]
+        fn prop_soft_hand_never_bust(ranks in prop::collection::vec(any_rank(), 0..8)) {
+            let value = HandValue::of(&hand_of(&ranks));
+            if value.soft {
+                prop_assert!(!value.is_bust());
+            }
+        }
+    }
+
+    // Property: A bust player always loses the full stake
+    proptest! {
+        #[test]
+        fn prop_bust_player_loses(
+            banker in prop::collection::vec(any_rank(), 2..6),
+            stake in 1u32..1000
+        ) {
+            let player = hand_of(&[Rank::King, Rank::Queen, Rank::Two]);
+            let settlement = settle(&player, &hand_of(&banker), stake, &Rules::standard());
+            prop_assert_eq!(settlement.outcome, Outcome::Lose);
+            prop_assert_eq!(settlement.net, -i64::from(stake));
+        }
+    }
+
+    // Property: Under standard rules equal hands never push
+    proptest! {
+        #[test]
+        fn prop_ties_go_to_banker(ranks in prop::collection::vec(any_rank(), 2..5)) {
+            let hand = hand_of(&ranks);
+            let settlement = settle(&hand, &hand, 10, &Rules::standard());
+            prop_assert_eq!(settlement.outcome, Outcome::Lose);
+        }
+    }
+
+    // Property: Payouts print and parse back unchanged
+    proptest! {
+        #[test]
+        fn prop_payout_round_trips(numerator in 0u32..100, denominator in 1u32..100) {
+            let payout = Payout::new(numerator, denominator);
+            prop_assert_eq!(payout.to_string().parse::<Payout>(), Ok(payout));
+        }
+    }
+
+    // Property: Every preset's hierarchy is a total order consistent with its own settlements
+    proptest! {
+        #[test]
+        fn prop_settlement_follows_the_hierarchy(
+            player in prop::collection::vec(any_rank(), 2..6),
+            banker in prop::collection::vec(any_rank(), 2..6),
+            preset in 0..Rules::presets().len(),
+        ) {
+            let rules = &Rules::presets()[preset];
+            let (player, banker) = (hand_of(&player), hand_of(&banker));
+            let settlement = settle(&player, &banker, 10, rules);
+            let (player_rank, banker_rank) = (settlement.player_rank, settlement.banker_rank);
+            let order = rules.compare(player_rank, banker_rank);
+            prop_assert_eq!(order.reverse(), rules.compare(banker_rank, player_rank));
+            if player_rank != HandRank::Bust && banker_rank != HandRank::Bust {
+                prop_assert_eq!(settlement.outcome == Outcome::Win, order == Ordering::Greater);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ace_counts_as_eleven_when_safe() {
+        let value = HandValue::of(&hand_of(&[Rank::Ace, Rank::Six]));
+        assert_eq!(value, HandValue { total: 17, soft: true });
+    }
+
+    #[test]
+    fn test_ace_counts_as_one_when_eleven_would_bust() {
+        let value = HandValue::of(&hand_of(&[Rank::Ace, Rank::Six, Rank::Nine]));
+        assert_eq!(value, HandValue { total: 16, soft: false });
+    }
+
+    #[test]
+    fn test_jokers_are_wild() {
+        let joker_and_seven = HandValue::of(&hand_of(&[Rank::Joker, Rank::Seven]));
+        assert_eq!(joker_and_seven, HandValue { total: 18, soft: true });
+        let two_jokers = HandValue::of(&hand_of(&[Rank::Joker, Rank::Joker]));
+        assert_eq!(two_jokers.total, 21);
+        let filled_in = HandValue::of(&hand_of(&[Rank::King, Rank::Five, Rank::Joker]));
+        assert_eq!(filled_in, HandValue { total: 21, soft: true });
+        let no_room = HandValue::of(&hand_of(&[Rank::King, Rank::Queen, Rank::Joker]));
+        assert_eq!(no_room, HandValue { total: 21, soft: false });
+        let bust = HandValue::of(&hand_of(&[Rank::King, Rank::Queen, Rank::Two, Rank::Joker]));
+        assert!(bust.is_bust());
+        let pontoon = HandRank::of(&hand_of(&[Rank::Joker, Rank::Ten]), &Rules::standard());
+        assert_eq!(pontoon, HandRank::Pontoon);
+    }
+
+    #[test]
+    fn test_hand_hierarchy() {
+        let rules = Rules::standard();
+        let pontoon = HandRank::of(&hand_of(&[Rank::Ace, Rank::King]), &rules);
+        let trick = HandRank::of(&hand_of(&[Rank::Two, Rank::Two, Rank::Three, Rank::Three, Rank::Four]), &rules);
+        let twenty_one = HandRank::of(&hand_of(&[Rank::Seven, Rank::Seven, Rank::Seven]), &rules);
+
+        assert_eq!(pontoon, HandRank::Pontoon);
+        assert_eq!(trick, HandRank::FiveCardTrick);
+        assert_eq!(twenty_one, HandRank::Total(21));
+        assert!(pontoon > trick && trick > twenty_one && twenty_one > HandRank::Total(20));
+    }
+
+    #[test]
+    fn test_five_cards_without_trick_rule_is_a_total() {
+        let hand = hand_of(&[Rank::Two, Rank::Two, Rank::Three, Rank::Three, Rank::Four]);
+        assert_eq!(HandRank::of(&hand, &Rules::no_five_card_trick()), HandRank::Total(14));
+    }
+
+    #[test]
+    fn test_pontoon_pays_double() {
+        let player = hand_of(&[Rank::Ace, Rank::Jack]);
+        let banker = hand_of(&[Rank::King, Rank::Nine]);
+        let settlement = settle(&player, &banker, 10, &Rules::standard());
+        assert_eq!(settlement.outcome, Outcome::Win);
+        assert_eq!(settlement.net, 20);
+    }
+
+    #[test]
+    fn test_payout_rounds_down() {
+        assert_eq!(Payout::new(3, 2).winnings(5), 7);
+    }
+
+    #[test]
+    fn test_blackjack_settles_doubles_splits_and_surrenders() {
+        let rules = Rules::blackjack();
+        let banker = hand_of(&[Rank::King, Rank::Eight]);
+        let natural = hand_of(&[Rank::Ace, Rank::Jack]);
+        assert_eq!(settle(&natural, &banker, 10, &rules).net, 15);
+        let split = HandPlay { split: true, ..HandPlay::default() };
+        assert_eq!(settle_hand(&natural, split, &banker, 10, &rules).net, 10);
+        let doubled = HandPlay { doubled: true, ..HandPlay::default() };
+        let eighteen = hand_of(&[Rank::Nine, Rank::Nine]);
+        assert_eq!(settle_hand(&eighteen, doubled, &banker, 10, &rules).outcome, Outcome::Push);
+        let nineteen = hand_of(&[Rank::Nine, Rank::Ten]);
+        assert_eq!(settle_hand(&nineteen, doubled, &banker, 10, &rules).net, 20);
+        let surrendered = HandPlay { surrendered: true, ..HandPlay::default() };
+        let sixteen = hand_of(&[Rank::Ten, Rank::Six]);
+        assert_eq!(settle_hand(&sixteen, surrendered, &banker, 5, &rules).net, -3);
+    }
+
+    #[test]
+    fn test_preset_lookup() {
+        assert_eq!(Rules::preset("standard"), Some(Rules::standard()));
+        assert_eq!(Rules::preset("unknown"), None);
+    }
+
+    #[test]
+    fn test_every_preset_has_a_short_description() {
+        for rules in Rules::presets() {
+            assert!(!rules.description.is_empty(), "{} has no description", rules.name);
+            assert!(rules.description.len() <= 65, "{}'s description is too long", rules.name);
+        }
+    }
+
+    #[test]
+    fn test_van_john_puts_five_card_trick_above_pontoon() {
+        let pontoon = hand_of(&[Rank::Ace, Rank::King]);
+        let trick = hand_of(&[Rank::Two, Rank::Three, Rank::Two, Rank::Four, Rank::Five]);
+        let van_john = Rules::preset("van-john").unwrap();
+        assert_eq!(settle(&trick, &pontoon, 10, &van_john).net, 30);
+        assert_eq!(settle(&pontoon, &trick, 10, &Rules::standard()).net, 20);
+    }
+
+    #[test]
+    fn test_vingt_et_un_banker_natural_collects_double() {
+        let rules = Rules::preset("vingt-et-un").unwrap();
+        let natural = hand_of(&[Rank::Ace, Rank::Queen]);
+        assert_eq!(settle(&hand_of(&[Rank::Ten, Rank::Nine]), &natural, 10, &rules).net, -20);
+        let bust = hand_of(&[Rank::Ten, Rank::Six, Rank::Nine]);
+        assert_eq!(settle(&bust, &natural, 10, &rules).net, -10);
+        let five_cards = hand_of(&[Rank::Two, Rank::Three, Rank::Two, Rank::Four, Rank::Five]);
+        assert_eq!(HandRank::of(&five_cards, &rules), HandRank::Total(16));
+    }
+
+    #[test]
+    fn test_hierarchy_without_pontoon_scores_naturals_as_21() {
+        let rules = Rules {
+            hierarchy: Vec::new(),
+            ..Rules::standard()
+        };
+        assert_eq!(HandRank::of(&hand_of(&[Rank::Ace, Rank::Jack]), &rules), HandRank::Total(21));
+    }
+
+    /// Parses the built-in presets followed by `block`
+    fn parse_with(block: &str) -> Result<Vec<Rules>, String> {
+        let text = format!("{}{}", include_str!("presets.rules"), block);
+        parse_presets(Box::leak(text.into_boxed_str()))
+    }
+
+    #[test]
+    fn test_parse_presets_reports_the_bad_line() {
+        let mine = parse_with("[mine]\nbanker_stands_on = 16\n").unwrap();
+        assert_eq!(mine.last().unwrap().banker_stands_on, 16);
+        assert_eq!(mine.last().unwrap().min_stick, Rules::standard().min_stick);
+
+        let line = include_str!("presets.rules").lines().count() + 2;
+        let error = parse_with("[mine]\nsplits = on\n").unwrap_err();
+        assert_eq!(error, format!("line {}: unknown key 'splits'", line));
+        for (block, message) in [
+            ("[mine]\nties = dealer\n", "expected banker or push"),
+            ("[mine]\nsplit = on\nsplit = off\n", "split is set twice"),
+            ("[mine]\nhierarchy = pontoon, royal\n", "unknown special hand 'royal'"),
+            ("[mine]\npontoon_payout = 3:0\n", "expected a payout like 3:2"),
+            ("[standard]\n", "preset [standard] is defined twice"),
+        ] {
+            let error = parse_with(block).unwrap_err();
+            assert!(error.contains(message), "{} does not mention {}", error, message);
+        }
+        assert!(parse_presets("[partial]\nmin_stick = 15\n").unwrap_err().contains("every key"));
+    }
+}
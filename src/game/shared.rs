@@ -0,0 +1,375 @@
+use crate::error::{PontoonError, Result};
+use crate::game::banker::BankerStrategy;
+use crate::game::round::{hand_is_finished, legal_actions_for};
+use crate::game::rules::{settle, HandRank, Rules, Settlement};
+use crate::game::strategy::Action;
+use crate::game::view::{HandView, SeatView, TableView, Viewer};
+use crate::models::card::Card;
+use crate::models::deck::Deck;
+use crate::models::hand::Hand;
+
+/// A round in which several players each play their own hand against one banker
+///
+/// Players act one at a time in seat order; the banker plays once everyone is done.
+#[derive(Debug, Clone)]
+pub struct SharedRound {
+    rules: Rules,
+    stake: u32,
+    /// Seat number and hand of each player, in turn order
+    players: Vec<(usize, Hand)>,
+    banker: Hand,
+    /// Index into `players` of the player to act, or None once the players are done
+    turn: Option<usize>,
+    settlements: Option<Vec<Settlement>>,
+    /// Cards shuffled back in by each replenishment not yet taken by `take_replenished`
+    replenished: Vec<usize>,
+}
+
+impl SharedRound {
+    /// Deals two cards each to every seat and the banker; the banker's second card is face down
+    ///
+    /// A deck that runs out part way is replenished from its discards. Fails with
+    /// `InvalidConfig` for rules with Blackjack options, which a shared table does not offer.
+    pub fn deal(deck: &mut Deck, rules: Rules, stake: u32, seats: &[usize]) -> Result<Self> {
+        assert!(!seats.is_empty(), "A shared round needs at least one player");
+        rules.check_shared()?;
+        let mut round = SharedRound {
+            rules,
+            stake,
+            players: seats.iter().map(|seat| (*seat, Hand::new())).collect(),
+            banker: Hand::new(),
+            turn: Some(0),
+            settlements: None,
+            replenished: Vec::new(),
+        };
+        for _ in 0..2 {
+            for index in 0..round.players.len() {
+                let card = round.draw(deck)?;
+                round.players[index].1.add_card(card);
+            }
+            let card = round.draw(deck)?;
+            round.banker.add_card(card);
+        }
+        round.skip_finished_players();
+        Ok(round)
+    }
+
+    /// Returns the rules this round is played under
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Returns each player's stake
+    pub fn stake(&self) -> u32 {
+        self.stake
+    }
+
+    /// Returns the seats playing this round, in turn order
+    pub fn seats(&self) -> Vec<usize> {
+        self.players.iter().map(|(seat, _)| *seat).collect()
+    }
+
+    /// Returns the hand held at a seat, if it is playing this round
+    pub fn hand(&self, seat: usize) -> Option<&Hand> {
+        self.players.iter().find(|(s, _)| *s == seat).map(|(_, hand)| hand)
+    }
+
+    /// Returns the banker's hand, including the face-down card
+    pub fn banker_hand(&self) -> &Hand {
+        &self.banker
+    }
+
+    /// Returns the banker's face-up card
+    pub fn banker_upcard(&self) -> Card {
+        self.banker.cards()[0]
+    }
+
+    /// Returns true once the banker's face-down card has been turned over
+    pub fn banker_revealed(&self) -> bool {
+        self.settlements.is_some()
+    }
+
+    /// Returns the seat whose turn it is, or None once every player is done
+    pub fn turn(&self) -> Option<usize> {
+        self.turn.map(|index| self.players[index].0)
+    }
+
+    /// Returns the actions the player to act may take
+    pub fn legal_actions(&self) -> Vec<Action> {
+        match self.turn {
+            Some(index) => legal_actions_for(&self.players[index].1, &self.rules),
+            None => Vec::new(),
+        }
+    }
+
+    /// Applies an action for the player to act; returns the card dealt on a Twist
+    ///
+    /// Changes nothing if the action is not legal.
+    pub fn apply(&mut self, deck: &mut Deck, action: Action) -> Result<Option<Card>> {
+        let legal = self.legal_actions();
+        let Some(index) = self.turn.filter(|_| legal.contains(&action)) else {
+            return Err(PontoonError::IllegalAction { action, legal });
+        };
+        match action {
+            Action::Twist => {
+                let card = self.draw(deck)?;
+                self.players[index].1.add_card(card);
+                self.skip_finished_players();
+                Ok(Some(card))
+            }
+            Action::Stick => {
+                self.stand_down();
+                Ok(None)
+            }
+            Action::Double | Action::Split | Action::Surrender => {
+                unreachable!("a shared table only offers Twist and Stick")
+            }
+        }
+    }
+
+    /// Ends the current player's turn whatever their total, e.g. when they leave the table
+    pub fn stand_down(&mut self) {
+        if let Some(index) = self.turn {
+            self.turn = Some(index + 1);
+            self.skip_finished_players();
+        }
+    }
+
+    /// Reveals the banker's card, plays the banker's hand and settles every player
+    ///
+    /// The banker does not draw when every player has bust. Does nothing until all
+    /// players are done. Fails with `DeckExhausted` only if every card in the shoe is
+    /// already in play.
+    pub fn play_banker(
+        &mut self,
+        deck: &mut Deck,
+        strategy: &dyn BankerStrategy,
+    ) -> Result<Vec<Card>> {
+        if self.turn.is_some() || self.settlements.is_some() {
+            return Ok(Vec::new());
+        }
+        let mut drawn = Vec::new();
+        let all_bust = self
+            .players
+            .iter()
+            .all(|(_, hand)| HandRank::of(hand, &self.rules) == HandRank::Bust);
+        if !all_bust {
+            while !hand_is_finished(&self.banker, &self.rules)
+                && strategy.decide(&self.banker, &self.rules) == Action::Twist
+            {
+                let card = self.draw(deck)?;
+                self.banker.add_card(card);
+                drawn.push(card);
+            }
+        }
+        self.settlements = Some(
+            self.players
+                .iter()
+                .map(|(_, hand)| settle(hand, &self.banker, self.stake, &self.rules))
+                .collect(),
+        );
+        Ok(drawn)
+    }
+
+    /// Returns how many cards each replenishment since the last call shuffled back in
+    ///
+    /// The deck is replenished from its discards whenever it runs out mid-round, so
+    /// a table can tell its players before showing the next card.
+    pub fn take_replenished(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.replenished)
+    }
+
+    /// Returns each seat's settlement once the banker has played
+    pub fn settlements(&self) -> Option<Vec<(usize, Settlement)>> {
+        let settlements = self.settlements.as_ref()?;
+        Some(self.seats().into_iter().zip(settlements.iter().copied()).collect())
+    }
+
+    /// Returns what `viewer` may see; the hole card stays face down until the banker plays
+    pub fn view(&self, viewer: Viewer) -> TableView {
+        let banker = if self.banker_revealed() {
+            HandView::open(&self.banker)
+        } else {
+            HandView::with_hole_card(&self.banker)
+        };
+        let settlements = self.settlements.as_ref();
+        TableView {
+            viewer,
+            rules: self.rules.clone(),
+            stake: self.stake,
+            seats: self
+                .players
+                .iter()
+                .enumerate()
+                .map(|(index, (seat, hand))| SeatView {
+                    seat: *seat,
+                    hand: HandView::open(hand),
+                    hands: vec![HandView::open(hand)],
+                    settlement: settlements.map(|settlements| settlements[index]),
+                })
+                .collect(),
+            banker,
+            turn: self.turn(),
+            legal_actions: match viewer {
+                Viewer::Seat(seat) if Some(seat) == self.turn() => self.legal_actions(),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    /// Takes the next card, first shuffling the discards back in if the deck is empty
+    fn draw(&mut self, deck: &mut Deck) -> Result<Card> {
+        if deck.cards_remaining() == 0 {
+            let mut in_play: Vec<Card> = self.banker.cards().to_vec();
+            for (_, hand) in &self.players {
+                in_play.extend_from_slice(hand.cards());
+            }
+            let cards = deck.replenish(&in_play);
+            if cards > 0 {
+                self.replenished.push(cards);
+            }
+        }
+        deck.deal()
+    }
+
+    /// Moves the turn past players whose hands can take no more cards
+    fn skip_finished_players(&mut self) {
+        while let Some(index) = self.turn {
+            if index >= self.players.len() {
+                self.turn = None;
+            } else if hand_is_finished(&self.players[index].1, &self.rules) {
+                self.turn = Some(index + 1);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::banker::StandOn;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    /// Plays a shared round with every seat sticking as soon as it may
+    fn play(seed: u64, players: usize) -> (SharedRound, Deck) {
+        let mut deck = Deck::new_seeded(seed);
+        let seats: Vec<usize> = (0..players).collect();
+        let mut round = SharedRound::deal(&mut deck, Rules::standard(), 10, &seats).unwrap();
+        while round.turn().is_some() {
+            let action = if round.legal_actions().contains(&Action::Stick) {
+                Action::Stick
+            } else {
+                Action::Twist
+            };
+            round.apply(&mut deck, action).unwrap();
+        }
+        round.play_banker(&mut deck, &StandOn::default()).unwrap();
+        (round, deck)
+    }
+
+    // Property: Every card at the table is distinct and came off the deck
+    proptest! {
+        #[test]
+        fn prop_cards_are_distinct(seed in any::<u64>(), players in 1usize..=6) {
+            let (round, deck) = play(seed, players);
+            let mut cards: Vec<Card> = round.banker_hand().cards().to_vec();
+            for seat in round.seats() {
+                cards.extend_from_slice(round.hand(seat).unwrap().cards());
+            }
+            let distinct: HashSet<String> = cards.iter().map(Card::notation).collect();
+            prop_assert_eq!(distinct.len(), cards.len());
+            prop_assert_eq!(cards.len() + deck.cards_remaining(), 52);
+        }
+    }
+
+    // Property: A full table played on from one deck, never gathered between rounds, has
+    // every card either in the shoe or dealt exactly once, and no card in two hands
+    proptest! {
+        #[test]
+        fn prop_replenished_shoe_keeps_every_card_once(
+            seed in any::<u64>(),
+            players in 1usize..=6,
+            twists in 0usize..4,
+            rounds in 1usize..12
+        ) {
+            let mut deck = Deck::new_seeded(seed);
+            let seats: Vec<usize> = (1..=players).collect();
+            let mut replenished = 0;
+            for _ in 0..rounds {
+                let mut round =
+                    SharedRound::deal(&mut deck, Rules::standard(), 10, &seats).unwrap();
+                while let Some(seat) = round.turn() {
+                    let twisted = round.hand(seat).unwrap().cards().len() - 2;
+                    let action = if twisted < twists {
+                        Action::Twist
+                    } else {
+                        *round.legal_actions().last().unwrap()
+                    };
+                    round.apply(&mut deck, action).unwrap();
+                }
+                round.play_banker(&mut deck, &StandOn::default()).unwrap();
+                replenished += round.take_replenished().len();
+                prop_assert!(round.take_replenished().is_empty());
+
+                let mut cards: Vec<Card> = round.banker_hand().cards().to_vec();
+                for seat in round.seats() {
+                    cards.extend_from_slice(round.hand(seat).unwrap().cards());
+                }
+                let distinct: HashSet<String> = cards.iter().map(Card::notation).collect();
+                prop_assert_eq!(distinct.len(), cards.len());
+                let every: HashSet<String> = deck
+                    .cards_in_deal_order()
+                    .iter()
+                    .chain(deck.cards_dealt())
+                    .map(Card::notation)
+                    .collect();
+                prop_assert_eq!(every.len(), 52);
+                prop_assert_eq!(deck.cards_remaining() + deck.cards_dealt().len(), 52);
+            }
+            // Every round takes at least two cards a seat, so a long game must top up
+            if 2 * (players + 1) * rounds > 52 {
+                prop_assert!(replenished > 0);
+            }
+        }
+    }
+
+    // Property: Each seat is settled exactly as a one-player round would settle its hand
+    proptest! {
+        #[test]
+        fn prop_each_seat_settles_against_the_banker(seed in any::<u64>(), players in 1usize..=6) {
+            let (round, _) = play(seed, players);
+            let settlements = round.settlements().unwrap();
+            prop_assert_eq!(settlements.len(), players);
+            for (seat, settlement) in settlements {
+                let hand = round.hand(seat).unwrap();
+                let expected = settle(hand, round.banker_hand(), 10, round.rules());
+                prop_assert_eq!(settlement, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_tables_refuse_blackjack_options() {
+        let mut deck = Deck::new_seeded(1);
+        let dealt = SharedRound::deal(&mut deck, Rules::blackjack(), 10, &[1, 2]);
+        assert!(matches!(dealt, Err(PontoonError::InvalidConfig(_))));
+        assert!(Rules::preset("van-john").unwrap().check_shared().is_ok());
+    }
+
+    #[test]
+    fn test_cannot_act_out_of_turn_or_illegally() {
+        let mut deck = Deck::new_seeded(12);
+        let mut round = SharedRound::deal(&mut deck, Rules::standard(), 10, &[3, 5]).unwrap();
+        round.stand_down();
+        round.stand_down();
+        assert_eq!(round.turn(), None);
+        let refused = round.apply(&mut deck, Action::Twist);
+        assert!(matches!(refused, Err(PontoonError::IllegalAction { .. })));
+        assert!(!round.banker_revealed());
+        round.play_banker(&mut deck, &StandOn::default()).unwrap();
+        assert!(round.banker_revealed());
+    }
+}
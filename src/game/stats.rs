@@ -0,0 +1,122 @@
+use crate::game::event::{EventListener, GameEvent};
+use crate::game::round::Seat;
+use crate::game::rules::{HandRank, Outcome};
+
+/// Running totals for a playing session, kept up to date from the event stream
+///
+/// Practice rounds are counted on their own and never touch the scored totals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Scored rounds settled
+    pub rounds: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub pushes: u32,
+    /// The player's total winnings, negative when down
+    pub net: i64,
+    pub pontoons: u32,
+    pub five_card_tricks: u32,
+    /// Rounds in which the player went bust
+    pub player_busts: u32,
+    /// Rounds in which the banker went bust
+    pub banker_busts: u32,
+    pub reshuffles: u32,
+    /// Times the deck ran out mid-round and was topped up from the discards
+    pub replenishments: u32,
+    /// Practice hands settled; each line tried after an undo counts again
+    pub practice_hands: u32,
+    pub undos: u32,
+    pub redos: u32,
+    /// True while the round in progress is a practice round
+    in_practice: bool,
+}
+
+impl EventListener for SessionStats {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::DeckReshuffled { .. } => self.reshuffles += 1,
+            GameEvent::ShoeReplenished { .. } => self.replenishments += 1,
+            GameEvent::RoundStarted { practice, .. } => self.in_practice = *practice,
+            GameEvent::ActionUndone { .. } => self.undos += 1,
+            GameEvent::ActionRedone { .. } => self.redos += 1,
+            GameEvent::RoundSettled(_) if self.in_practice => self.practice_hands += 1,
+            _ if self.in_practice => {}
+            GameEvent::HandBust { seat: Seat::Player } => self.player_busts += 1,
+            GameEvent::HandBust { seat: Seat::Banker } => self.banker_busts += 1,
+            GameEvent::RoundSettled(settlement) => {
+                self.rounds += 1;
+                self.net += settlement.net;
+                match settlement.outcome {
+                    Outcome::Win => self.wins += 1,
+                    Outcome::Lose => self.losses += 1,
+                    Outcome::Push => self.pushes += 1,
+                }
+                match settlement.player_rank {
+                    HandRank::Pontoon => self.pontoons += 1,
+                    HandRank::FiveCardTrick => self.five_card_tricks += 1,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::round::Phase;
+    use crate::game::rules::Rules;
+    use crate::game::strategy::Action;
+    use crate::game::table::Table;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_practice_rounds_are_not_scored() {
+        let stats = Arc::new(Mutex::new(SessionStats::default()));
+        let mut table = Table::new(Deck::new_seeded(6), Rules::standard()).with_practice();
+        table.subscribe(Box::new(stats.clone()));
+        table.start_round(10).unwrap();
+        while table.round().unwrap().phase() == Phase::PlayerTurn {
+            table.act(Action::Twist).unwrap();
+        }
+        table.play_banker().unwrap();
+        table.undo();
+        table.redo();
+
+        let stats = stats.lock().unwrap();
+        assert_eq!(stats.rounds, 0);
+        assert_eq!(stats.net, 0);
+        assert_eq!(stats.player_busts, 0);
+        assert_eq!((stats.practice_hands, stats.undos, stats.redos), (1, 1, 1));
+    }
+
+    // Property: Every settled round is counted once, and the net matches the settlements
+    proptest! {
+        #[test]
+        fn prop_stats_add_up(seed in any::<u64>(), rounds in 1u32..20) {
+            let stats = Arc::new(Mutex::new(SessionStats::default()));
+            let mut table = Table::new(Deck::new_seeded(seed), Rules::standard());
+            table.subscribe(Box::new(stats.clone()));
+
+            let mut net = 0;
+            for _ in 0..rounds {
+                table.start_round(10).unwrap();
+                while table.round().unwrap().phase() == Phase::PlayerTurn {
+                    if table.act(Action::Stick).is_err() {
+                        table.act(Action::Twist).unwrap();
+                    }
+                }
+                table.play_banker().unwrap();
+                net += table.round().unwrap().settle().net;
+            }
+
+            let stats = stats.lock().unwrap();
+            prop_assert_eq!(stats.rounds, rounds);
+            prop_assert_eq!(stats.wins + stats.losses + stats.pushes, rounds);
+            prop_assert_eq!(stats.net, net);
+        }
+    }
+}
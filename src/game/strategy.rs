@@ -0,0 +1,229 @@
+use crate::game::rules::{HandValue, Rules};
+use crate::models::card::{Card, Rank};
+use crate::models::hand::Hand;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// A decision the player can make on their turn
+///
+/// Only Twist and Stick are offered under Pontoon rules; the rest need a rule that allows them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Twist,
+    Stick,
+    /// Double the stake on a two-card hand, take exactly one more card and stop
+    Double,
+    /// Split a pair into two hands, each with its own stake
+    Split,
+    /// Give up a two-card hand before playing it, losing half the stake
+    Surrender,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action_str = match self {
+            Action::Twist => "Twist",
+            Action::Stick => "Stick",
+            Action::Double => "Double",
+            Action::Split => "Split",
+            Action::Surrender => "Surrender",
+        };
+        write!(f, "{}", action_str)
+    }
+}
+
+/// Everything a player may look at when deciding what to do
+#[derive(Debug, Clone, Copy)]
+pub struct Situation<'a> {
+    pub hand: &'a Hand,
+    pub banker_upcard: Card,
+    pub rules: &'a Rules,
+    pub legal_actions: &'a [Action],
+}
+
+/// Chooses the player's actions
+pub trait PlayerStrategy: Send + Sync {
+    /// Short name used to select the strategy on the command line
+    fn name(&self) -> String;
+
+    /// Picks one of `situation.legal_actions`
+    fn decide(&self, situation: &Situation) -> Action;
+}
+
+/// Twists until the hand reaches a fixed total, then sticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StickOn {
+    pub total: u8,
+}
+
+impl PlayerStrategy for StickOn {
+    fn name(&self) -> String {
+        format!("stick-on-{}", self.total)
+    }
+
+    fn decide(&self, situation: &Situation) -> Action {
+        if HandValue::of(situation.hand).total >= self.total {
+            prefer(Action::Stick, situation)
+        } else {
+            prefer(Action::Twist, situation)
+        }
+    }
+}
+
+/// A simple table-based strategy that accounts for the banker's upcard and the Five Card Trick,
+/// and doubles, splits and surrenders the textbook hands where the rules allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BasicStrategy;
+
+impl PlayerStrategy for BasicStrategy {
+    fn name(&self) -> String {
+        "basic".to_string()
+    }
+
+    fn decide(&self, situation: &Situation) -> Action {
+        let value = HandValue::of(situation.hand);
+        let cards = situation.hand.card_count();
+
+        // With four cards, a small total is worth risking for the Five Card Trick
+        if situation.rules.five_card_trick() && cards == 4 && value.total <= 16 {
+            return prefer(Action::Twist, situation);
+        }
+
+        let upcard = situation.banker_upcard.base_value();
+        let offered = |action| situation.legal_actions.contains(&action);
+        if offered(Action::Split)
+            && matches!(situation.hand.cards()[0].rank(), Rank::Ace | Rank::Eight)
+        {
+            return Action::Split;
+        }
+        // Hard 16 against a 9, ten or Ace
+        let strong_upcard = !(2..=8).contains(&upcard);
+        if offered(Action::Surrender) && !value.soft && value.total == 16 && strong_upcard {
+            return Action::Surrender;
+        }
+        if offered(Action::Double)
+            && !value.soft
+            && (value.total == 11 || (value.total == 10 && (2..=9).contains(&upcard)))
+        {
+            return Action::Double;
+        }
+
+        let banker_weak = (2..=6).contains(&upcard);
+        let target = if value.soft {
+            19
+        } else if banker_weak {
+            situation.rules.min_stick.max(15)
+        } else {
+            17
+        };
+
+        if value.total >= target {
+            prefer(Action::Stick, situation)
+        } else {
+            prefer(Action::Twist, situation)
+        }
+    }
+}
+
+/// Returns `wanted` if it is legal, otherwise the first legal action
+fn prefer(wanted: Action, situation: &Situation) -> Action {
+    if situation.legal_actions.contains(&wanted) {
+        wanted
+    } else {
+        situation.legal_actions.first().copied().unwrap_or(wanted)
+    }
+}
+
+/// Totals a `stick-on-<N>` strategy may stick on; below 15 only suits rules with no minimum
+const STICK_ON_TOTALS: RangeInclusive<u8> = 1..=21;
+
+/// Returns the names of the built-in player strategies
+pub fn strategy_names() -> Vec<String> {
+    let (low, high) = (STICK_ON_TOTALS.start(), STICK_ON_TOTALS.end());
+    vec!["basic".to_string(), format!("stick-on-<{}..{}>", low, high)]
+}
+
+/// Looks up a built-in player strategy by name
+pub fn strategy_by_name(name: &str) -> Option<Box<dyn PlayerStrategy>> {
+    if name == "basic" {
+        return Some(Box::new(BasicStrategy));
+    }
+    let total = name.strip_prefix("stick-on-")?.parse::<u8>().ok()?;
+    if STICK_ON_TOTALS.contains(&total) {
+        Some(Box::new(StickOn { total }))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::Suit;
+    use proptest::prelude::*;
+
+    fn hand_of(ranks: &[Rank]) -> Hand {
+        let mut hand = Hand::new();
+        for rank in ranks {
+            hand.add_card(Card::new(*rank, Suit::Clubs));
+        }
+        hand
+    }
+
+    fn any_rank() -> impl Strategy<Value = Rank> {
+        (0..13usize).prop_map(|i| Rank::all()[i])
+    }
+
+    const BOTH: [Action; 2] = [Action::Twist, Action::Stick];
+
+    // Property: Strategies only ever pick a legal action
+    proptest! {
+        #[test]
+        fn prop_strategies_pick_legal_actions(
+            ranks in prop::collection::vec(any_rank(), 2..5),
+            upcard in any_rank(),
+            only_twist in any::<bool>()
+        ) {
+            let hand = hand_of(&ranks);
+            let rules = Rules::standard();
+            let legal: &[Action] = if only_twist { &[Action::Twist] } else { &BOTH };
+            let situation = Situation {
+                hand: &hand,
+                banker_upcard: Card::new(upcard, Suit::Spades),
+                rules: &rules,
+                legal_actions: legal,
+            };
+            for strategy in [strategy_by_name("basic").unwrap(), strategy_by_name("stick-on-17").unwrap()] {
+                prop_assert!(legal.contains(&strategy.decide(&situation)));
+            }
+        }
+    }
+
+    // Property: stick-on-N sticks exactly when the total reaches N
+    proptest! {
+        #[test]
+        fn prop_stick_on_threshold(ranks in prop::collection::vec(any_rank(), 2..4), total in 15u8..=21) {
+            let hand = hand_of(&ranks);
+            let rules = Rules::standard();
+            let situation = Situation {
+                hand: &hand,
+                banker_upcard: Card::new(Rank::Ten, Suit::Spades),
+                rules: &rules,
+                legal_actions: &BOTH,
+            };
+            let expected = if HandValue::of(&hand).total >= total { Action::Stick } else { Action::Twist };
+            prop_assert_eq!(StickOn { total }.decide(&situation), expected);
+        }
+    }
+
+    #[test]
+    fn test_strategy_names_round_trip() {
+        assert_eq!(strategy_by_name("stick-on-16").unwrap().name(), "stick-on-16");
+        assert_eq!(strategy_by_name("basic").unwrap().name(), "basic");
+        assert_eq!(strategy_by_name("stick-on-12").unwrap().name(), "stick-on-12");
+        assert!(strategy_by_name("stick-on-0").is_none());
+        assert!(strategy_by_name("stick-on-30").is_none());
+        assert_eq!(strategy_names()[1], "stick-on-<1..21>");
+        assert!(strategy_by_name("random").is_none());
+    }
+}
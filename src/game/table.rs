@@ -0,0 +1,475 @@
+use crate::error::{PontoonError, Result};
+use crate::game::banker::{BankerStrategy, StandOn};
+use crate::game::event::{EventListener, GameEvent};
+use crate::game::round::{Round, Seat};
+use crate::game::rules::Rules;
+use crate::game::strategy::Action;
+use crate::game::view::{TableView, Viewer};
+use crate::models::card::Card;
+use crate::models::deck::Deck;
+
+/// The deck and round as they stood before a practice action
+struct Snapshot {
+    deck: Deck,
+    round: Round,
+}
+
+/// Runs rounds from one deck and publishes every event to its subscribers
+pub struct Table {
+    deck: Deck,
+    rules: Rules,
+    banker: Box<dyn BankerStrategy>,
+    listeners: Vec<Box<dyn EventListener>>,
+    round: Option<Round>,
+    /// Deck order before the current round was dealt, next card first
+    round_deck: Vec<Card>,
+    /// How many of the current round's events have been published
+    published: usize,
+    practice: bool,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl Table {
+    /// Creates a table with the house banker for the rules and no subscribers
+    pub fn new(deck: Deck, rules: Rules) -> Self {
+        Table {
+            deck,
+            banker: Box::new(StandOn::house(&rules)),
+            rules,
+            listeners: Vec::new(),
+            round: None,
+            round_deck: Vec::new(),
+            published: 0,
+            practice: false,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Deals practice rounds, in which the player's actions can be undone and redone
+    pub fn with_practice(mut self) -> Self {
+        self.practice = true;
+        self
+    }
+
+    /// Returns true if rounds at this table are practice rounds
+    pub fn is_practice(&self) -> bool {
+        self.practice
+    }
+
+    /// Uses a different banker strategy
+    pub fn with_banker(mut self, banker: Box<dyn BankerStrategy>) -> Self {
+        self.banker = banker;
+        self
+    }
+
+    /// Sends every future event to a listener
+    pub fn subscribe(&mut self, listener: Box<dyn EventListener>) {
+        self.listeners.push(listener);
+    }
+
+    /// Returns the deck
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    /// Returns the rules in play
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Returns the round in progress, or the last one played
+    pub fn round(&self) -> Option<&Round> {
+        self.round.as_ref()
+    }
+
+    /// Returns what `viewer` may see of the current round
+    pub fn view(&self, viewer: Viewer) -> Option<TableView> {
+        self.round.as_ref().map(|round| round.view(viewer))
+    }
+
+    /// Returns the deck order before the current round was dealt, next card first
+    pub fn round_deck(&self) -> &[Card] {
+        &self.round_deck
+    }
+
+    /// Reshuffles if the deck is running low, then deals a new round
+    pub fn start_round(&mut self, stake: u32) -> Result<&Round> {
+        if self.deck.needs_reshuffle() {
+            self.deck.reshuffle();
+            self.publish(&GameEvent::DeckReshuffled {
+                cards: self.deck.cards_remaining(),
+            });
+        }
+        self.round_deck = self.deck.cards_in_deal_order();
+        let round = if self.practice {
+            Round::deal_practice(&mut self.deck, self.rules.clone(), stake)?
+        } else {
+            Round::deal(&mut self.deck, self.rules.clone(), stake)?
+        };
+        self.round = Some(round);
+        self.published = 0;
+        self.undo.clear();
+        self.redo.clear();
+        self.publish_round();
+        Ok(self.current())
+    }
+
+    /// Applies a player action to the current round
+    ///
+    /// Fails with `IllegalAction` if there is no round or the action is not allowed.
+    pub fn act(&mut self, action: Action) -> Result<()> {
+        let Some(round) = self.round.as_mut() else {
+            return Err(PontoonError::IllegalAction {
+                action,
+                legal: Vec::new(),
+            });
+        };
+        let before = self.practice.then(|| Snapshot {
+            deck: self.deck.clone(),
+            round: round.clone(),
+        });
+        let applied = round.apply(&mut self.deck, action);
+        if let (Ok(()), Some(before)) = (&applied, before) {
+            self.undo.push(before);
+            self.redo.clear();
+        }
+        self.publish_round();
+        applied
+    }
+
+    /// Lets the banker play out the current round and settles it
+    pub fn play_banker(&mut self) -> Result<()> {
+        let played = match self.round.as_mut() {
+            Some(round) => round.play_banker(&mut self.deck, self.banker.as_ref()),
+            None => Ok(()),
+        };
+        self.publish_round();
+        played
+    }
+
+    /// Returns true if there is a practice action to take back
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there is an undone practice action to play again
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Takes back the player's last action, restoring the deck and hands exactly
+    ///
+    /// Works after the banker has played too. Returns false outside practice or
+    /// when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(before) = self.undo.pop() else {
+            return false;
+        };
+        let after = self.restore(before);
+        let (seat, action, returned, rewound) = difference(self.current(), &after.round);
+        self.redo.push(after);
+        self.publish(&GameEvent::ActionUndone {
+            seat,
+            action,
+            returned,
+            rewound,
+        });
+        true
+    }
+
+    /// Plays an undone action again; the same cards come off the deck
+    pub fn redo(&mut self) -> bool {
+        let Some(after) = self.redo.pop() else {
+            return false;
+        };
+        let before = self.restore(after);
+        let (seat, action, dealt, replayed) = difference(&before.round, self.current());
+        self.undo.push(before);
+        self.publish(&GameEvent::ActionRedone {
+            seat,
+            action,
+            dealt,
+            replayed,
+        });
+        true
+    }
+
+    /// Puts a snapshot in place and returns the state it replaced
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        let current = Snapshot {
+            deck: std::mem::replace(&mut self.deck, snapshot.deck),
+            round: self
+                .round
+                .replace(snapshot.round)
+                .expect("Snapshots are only taken during a round"),
+        };
+        self.published = self.current().events().len();
+        current
+    }
+
+    fn current(&self) -> &Round {
+        self.round.as_ref().expect("Snapshots are only taken during a round")
+    }
+
+    /// Publishes the current round's events that listeners have not seen yet
+    fn publish_round(&mut self) {
+        let Some(round) = self.round.as_ref() else {
+            return;
+        };
+        for event in &round.events()[self.published..] {
+            for listener in &mut self.listeners {
+                listener.on_event(event);
+            }
+        }
+        self.published = round.events().len();
+    }
+
+    fn publish(&mut self, event: &GameEvent) {
+        for listener in &mut self.listeners {
+            listener.on_event(event);
+        }
+    }
+}
+
+/// The first action `later` took beyond `earlier`, the cards dealt from then on, and every
+/// event from then on
+fn difference(earlier: &Round, later: &Round) -> (Seat, Action, Vec<Card>, Vec<GameEvent>) {
+    let (seat, action) = later.actions()[earlier.actions().len()];
+    let events = &later.events()[earlier.events().len()..];
+    let dealt = events
+        .iter()
+        .filter_map(|event| match event {
+            GameEvent::CardDealt { card, .. } => Some(*card),
+            _ => None,
+        })
+        .collect();
+    (seat, action, dealt, events.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event::{EventLog, RoundState};
+    use crate::game::round::Phase;
+    use proptest::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    // Property: Subscribers see exactly the round's own events, so they can rebuild its state
+    proptest! {
+        #[test]
+        fn prop_subscribers_can_rebuild_round(seed in any::<u64>(), twists in 0usize..4) {
+            let log = Arc::new(Mutex::new(EventLog::default()));
+            let mut table = Table::new(Deck::new_seeded(seed), Rules::standard());
+            table.subscribe(Box::new(log.clone()));
+
+            table.start_round(10).unwrap();
+            for _ in 0..twists {
+                let _ = table.act(Action::Twist);
+            }
+            while table.round().unwrap().phase() == Phase::PlayerTurn {
+                if table.act(Action::Stick).is_err() {
+                    table.act(Action::Twist).unwrap();
+                }
+            }
+            table.play_banker().unwrap();
+
+            let round = table.round().unwrap();
+            prop_assert_eq!(round.phase(), Phase::Finished);
+            let log = log.lock().unwrap();
+            prop_assert_eq!(log.events(), round.events());
+            prop_assert_eq!(&RoundState::from_events(log.events()), round.state());
+        }
+    }
+
+    /// Applies a practice move: an action chosen from the legal ones, undo, redo or the
+    /// banker's turn, and checks a subscriber's fold of the log still matches the round
+    fn check_fold_after(table: &mut Table, log: &Arc<Mutex<EventLog>>, step: (u8, usize)) {
+        match step.0 % 4 {
+            0 => {
+                let legal = table.round().unwrap().legal_actions();
+                if !legal.is_empty() {
+                    table.act(legal[step.1 % legal.len()]).unwrap();
+                }
+            }
+            1 => {
+                table.undo();
+            }
+            2 => {
+                table.redo();
+            }
+            _ => {
+                if table.round().unwrap().phase() == Phase::BankerTurn {
+                    table.play_banker().unwrap();
+                }
+            }
+        }
+        let log = log.lock().unwrap();
+        assert_eq!(&RoundState::from_events(log.events()), table.round().unwrap().state());
+    }
+
+    // Property: A subscriber folding the log across undo and redo keeps the table's state
+    proptest! {
+        #[test]
+        fn prop_undo_and_redo_fold_to_the_table_state(
+            seed in any::<u64>(),
+            blackjack in any::<bool>(),
+            steps in prop::collection::vec((any::<u8>(), any::<usize>()), 1..16),
+        ) {
+            let rules = if blackjack { Rules::blackjack() } else { Rules::standard() };
+            let log = Arc::new(Mutex::new(EventLog::default()));
+            let mut table = Table::new(Deck::new_seeded(seed), rules).with_practice();
+            table.subscribe(Box::new(log.clone()));
+            table.start_round(10).unwrap();
+            for step in steps {
+                check_fold_after(&mut table, &log, step);
+            }
+        }
+    }
+
+    #[test]
+    fn test_undoing_a_split_folds_back_to_one_hand() {
+        let cards = ["8h", "9d", "8c", "7s", "3c", "Td", "2h", "Kc"];
+        let mut cards: Vec<Card> = cards.iter().map(|card| card.parse().unwrap()).collect();
+        // A full deck underneath keeps the shoe above the reshuffle threshold
+        cards.extend(Deck::create_standard_deck());
+        let deck = Deck::stacked(cards, 0);
+        let log = Arc::new(Mutex::new(EventLog::default()));
+        let mut table = Table::new(deck, Rules::blackjack()).with_practice();
+        table.subscribe(Box::new(log.clone()));
+        table.start_round(10).unwrap();
+        // Split, double the first hand, stick on the second and let the banker play, then
+        // take it all back and play it again
+        for action in [Action::Split, Action::Double, Action::Stick] {
+            table.act(action).unwrap();
+        }
+        table.play_banker().unwrap();
+        let steps = [(1, 0), (1, 0), (1, 0), (2, 0), (2, 0), (2, 0), (1, 0), (1, 0), (1, 0)];
+        for step in steps {
+            check_fold_after(&mut table, &log, step);
+        }
+        assert_eq!(table.round().unwrap().player_hands().len(), 1);
+    }
+
+    #[test]
+    fn test_reshuffle_is_published() {
+        let log = Arc::new(Mutex::new(EventLog::default()));
+        let mut table = Table::new(Deck::new_seeded(4), Rules::standard());
+        table.subscribe(Box::new(log.clone()));
+        while !table.deck().needs_reshuffle() {
+            table.start_round(10).unwrap();
+            while table.round().unwrap().phase() == Phase::PlayerTurn {
+                if table.act(Action::Stick).is_err() {
+                    table.act(Action::Twist).unwrap();
+                }
+            }
+            table.play_banker().unwrap();
+        }
+        table.start_round(10).unwrap();
+        let reshuffles = log
+            .lock()
+            .unwrap()
+            .events()
+            .iter()
+            .filter(|event| matches!(event, GameEvent::DeckReshuffled { cards: 52 }))
+            .count();
+        assert_eq!(reshuffles, 1);
+    }
+
+    fn practice_table(seed: u64) -> Table {
+        let mut table = Table::new(Deck::new_seeded(seed), Rules::standard()).with_practice();
+        table.start_round(10).unwrap();
+        table
+    }
+
+    // Property: Undoing an action restores the deck and hands exactly, and redo replays it
+    proptest! {
+        #[test]
+        fn prop_undo_restores_exactly(seed in any::<u64>(), actions in prop::collection::vec(any::<bool>(), 1..6)) {
+            let mut table = practice_table(seed);
+            prop_assume!(table.round().unwrap().phase() == Phase::PlayerTurn);
+            let deck_before = table.deck().cards_in_deal_order();
+            let state_before = table.round().unwrap().state().clone();
+
+            let mut taken = 0;
+            for twist in actions {
+                let action = if twist { Action::Twist } else { Action::Stick };
+                if table.act(action).is_ok() {
+                    taken += 1;
+                }
+            }
+            table.play_banker().unwrap();
+            let deck_after = table.deck().cards_in_deal_order();
+            let state_after = table.round().unwrap().state().clone();
+
+            for _ in 0..taken {
+                prop_assert!(table.undo());
+            }
+            prop_assert!(!table.can_undo());
+            prop_assert_eq!(table.deck().cards_in_deal_order(), deck_before);
+            prop_assert_eq!(table.round().unwrap().state(), &state_before);
+
+            for _ in 0..taken {
+                prop_assert!(table.redo());
+            }
+            prop_assert!(!table.can_redo());
+            prop_assert_eq!(table.deck().cards_in_deal_order(), deck_after);
+            prop_assert_eq!(table.round().unwrap().state(), &state_after);
+        }
+    }
+
+    // Property: After an undo, the other line is dealt the same upcoming cards
+    proptest! {
+        #[test]
+        fn prop_other_line_sees_same_cards(seed in any::<u64>()) {
+            let mut table = practice_table(seed);
+            let next = table.deck().cards_in_deal_order()[0];
+            prop_assume!(table.act(Action::Stick).is_ok());
+            table.play_banker().unwrap();
+            prop_assert!(table.undo());
+            prop_assert!(table.act(Action::Twist).is_ok());
+            prop_assert_eq!(table.round().unwrap().player_hand().cards()[2], next);
+            prop_assert!(!table.can_redo());
+        }
+    }
+
+    #[test]
+    fn test_undo_is_off_in_scored_play() {
+        let mut table = Table::new(Deck::new_seeded(2), Rules::standard());
+        table.start_round(10).unwrap();
+        assert!(table.act(Action::Twist).is_ok());
+        assert!(!table.can_undo());
+        assert!(!table.undo());
+        assert!(!table.round().unwrap().is_practice());
+    }
+
+    #[test]
+    fn test_undo_reports_returned_cards() {
+        let log = Arc::new(Mutex::new(EventLog::default()));
+        let mut table = practice_table(8);
+        table.subscribe(Box::new(log.clone()));
+        table.act(Action::Twist).unwrap();
+        let dealt = table.round().unwrap().player_hand().cards()[2];
+        assert!(table.undo());
+        let log = log.lock().unwrap();
+        let Some(GameEvent::ActionUndone {
+            seat: Seat::Player,
+            action: Action::Twist,
+            returned,
+            ..
+        }) = log.events().last()
+        else {
+            panic!("The last event is not the undo");
+        };
+        assert_eq!(returned, &vec![dealt]);
+    }
+
+    #[test]
+    fn test_no_round_means_no_action() {
+        let mut table = Table::new(Deck::new_seeded(1), Rules::standard());
+        let refused = table.act(Action::Twist);
+        assert!(matches!(refused, Err(PontoonError::IllegalAction { .. })));
+        assert!(table.round().is_none());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::game::advisor::Advisor;
+use crate::game::strategy::Action;
+use crate::game::view::TableView;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest to wait for input before looking at the turn clock again
+pub const CLOCK_CHECK: Duration = Duration::from_millis(100);
+
+/// A source of the current time, so timed turns can be tested without waiting
+pub trait Clock: Send + Sync {
+    /// Returns the time elapsed since some fixed starting point
+    fn now(&self) -> Duration;
+}
+
+/// The wall clock
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock that only moves when told to; clones share the same time
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().expect("Clock poisoned") += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().expect("Clock poisoned")
+    }
+}
+
+/// What is played for a player who runs out of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// Stick, or Twist when the hand is too low to stick on
+    Stick,
+    /// Whatever the advisor recommends
+    Advisor,
+}
+
+impl TimeoutAction {
+    /// Returns the move to play for the viewer of `view`, or None if it is not their turn
+    pub fn choose(&self, view: &TableView) -> Option<Action> {
+        match self {
+            TimeoutAction::Stick if view.legal_actions.contains(&Action::Stick) => {
+                Some(Action::Stick)
+            }
+            TimeoutAction::Stick => view.legal_actions.first().copied(),
+            TimeoutAction::Advisor => {
+                view.with_situation(|situation| Advisor::default().recommend(situation))
+            }
+        }
+    }
+}
+
+impl fmt::Display for TimeoutAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutAction::Stick => write!(f, "stick"),
+            TimeoutAction::Advisor => write!(f, "advisor"),
+        }
+    }
+}
+
+impl FromStr for TimeoutAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stick" => Ok(TimeoutAction::Stick),
+            "advisor" => Ok(TimeoutAction::Advisor),
+            other => Err(format!("unknown timeout action '{}' (use stick or advisor)", other)),
+        }
+    }
+}
+
+/// A time limit on each decision
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnLimit {
+    pub limit: Duration,
+    /// Warn the player when this much time is left; each warning is given once
+    pub warnings: Vec<Duration>,
+    pub on_timeout: TimeoutAction,
+}
+
+impl TurnLimit {
+    /// Warns at half time and again with five seconds left
+    pub fn new(limit: Duration, on_timeout: TimeoutAction) -> Self {
+        let mut warnings = vec![limit / 2];
+        if Duration::from_secs(5) < limit / 2 {
+            warnings.push(Duration::from_secs(5));
+        }
+        TurnLimit {
+            limit,
+            warnings,
+            on_timeout,
+        }
+    }
+}
+
+/// Something a turn timer has to report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerEvent {
+    /// Time is running short; this much is left
+    Warning(Duration),
+    /// Time is up
+    Expired,
+}
+
+/// Times one decision against a `TurnLimit`
+#[derive(Debug, Clone)]
+pub struct TurnTimer {
+    deadline: Duration,
+    /// Time left at which warnings are still to be given, most first
+    warnings: Vec<Duration>,
+    expired: bool,
+}
+
+impl TurnTimer {
+    /// Starts timing a decision now
+    pub fn start(limit: &TurnLimit, clock: &dyn Clock) -> Self {
+        let mut warnings: Vec<Duration> =
+            limit.warnings.iter().copied().filter(|left| *left < limit.limit).collect();
+        warnings.sort_unstable_by(|a, b| b.cmp(a));
+        warnings.dedup();
+        TurnTimer {
+            deadline: clock.now() + limit.limit,
+            warnings,
+            expired: false,
+        }
+    }
+
+    /// Returns the time left to decide
+    pub fn remaining(&self, clock: &dyn Clock) -> Duration {
+        self.deadline.saturating_sub(clock.now())
+    }
+
+    /// Returns a warning or the expiry if one is due and has not been reported yet
+    ///
+    /// Warnings whose time has passed together are reported as one; once the time
+    /// is up no more warnings are given.
+    pub fn poll(&mut self, clock: &dyn Clock) -> Option<TimerEvent> {
+        if self.expired {
+            return None;
+        }
+        let left = self.remaining(clock);
+        if left.is_zero() {
+            self.expired = true;
+            self.warnings.clear();
+            return Some(TimerEvent::Expired);
+        }
+        let due = self.warnings.iter().take_while(|warning| **warning >= left).count();
+        if due == 0 {
+            return None;
+        }
+        self.warnings.drain(..due);
+        Some(TimerEvent::Warning(left))
+    }
+
+    /// Returns how long until `poll` next has something to report; None once expired
+    pub fn until_next(&self, clock: &dyn Clock) -> Option<Duration> {
+        if self.expired {
+            return None;
+        }
+        let left = self.remaining(clock);
+        let next = self.warnings.first().map_or(Duration::ZERO, |warning| *warning);
+        Some(left.saturating_sub(next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::Rules;
+    use crate::game::shared::SharedRound;
+    use crate::game::view::Viewer;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+
+    // Property: Warnings come once each and before the expiry, which comes exactly at the limit
+    proptest! {
+        #[test]
+        fn prop_timer_warns_then_expires_once(
+            limit_ms in 1u64..60_000,
+            warnings in prop::collection::vec(0u64..60_000, 0..4),
+            steps in prop::collection::vec(1u64..5_000, 1..60)
+        ) {
+            let clock = ManualClock::new();
+            let limit = TurnLimit {
+                limit: Duration::from_millis(limit_ms),
+                warnings: warnings.iter().map(|ms| Duration::from_millis(*ms)).collect(),
+                on_timeout: TimeoutAction::Stick,
+            };
+            let mut timer = TurnTimer::start(&limit, &clock);
+            let mut events = Vec::new();
+            let mut elapsed = 0;
+            for step in steps {
+                clock.advance(Duration::from_millis(step));
+                elapsed += step;
+                if let Some(event) = timer.poll(&clock) {
+                    prop_assert_eq!(event == TimerEvent::Expired, elapsed >= limit_ms);
+                    events.push(event);
+                }
+                prop_assert!(timer.poll(&clock).is_none());
+            }
+            let expiries = events.iter().filter(|e| **e == TimerEvent::Expired).count();
+            prop_assert_eq!(expiries, usize::from(elapsed >= limit_ms));
+            prop_assert!(events.iter().position(|e| *e == TimerEvent::Expired)
+                .is_none_or(|at| at == events.len() - 1));
+            let lefts = events.iter().filter_map(|event| match event {
+                TimerEvent::Warning(left) => Some(*left),
+                TimerEvent::Expired => None,
+            });
+            let mut previous = Duration::MAX;
+            for left in lefts {
+                prop_assert!(left < previous);
+                previous = left;
+            }
+        }
+    }
+
+    #[test]
+    fn test_until_next_wakes_for_each_warning() {
+        let clock = ManualClock::new();
+        let limit = TurnLimit::new(Duration::from_secs(30), TimeoutAction::Stick);
+        let mut timer = TurnTimer::start(&limit, &clock);
+        assert_eq!(timer.until_next(&clock), Some(Duration::from_secs(15)));
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(timer.poll(&clock), Some(TimerEvent::Warning(Duration::from_secs(15))));
+        assert_eq!(timer.until_next(&clock), Some(Duration::from_secs(10)));
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(timer.poll(&clock), Some(TimerEvent::Warning(Duration::from_secs(5))));
+        assert_eq!(timer.until_next(&clock), Some(Duration::from_secs(5)));
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(timer.poll(&clock), Some(TimerEvent::Expired));
+        assert_eq!(timer.until_next(&clock), None);
+    }
+
+    // Property: The move played on a timeout is always legal
+    proptest! {
+        #[test]
+        fn prop_timeout_action_is_legal(seed in any::<u64>(), advisor in any::<bool>()) {
+            let mut deck = Deck::new_seeded(seed);
+            let mut round = SharedRound::deal(&mut deck, Rules::standard(), 10, &[1, 2]).unwrap();
+            let on_timeout = if advisor { TimeoutAction::Advisor } else { TimeoutAction::Stick };
+            while let Some(seat) = round.turn() {
+                let view = round.view(Viewer::Seat(seat));
+                let action = on_timeout.choose(&view).unwrap();
+                prop_assert!(view.legal_actions.contains(&action));
+                if !advisor && view.legal_actions.contains(&Action::Stick) {
+                    prop_assert_eq!(action, Action::Stick);
+                }
+                prop_assert_eq!(on_timeout.choose(&round.view(Viewer::Spectator)), None);
+                round.apply(&mut deck, action).unwrap();
+            }
+        }
+    }
+}
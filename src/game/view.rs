@@ -0,0 +1,232 @@
+use crate::game::rules::{HandValue, Rules, Settlement};
+use crate::game::strategy::{Action, Situation};
+use crate::models::card::Card;
+use crate::models::hand::Hand;
+
+/// Seat number of the lone player in views of a one-player round
+pub const PLAYER_SEAT: usize = 1;
+
+/// Who a view is made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Viewer {
+    /// The player sitting at this seat
+    Seat(usize),
+    /// Someone watching who holds no hand
+    Spectator,
+}
+
+/// A card as one viewer sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeenCard {
+    FaceUp(Card),
+    FaceDown,
+}
+
+impl SeenCard {
+    /// Returns the card if the viewer can see it
+    pub fn card(&self) -> Option<Card> {
+        match self {
+            SeenCard::FaceUp(card) => Some(*card),
+            SeenCard::FaceDown => None,
+        }
+    }
+}
+
+/// A hand as one viewer sees it, with face-down cards redacted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HandView {
+    cards: Vec<SeenCard>,
+}
+
+impl HandView {
+    /// Shows every card in the hand
+    pub fn open(hand: &Hand) -> Self {
+        HandView {
+            cards: hand.cards().iter().map(|card| SeenCard::FaceUp(*card)).collect(),
+        }
+    }
+
+    /// Shows the hand with its second card, the banker's hole card, face down
+    pub fn with_hole_card(hand: &Hand) -> Self {
+        let mut view = Self::open(hand);
+        if let Some(card) = view.cards.get_mut(1) {
+            *card = SeenCard::FaceDown;
+        }
+        view
+    }
+
+    /// Returns the cards in the order they were dealt
+    pub fn cards(&self) -> &[SeenCard] {
+        &self.cards
+    }
+
+    /// Adds a card to the end of the hand
+    pub fn push(&mut self, card: SeenCard) {
+        self.cards.push(card);
+    }
+
+    /// Turns the first face-down card over; returns false if there was none
+    pub fn reveal(&mut self, card: Card) -> bool {
+        match self.cards.iter_mut().find(|seen| **seen == SeenCard::FaceDown) {
+            Some(seen) => {
+                *seen = SeenCard::FaceUp(card);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the number of cards that cannot be seen
+    pub fn face_down(&self) -> usize {
+        self.cards.iter().filter(|seen| **seen == SeenCard::FaceDown).count()
+    }
+
+    /// Returns the first card if it is face up
+    pub fn upcard(&self) -> Option<Card> {
+        self.cards.first().and_then(SeenCard::card)
+    }
+
+    /// Returns the whole hand, or None while any card is face down
+    pub fn hand(&self) -> Option<Hand> {
+        let mut hand = Hand::new();
+        for seen in &self.cards {
+            hand.add_card(seen.card()?);
+        }
+        Some(hand)
+    }
+
+    /// Returns the total, or None while any card is face down
+    pub fn total(&self) -> Option<u8> {
+        self.hand().map(|hand| HandValue::of(&hand).total)
+    }
+}
+
+/// One player's place at the table as seen by a viewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatView {
+    pub seat: usize,
+    /// The hand being played, or played last
+    pub hand: HandView,
+    /// Every hand the seat holds, in order; more than one only after a split
+    pub hands: Vec<HandView>,
+    /// Set once the banker has played
+    pub settlement: Option<Settlement>,
+}
+
+/// Everything one viewer is allowed to know about a round
+///
+/// Views are the only picture of the table that bots, clients and renderers are
+/// given, so a face-down card cannot be read by anyone who should not see it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableView {
+    pub viewer: Viewer,
+    pub rules: Rules,
+    pub stake: u32,
+    pub seats: Vec<SeatView>,
+    pub banker: HandView,
+    /// The seat that must act next, if any
+    pub turn: Option<usize>,
+    /// The viewer's legal actions; empty unless it is the viewer's turn
+    pub legal_actions: Vec<Action>,
+}
+
+impl TableView {
+    /// Returns the view of a player's seat
+    pub fn seat(&self, seat: usize) -> Option<&SeatView> {
+        self.seats.iter().find(|view| view.seat == seat)
+    }
+
+    /// Returns the viewer's own seat, if they are playing this round
+    pub fn own_seat(&self) -> Option<&SeatView> {
+        match self.viewer {
+            Viewer::Seat(seat) => self.seat(seat),
+            Viewer::Spectator => None,
+        }
+    }
+
+    /// Returns true once every seat has been settled
+    pub fn is_settled(&self) -> bool {
+        self.seats.iter().all(|seat| seat.settlement.is_some())
+    }
+
+    /// Calls `decide` with the viewer's decision, if it is their turn
+    pub fn with_situation<T>(&self, decide: impl FnOnce(&Situation) -> T) -> Option<T> {
+        if self.legal_actions.is_empty() {
+            return None;
+        }
+        let hand = self.own_seat()?.hand.hand()?;
+        Some(decide(&Situation {
+            hand: &hand,
+            banker_upcard: self.banker.upcard()?,
+            rules: &self.rules,
+            legal_actions: &self.legal_actions,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::banker::StandOn;
+    use crate::game::round::{Phase, Round};
+    use crate::game::shared::SharedRound;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+
+    // Property: Until the banker plays, no view of a round contains the hole card
+    proptest! {
+        #[test]
+        fn prop_views_never_show_the_hole_card(seed in any::<u64>(), players in 1usize..=5) {
+            let mut deck = Deck::new_seeded(seed);
+            let seats: Vec<usize> = (1..=players).collect();
+            let mut round = SharedRound::deal(&mut deck, Rules::standard(), 10, &seats).unwrap();
+            let hole_card = round.banker_hand().cards()[1];
+            while let Some(turn) = round.turn() {
+                let viewers = seats.iter().map(|seat| Viewer::Seat(*seat)).chain([Viewer::Spectator]);
+                for viewer in viewers {
+                    let view = round.view(viewer);
+                    let mut seen: Vec<Card> =
+                        view.banker.cards().iter().filter_map(SeenCard::card).collect();
+                    for seat in &view.seats {
+                        seen.extend(seat.hand.cards().iter().filter_map(SeenCard::card));
+                    }
+                    prop_assert!(!seen.contains(&hole_card));
+                    prop_assert_eq!(view.banker.face_down(), 1);
+                    prop_assert_eq!(view.legal_actions.is_empty(), viewer != Viewer::Seat(turn));
+                }
+                let action = round.legal_actions().last().copied().unwrap();
+                round.apply(&mut deck, action).unwrap();
+            }
+            round.play_banker(&mut deck, &StandOn::default()).unwrap();
+            let view = round.view(Viewer::Spectator);
+            prop_assert_eq!(view.banker.hand(), Some(round.banker_hand().clone()));
+            prop_assert!(view.is_settled());
+        }
+    }
+
+    #[test]
+    fn test_one_player_round_view() {
+        let mut deck = Deck::new_seeded(4);
+        let round = Round::deal(&mut deck, Rules::standard(), 10).unwrap();
+        let view = round.view(Viewer::Seat(PLAYER_SEAT));
+        assert_eq!(view.own_seat().unwrap().hand.hand(), Some(round.player_hand().clone()));
+        assert_eq!(view.banker.upcard(), Some(round.banker_upcard()));
+        if round.phase() == Phase::PlayerTurn {
+            assert_eq!(view.banker.cards()[1], SeenCard::FaceDown);
+            assert_eq!(view.banker.total(), None);
+            assert_eq!(view.legal_actions, round.legal_actions());
+            assert!(round.view(Viewer::Spectator).legal_actions.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_reveal_turns_over_the_face_down_card() {
+        let mut deck = Deck::new_seeded(9);
+        let round = Round::deal(&mut deck, Rules::standard(), 10).unwrap();
+        let mut view = HandView::with_hole_card(round.banker_hand());
+        assert_eq!(view.hand(), None);
+        assert!(view.reveal(round.banker_hand().cards()[1]));
+        assert!(!view.reveal(round.banker_hand().cards()[1]));
+        assert_eq!(view.hand(), Some(round.banker_hand().clone()));
+    }
+}
@@ -0,0 +1,462 @@
+//! Hand histories: a line-based record of every round played
+//!
+//! The format is described in `HAND_HISTORY.md`. Each line is one [`Entry`];
+//! blank lines and lines starting with `#` are ignored.
+
+pub mod replay;
+
+use crate::game::event::GameEvent;
+use crate::game::round::{Round, Seat};
+use crate::game::rules::{HandRank, Outcome, Settlement};
+use crate::game::strategy::Action;
+use crate::models::card::Card;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// First line of every hand history file
+pub const HEADER: &str = "# Pontoon hand history v1";
+
+/// One line of a hand history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    /// Start of a game: the deck seed, shoe size, rule preset and deck preset
+    Game {
+        seed: u64,
+        decks: usize,
+        rules: String,
+        deck_type: String,
+    },
+    /// Start of a round: the stake and the cards still in the deck, next card first
+    Round { number: u32, stake: u32, deck: Vec<Card> },
+    /// A card dealt to a seat, face up or face down
+    Deal { seat: Seat, card: Card, face_up: bool },
+    /// A decision taken by a seat
+    Action { seat: Seat, action: Action },
+    /// A face-down card turned over
+    Reveal { seat: Seat, card: Card },
+    /// How the round was settled
+    Settle(Settlement),
+    /// End of a round
+    End,
+}
+
+/// Error returned when a hand history line cannot be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHistoryError {
+    /// 1-based line number, or 0 when parsing a single line
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseHistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseHistoryError {}
+
+fn error(message: impl Into<String>) -> ParseHistoryError {
+    ParseHistoryError {
+        line: 0,
+        message: message.into(),
+    }
+}
+
+pub(crate) fn seat_name(seat: Seat) -> &'static str {
+    match seat {
+        Seat::Player => "player",
+        Seat::Banker => "banker",
+    }
+}
+
+fn parse_seat(text: &str) -> Result<Seat, ParseHistoryError> {
+    match text {
+        "player" => Ok(Seat::Player),
+        "banker" => Ok(Seat::Banker),
+        _ => Err(error(format!("unknown seat '{}'", text))),
+    }
+}
+
+pub(crate) fn action_name(action: Action) -> &'static str {
+    match action {
+        Action::Twist => "twist",
+        Action::Stick => "stick",
+        Action::Double => "double",
+        Action::Split => "split",
+        Action::Surrender => "surrender",
+    }
+}
+
+fn parse_action(text: &str) -> Result<Action, ParseHistoryError> {
+    match text {
+        "twist" => Ok(Action::Twist),
+        "stick" => Ok(Action::Stick),
+        "double" => Ok(Action::Double),
+        "split" => Ok(Action::Split),
+        "surrender" => Ok(Action::Surrender),
+        _ => Err(error(format!("unknown action '{}'", text))),
+    }
+}
+
+pub(crate) fn rank_name(rank: HandRank) -> String {
+    match rank {
+        HandRank::Bust => "bust".to_string(),
+        HandRank::Total(total) => total.to_string(),
+        HandRank::FiveCardTrick => "five-card-trick".to_string(),
+        HandRank::Pontoon => "pontoon".to_string(),
+    }
+}
+
+pub(crate) fn parse_rank(text: &str) -> Result<HandRank, ParseHistoryError> {
+    match text {
+        "bust" => Ok(HandRank::Bust),
+        "five-card-trick" => Ok(HandRank::FiveCardTrick),
+        "pontoon" => Ok(HandRank::Pontoon),
+        _ => text
+            .parse()
+            .map(HandRank::Total)
+            .map_err(|_| error(format!("unknown hand '{}'", text))),
+    }
+}
+
+pub(crate) fn outcome_name(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Win => "win",
+        Outcome::Lose => "lose",
+        Outcome::Push => "push",
+    }
+}
+
+fn parse_outcome(text: &str) -> Result<Outcome, ParseHistoryError> {
+    match text {
+        "win" => Ok(Outcome::Win),
+        "lose" => Ok(Outcome::Lose),
+        "push" => Ok(Outcome::Push),
+        _ => Err(error(format!("unknown outcome '{}'", text))),
+    }
+}
+
+fn parse_card(text: &str) -> Result<Card, ParseHistoryError> {
+    text.parse().map_err(|e: crate::models::card::ParseCardError| error(e.to_string()))
+}
+
+fn parse_number<T: FromStr>(text: &str) -> Result<T, ParseHistoryError> {
+    text.parse()
+        .map_err(|_| error(format!("'{}' is not a number", text)))
+}
+
+/// Splits `key=value`, checking the key
+fn field<'a>(text: Option<&'a str>, key: &str) -> Result<&'a str, ParseHistoryError> {
+    text.and_then(|part| part.strip_prefix(key))
+        .and_then(|rest| rest.strip_prefix('='))
+        .ok_or_else(|| error(format!("expected {}=", key)))
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Game {
+                seed,
+                decks,
+                rules,
+                deck_type,
+            } => write!(
+                f,
+                "game seed={} decks={} rules={} deck={}",
+                seed, decks, rules, deck_type
+            ),
+            Entry::Round { number, stake, deck } => {
+                let cards: Vec<String> = deck.iter().map(Card::notation).collect();
+                write!(f, "round {} stake={} deck={}", number, stake, cards.join(","))
+            }
+            Entry::Deal { seat, card, face_up } => write!(
+                f,
+                "deal {} {} {}",
+                seat_name(*seat),
+                card.notation(),
+                if *face_up { "up" } else { "down" }
+            ),
+            Entry::Action { seat, action } => {
+                write!(f, "action {} {}", seat_name(*seat), action_name(*action))
+            }
+            Entry::Reveal { seat, card } => {
+                write!(f, "reveal {} {}", seat_name(*seat), card.notation())
+            }
+            Entry::Settle(settlement) => write!(
+                f,
+                "settle {} player={} banker={} net={}",
+                outcome_name(settlement.outcome),
+                rank_name(settlement.player_rank),
+                rank_name(settlement.banker_rank),
+                settlement.net
+            ),
+            Entry::End => write!(f, "end"),
+        }
+    }
+}
+
+impl FromStr for Entry {
+    type Err = ParseHistoryError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parts = line.split_whitespace();
+        let keyword = parts.next().ok_or_else(|| error("empty line"))?;
+        let entry = match keyword {
+            "game" => Entry::Game {
+                seed: parse_number(field(parts.next(), "seed")?)?,
+                decks: parse_number(field(parts.next(), "decks")?)?,
+                rules: field(parts.next(), "rules")?.to_string(),
+                // Histories written before other deck types existed all used standard decks
+                deck_type: match parts.next() {
+                    None => "standard".to_string(),
+                    deck => field(deck, "deck")?.to_string(),
+                },
+            },
+            "round" => Entry::Round {
+                number: parse_number(parts.next().ok_or_else(|| error("missing round number"))?)?,
+                stake: parse_number(field(parts.next(), "stake")?)?,
+                deck: field(parts.next(), "deck")?
+                    .split(',')
+                    .filter(|card| !card.is_empty())
+                    .map(parse_card)
+                    .collect::<Result<_, _>>()?,
+            },
+            "deal" => Entry::Deal {
+                seat: parse_seat(parts.next().unwrap_or(""))?,
+                card: parse_card(parts.next().unwrap_or(""))?,
+                face_up: match parts.next() {
+                    Some("up") => true,
+                    Some("down") => false,
+                    _ => return Err(error("expected up or down")),
+                },
+            },
+            "action" => Entry::Action {
+                seat: parse_seat(parts.next().unwrap_or(""))?,
+                action: parse_action(parts.next().unwrap_or(""))?,
+            },
+            "reveal" => Entry::Reveal {
+                seat: parse_seat(parts.next().unwrap_or(""))?,
+                card: parse_card(parts.next().unwrap_or(""))?,
+            },
+            "settle" => Entry::Settle(Settlement {
+                outcome: parse_outcome(parts.next().unwrap_or(""))?,
+                player_rank: parse_rank(field(parts.next(), "player")?)?,
+                banker_rank: parse_rank(field(parts.next(), "banker")?)?,
+                net: parse_number(field(parts.next(), "net")?)?,
+            }),
+            "end" => Entry::End,
+            _ => return Err(error(format!("unknown entry '{}'", keyword))),
+        };
+        match parts.next() {
+            None => Ok(entry),
+            Some(extra) => Err(error(format!("unexpected '{}'", extra))),
+        }
+    }
+}
+
+/// Parses a whole hand history, skipping blank lines and `#` comments
+pub fn parse(text: &str) -> Result<Vec<Entry>, ParseHistoryError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            line.parse().map_err(|e: ParseHistoryError| ParseHistoryError {
+                line: index + 1,
+                ..e
+            })
+        })
+        .collect()
+}
+
+/// Lists a finished round as history entries
+///
+/// `deck` is the deck order before the round was dealt, next card first.
+pub fn round_entries(number: u32, deck: Vec<Card>, round: &Round) -> Vec<Entry> {
+    let mut entries = vec![Entry::Round {
+        number,
+        stake: round.stake(),
+        deck,
+    }];
+    // Turn changes and busts are implied by the cards, so they get no line of their own
+    entries.extend(round.events().iter().filter_map(|event| match *event {
+        GameEvent::CardDealt {
+            seat,
+            card,
+            face_up,
+        } => Some(Entry::Deal {
+            seat,
+            card,
+            face_up,
+        }),
+        GameEvent::ActionTaken { seat, action } => Some(Entry::Action { seat, action }),
+        GameEvent::CardRevealed { seat, card } => Some(Entry::Reveal { seat, card }),
+        GameEvent::RoundSettled(settlement) => Some(Entry::Settle(settlement)),
+        _ => None,
+    }));
+    entries.push(Entry::End);
+    entries
+}
+
+/// Appends rounds to a hand history file as they finish
+pub struct HistoryWriter {
+    out: Box<dyn Write + Send>,
+    rounds: u32,
+}
+
+impl HistoryWriter {
+    /// Starts a history, writing the header and the game line
+    pub fn new(
+        mut out: Box<dyn Write + Send>,
+        seed: u64,
+        decks: usize,
+        rules: &str,
+        deck_type: &str,
+    ) -> io::Result<Self> {
+        writeln!(out, "{}", HEADER)?;
+        writeln!(
+            out,
+            "{}",
+            Entry::Game {
+                seed,
+                decks,
+                rules: rules.to_string(),
+                deck_type: deck_type.to_string(),
+            }
+        )?;
+        out.flush()?;
+        Ok(HistoryWriter { out, rounds: 0 })
+    }
+
+    /// Writes a finished round; `deck` is the deck order before it was dealt
+    pub fn record_round(&mut self, deck: Vec<Card>, round: &Round) -> io::Result<()> {
+        self.rounds += 1;
+        for entry in round_entries(self.rounds, deck, round) {
+            writeln!(self.out, "{}", entry)?;
+        }
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::banker::StandOn;
+    use crate::game::round::play_round;
+    use crate::game::rules::Rules;
+    use crate::game::strategy::StickOn;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer that keeps what was written so tests can read it back
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn played_round(seed: u64, stick: u8) -> (Vec<Card>, Round) {
+        let mut deck = Deck::new_seeded(seed);
+        let order = deck.cards_in_deal_order();
+        let player = StickOn { total: stick };
+        let (round, _) =
+            play_round(&mut deck, &Rules::standard(), 10, &player, &StandOn::default()).unwrap();
+        (order, round)
+    }
+
+    // Property: Every entry of a played round survives a write and parse
+    proptest! {
+        #[test]
+        fn prop_round_entries_round_trip(seed in any::<u64>(), stick in 15u8..=21) {
+            let (order, round) = played_round(seed, stick);
+            for entry in round_entries(1, order, &round) {
+                prop_assert_eq!(entry.to_string().parse::<Entry>(), Ok(entry));
+            }
+        }
+    }
+
+    // Property: The deals in a round's history are exactly the top cards of the deck, in order
+    proptest! {
+        #[test]
+        fn prop_deals_follow_deck_order(seed in any::<u64>(), stick in 15u8..=21) {
+            let (order, round) = played_round(seed, stick);
+            let dealt: Vec<Card> = round_entries(1, order.clone(), &round)
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    Entry::Deal { card, .. } => Some(card),
+                    _ => None,
+                })
+                .collect();
+            prop_assert_eq!(&dealt[..], &order[..dealt.len()]);
+            prop_assert_eq!(dealt.len(), round.player_hand().card_count() + round.banker_hand().card_count());
+        }
+    }
+
+    #[test]
+    fn test_writer_output_parses() {
+        let buffer = SharedBuffer::default();
+        let mut writer =
+            HistoryWriter::new(Box::new(buffer.clone()), 42, 1, "standard", "spanish").unwrap();
+        let (order, round) = played_round(42, 17);
+        writer.record_round(order, &round).unwrap();
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(text.starts_with(HEADER));
+        let entries = parse(&text).unwrap();
+        assert_eq!(
+            entries[0],
+            Entry::Game {
+                seed: 42,
+                decks: 1,
+                rules: "standard".to_string(),
+                deck_type: "spanish".to_string(),
+            }
+        );
+        assert_eq!(entries.last(), Some(&Entry::End));
+    }
+
+    #[test]
+    fn test_game_line_without_deck_is_standard() {
+        let entry: Entry = "game seed=7 decks=2 rules=no-trick".parse().unwrap();
+        assert_eq!(
+            entry,
+            Entry::Game {
+                seed: 7,
+                decks: 2,
+                rules: "no-trick".to_string(),
+                deck_type: "standard".to_string(),
+            }
+        );
+        assert!("game seed=7 decks=2 rules=no-trick jokers".parse::<Entry>().is_err());
+    }
+
+    #[test]
+    fn test_parse_reports_line_numbers() {
+        let error = parse("# comment\n\nend\nfold player\n").unwrap_err();
+        assert_eq!(error.line, 4);
+    }
+
+    #[test]
+    fn test_example_lines() {
+        assert_eq!(
+            "deal banker Qd down".parse::<Entry>(),
+            Ok(Entry::Deal {
+                seat: Seat::Banker,
+                card: "Qd".parse().unwrap(),
+                face_up: false
+            })
+        );
+        assert!("deal banker Qd sideways".parse::<Entry>().is_err());
+        assert!("end now".parse::<Entry>().is_err());
+    }
+}
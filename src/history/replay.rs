@@ -0,0 +1,375 @@
+use super::{round_entries, Entry};
+use crate::error::{PontoonError, Result};
+use crate::game::banker::StandOn;
+use crate::game::round::{Phase, Round, Seat};
+use crate::game::rules::{Rules, Settlement};
+use crate::game::strategy::Action;
+use crate::models::card::Card;
+use crate::models::deck::Deck;
+use crate::models::deck_type::DeckType;
+use crate::models::hand::Hand;
+use std::fmt;
+
+/// One round read back from a hand history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRound {
+    pub number: u32,
+    pub stake: u32,
+    /// Deck order before the deal, next card first
+    pub deck: Vec<Card>,
+    /// Every entry from the `round` line to the `end` line inclusive
+    pub entries: Vec<Entry>,
+}
+
+/// One game read back from a hand history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedGame {
+    pub seed: u64,
+    pub decks: usize,
+    pub rules: String,
+    pub deck_type: String,
+    pub rounds: Vec<RecordedRound>,
+}
+
+/// Groups parsed entries into games and rounds
+///
+/// A round left unfinished at the end of the file (e.g. after a crash) is dropped.
+pub fn games(entries: &[Entry]) -> Result<Vec<RecordedGame>> {
+    let mut games: Vec<RecordedGame> = Vec::new();
+    let mut current: Option<RecordedRound> = None;
+
+    for entry in entries {
+        match entry {
+            Entry::Game {
+                seed,
+                decks,
+                rules,
+                deck_type,
+            } => {
+                current = None;
+                games.push(RecordedGame {
+                    seed: *seed,
+                    decks: *decks,
+                    rules: rules.clone(),
+                    deck_type: deck_type.clone(),
+                    rounds: Vec::new(),
+                });
+            }
+            Entry::Round { number, stake, deck } => {
+                if games.is_empty() {
+                    return Err(PontoonError::ParseError(format!(
+                        "round {} appears before any game line",
+                        number
+                    )));
+                }
+                current = Some(RecordedRound {
+                    number: *number,
+                    stake: *stake,
+                    deck: deck.clone(),
+                    entries: vec![entry.clone()],
+                });
+            }
+            Entry::End => {
+                let mut round = current.take().ok_or_else(|| {
+                    PontoonError::ParseError("end appears outside a round".to_string())
+                })?;
+                round.entries.push(Entry::End);
+                games.last_mut().expect("a round implies a game").rounds.push(round);
+            }
+            _ => current
+                .as_mut()
+                .ok_or_else(|| {
+                    PontoonError::ParseError(format!("'{}' appears outside a round", entry))
+                })?
+                .entries
+                .push(entry.clone()),
+        }
+    }
+    Ok(games)
+}
+
+/// What the table looked like after a given entry of a round
+#[derive(Debug, Clone, Default)]
+pub struct TableState {
+    /// The player's hands in order, more than one only after a split
+    pub player: Vec<Hand>,
+    pub banker: Hand,
+    /// True while the banker still has a face-down card
+    pub banker_hidden: bool,
+    pub settlement: Option<Settlement>,
+}
+
+impl TableState {
+    /// Rebuilds the table from the first `steps` entries of a round
+    ///
+    /// A split moves the hand's second card to a new hand played next. The card after a
+    /// twist, double or split goes to the hand being played; any other card dealt to the
+    /// player while a split hand is waiting is that hand's second card, so play has
+    /// moved on to it.
+    pub fn after(round: &RecordedRound, steps: usize) -> Self {
+        let mut state = TableState::default();
+        let mut playing = 0;
+        let mut dealing_for_action = false;
+        for entry in round.entries.iter().take(steps) {
+            match entry {
+                Entry::Deal { seat: Seat::Player, card, .. } => {
+                    if state.player.is_empty() {
+                        state.player.push(Hand::new());
+                    } else if !dealing_for_action && playing + 1 < state.player.len() {
+                        playing += 1;
+                    }
+                    state.player[playing].add_card(*card);
+                    dealing_for_action = false;
+                }
+                Entry::Deal { seat: Seat::Banker, card, face_up } => {
+                    state.banker.add_card(*card);
+                    if !face_up {
+                        state.banker_hidden = true;
+                    }
+                }
+                Entry::Action { seat: Seat::Player, action } => {
+                    dealing_for_action =
+                        matches!(action, Action::Twist | Action::Double | Action::Split);
+                    if *action == Action::Split {
+                        let second = state.player.get_mut(playing).and_then(Hand::take_last);
+                        if let Some(card) = second {
+                            let mut split_off = Hand::new();
+                            split_off.add_card(card);
+                            state.player.insert(playing + 1, split_off);
+                        }
+                    }
+                }
+                Entry::Reveal { seat: Seat::Banker, .. } => state.banker_hidden = false,
+                Entry::Settle(settlement) => state.settlement = Some(*settlement),
+                _ => {}
+            }
+        }
+        state
+    }
+}
+
+/// A difference between a recorded round and the engine's re-run of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub game: usize,
+    pub round: u32,
+    pub recorded: Option<Entry>,
+    pub replayed: Option<Entry>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |entry: &Option<Entry>| match entry {
+            Some(entry) => entry.to_string(),
+            None => "(nothing)".to_string(),
+        };
+        write!(
+            f,
+            "game {} round {}: recorded '{}' but the engine gives '{}'",
+            self.game + 1,
+            self.round,
+            show(&self.recorded),
+            show(&self.replayed)
+        )
+    }
+}
+
+/// Re-runs a game from its seed with the recorded player decisions and the house banker,
+/// returning the first difference in each round that does not match
+///
+/// A `game` line the engine cannot play, with an unknown preset or no decks, is itself
+/// reported as the mismatch.
+pub fn verify(game_index: usize, game: &RecordedGame) -> Result<Vec<Mismatch>> {
+    let (Some(rules), Some(deck_type), 1..) =
+        (Rules::preset(&game.rules), DeckType::preset(&game.deck_type), game.decks)
+    else {
+        return Ok(vec![Mismatch {
+            game: game_index,
+            round: 0,
+            recorded: Some(Entry::Game {
+                seed: game.seed,
+                decks: game.decks,
+                rules: game.rules.clone(),
+                deck_type: game.deck_type.clone(),
+            }),
+            replayed: None,
+        }]);
+    };
+
+    let mut deck = Deck::of_type_seeded(&deck_type, game.decks, game.seed);
+    let mut mismatches = Vec::new();
+    for recorded in &game.rounds {
+        if deck.needs_reshuffle() {
+            deck.reshuffle();
+        }
+        let order = deck.cards_in_deal_order();
+        let round = replay_round(&mut deck, &rules, recorded)?;
+        let replayed = round_entries(recorded.number, order, &round);
+
+        let length = replayed.len().max(recorded.entries.len());
+        if let Some(index) = (0..length).find(|i| replayed.get(*i) != recorded.entries.get(*i)) {
+            mismatches.push(Mismatch {
+                game: game_index,
+                round: recorded.number,
+                recorded: recorded.entries.get(index).cloned(),
+                replayed: replayed.get(index).cloned(),
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Plays a round with the recorded player actions, stopping at the first one the engine refuses
+fn replay_round(deck: &mut Deck, rules: &Rules, recorded: &RecordedRound) -> Result<Round> {
+    let mut round = Round::deal(deck, rules.clone(), recorded.stake)?;
+    let player_actions = recorded.entries.iter().filter_map(|entry| match entry {
+        Entry::Action {
+            seat: Seat::Player,
+            action,
+        } => Some(*action),
+        _ => None,
+    });
+    for action in player_actions {
+        if round.phase() != Phase::PlayerTurn {
+            break;
+        }
+        match round.apply(deck, action) {
+            Err(PontoonError::IllegalAction { .. }) => break,
+            result => result?,
+        }
+    }
+    round.play_banker(deck, &StandOn::house(rules))?;
+    Ok(round)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::round::play_round;
+    use crate::game::strategy::BasicStrategy;
+    use crate::history::parse;
+    use proptest::prelude::*;
+
+    /// Plays a game the way the interactive controller does and returns its history text
+    fn recorded_game(seed: u64, rounds: u32) -> String {
+        recorded_game_of(&Rules::standard(), &DeckType::standard(), seed, rounds)
+    }
+
+    fn recorded_game_of(rules: &Rules, deck_type: &DeckType, seed: u64, rounds: u32) -> String {
+        let mut deck = Deck::of_type_seeded(deck_type, 1, seed);
+        let mut lines = vec![Entry::Game {
+            seed,
+            decks: 1,
+            rules: rules.name.to_string(),
+            deck_type: deck_type.name.to_string(),
+        }
+        .to_string()];
+        for number in 1..=rounds {
+            if deck.needs_reshuffle() {
+                deck.reshuffle();
+            }
+            let order = deck.cards_in_deal_order();
+            let (round, _) =
+                play_round(&mut deck, rules, 10, &BasicStrategy, &StandOn::house(rules)).unwrap();
+            lines.extend(round_entries(number, order, &round).iter().map(Entry::to_string));
+        }
+        lines.join("\n")
+    }
+
+    // Property: An untouched history always verifies against the engine
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+        #[test]
+        fn prop_recorded_games_verify(
+            seed in any::<u64>(),
+            rounds in 1u32..12,
+            preset in 0..DeckType::presets().len(),
+            rules in 0..Rules::presets().len(),
+        ) {
+            let (rules, deck_type) = (&Rules::presets()[rules], &DeckType::presets()[preset]);
+            let text = recorded_game_of(rules, deck_type, seed, rounds);
+            let games = games(&parse(&text).unwrap()).unwrap();
+            prop_assert_eq!(games.len(), 1);
+            prop_assert_eq!(games[0].rounds.len(), rounds as usize);
+            prop_assert!(verify(0, &games[0]).unwrap().is_empty());
+        }
+    }
+
+    // Property: The table after the last step shows the settled hands
+    proptest! {
+        #[test]
+        fn prop_final_state_is_settled(seed in any::<u64>()) {
+            let games = games(&parse(&recorded_game(seed, 1)).unwrap()).unwrap();
+            let round = &games[0].rounds[0];
+            let state = TableState::after(round, round.entries.len());
+            prop_assert!(state.settlement.is_some());
+            prop_assert!(!state.banker_hidden);
+            prop_assert!(state.player.iter().all(|hand| hand.card_count() >= 2));
+        }
+    }
+
+    // Property: The hands rebuilt from a recorded Blackjack round are the engine's, splits and all
+    proptest! {
+        #[test]
+        fn prop_split_hands_are_rebuilt(seed in any::<u64>()) {
+            let rules = Rules::blackjack();
+            let mut deck = Deck::new_seeded(seed);
+            let order = deck.cards_in_deal_order();
+            let (round, _) =
+                play_round(&mut deck, &rules, 10, &BasicStrategy, &StandOn::house(&rules)).unwrap();
+            let recorded = RecordedRound {
+                number: 1,
+                stake: 10,
+                deck: order.clone(),
+                entries: round_entries(1, order, &round),
+            };
+            let state = TableState::after(&recorded, recorded.entries.len());
+            prop_assert_eq!(state.player.iter().collect::<Vec<_>>(), round.player_hands());
+        }
+    }
+
+    #[test]
+    fn test_tampered_settlement_is_reported() {
+        // Claim a win in every round
+        let text = recorded_game(5, 3);
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| {
+                if line.starts_with("settle") {
+                    "settle win player=21 banker=20 net=99".to_string()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        let games = games(&parse(&lines.join("\n")).unwrap()).unwrap();
+        let mismatches = verify(0, &games[0]).unwrap();
+        assert_eq!(mismatches.len(), 3);
+        assert!(matches!(mismatches[0].recorded, Some(Entry::Settle(_))));
+    }
+
+    #[test]
+    fn test_game_without_decks_is_reported() {
+        let text = recorded_game(5, 2).replacen("decks=1", "decks=0", 1);
+        let games = games(&parse(&text).unwrap()).unwrap();
+        let mismatches = verify(0, &games[0]).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert!(matches!(mismatches[0].recorded, Some(Entry::Game { decks: 0, .. })));
+    }
+
+    #[test]
+    fn test_hidden_card_until_reveal() {
+        let games = games(&parse(&recorded_game(11, 1)).unwrap()).unwrap();
+        let round = &games[0].rounds[0];
+        assert!(TableState::after(round, 5).banker_hidden);
+        assert!(!TableState::after(round, 4).banker_hidden);
+    }
+
+    #[test]
+    fn test_unfinished_round_is_dropped() {
+        let text = recorded_game(2, 2);
+        let cut = &text[..text.rfind("end").unwrap()];
+        let games = games(&parse(cut).unwrap()).unwrap();
+        assert_eq!(games[0].rounds.len(), 1);
+    }
+}
@@ -1,3 +1,13 @@
 pub mod models;
+pub mod bot;
+pub mod controller;
+pub mod drill;
+pub mod error;
+pub mod freezeout;
 pub mod game;
+pub mod history;
+pub mod logging;
+pub mod net;
+pub mod sim;
 pub mod ui;
+pub mod viewer;
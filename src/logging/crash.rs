@@ -0,0 +1,132 @@
+use super::{data_dir, timestamp, Level, Record};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Exit code used after a crash report has been written
+pub const CRASH_EXIT_CODE: i32 = 101;
+
+/// What is known about the program when it panicked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    /// Time since the Unix epoch
+    pub time: Duration,
+    pub version: String,
+    /// The deck seed in play, if one was noted
+    pub seed: Option<u64>,
+    pub message: String,
+    /// File, line and column of the panic
+    pub location: Option<String>,
+    /// The last log records before the panic, oldest first
+    pub recent: Vec<String>,
+}
+
+impl CrashReport {
+    fn from_panic(info: &PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        // The logger may be locked by the code that panicked, so never wait for it
+        let (seed, recent) = match super::LOGGER.try_lock() {
+            Ok(logger) => (logger.seed, logger.recent.iter().cloned().collect()),
+            Err(_) => (None, Vec::new()),
+        };
+        CrashReport {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            message,
+            location: info.location().map(|at| at.to_string()),
+            recent,
+        }
+    }
+
+    /// Writes the report to `crash-<time>.txt` in `dir`, returning its path
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("crash-{}.txt", self.time.as_millis()));
+        fs::write(&path, self.to_string())?;
+        Ok(path)
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Pontoon crash report")?;
+        writeln!(f, "time: {}", timestamp(self.time))?;
+        writeln!(f, "version: {}", self.version)?;
+        match self.seed {
+            Some(seed) => writeln!(f, "seed: {}", seed)?,
+            None => writeln!(f, "seed: unknown")?,
+        }
+        writeln!(f, "panic: {}", self.message)?;
+        if let Some(location) = &self.location {
+            writeln!(f, "at: {}", location)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Recent events ({}):", self.recent.len())?;
+        for line in &self.recent {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the default panic message with a crash report
+///
+/// On a panic the report is written to the data directory and logged, the
+/// player is told where to find it, and the process exits with
+/// [`CRASH_EXIT_CODE`] instead of printing a backtrace.
+pub fn install() {
+    panic::set_hook(Box::new(|info| {
+        let report = CrashReport::from_panic(info);
+        if super::LOGGER.try_lock().is_ok() {
+            super::log(Record::new(Level::Error, "panic", &report.message));
+        }
+        let written = data_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))
+            .and_then(|dir| report.write_to(&dir));
+        eprintln!("\nSorry, Pontoon hit a problem it could not recover from: {}", report.message);
+        match written {
+            Ok(path) => eprintln!("A crash report was saved to {}", path.display()),
+            Err(error) => eprintln!("The crash report could not be saved ({}):\n\n{}", error, report),
+        }
+        process::exit(CRASH_EXIT_CODE);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_holds_version_seed_and_recent_events() {
+        let report = CrashReport {
+            time: Duration::from_secs(1_700_000_000),
+            version: "0.1.0".to_string(),
+            seed: Some(42),
+            message: "Deck should have cards".to_string(),
+            location: Some("src/game/round.rs:12:5".to_string()),
+            recent: vec!["ts=... msg=\"card dealt\"".to_string()],
+        };
+        assert_eq!(
+            report.to_string(),
+            "Pontoon crash report\n\
+             time: 2023-11-14T22:13:20.000Z\n\
+             version: 0.1.0\n\
+             seed: 42\n\
+             panic: Deck should have cards\n\
+             at: src/game/round.rs:12:5\n\
+             \n\
+             Recent events (1):\n\
+             ts=... msg=\"card dealt\"\n"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A log file that is rolled over once it grows past a size
+///
+/// When a line would take `pontoon.log` past `max_bytes`, it is renamed to
+/// `pontoon.log.1`, the older files move up one number and the oldest past
+/// `keep` is deleted. Lines are never split across files.
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// Opens `path` for appending, creating it and its directory if needed
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_bytes,
+            keep,
+            file,
+            size,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path of the `n`th older file; 0 is the current one
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        numbered(&self.path, n)
+    }
+
+    /// Appends one line, rolling the files over first if it would not fit
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+        let oldest = self.rotated_path(self.keep);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for n in (1..self.keep).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", n));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A fresh directory under the system temp dir for one test case
+    fn scratch_dir() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "pontoon-log-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // Property: Rotation keeps at most `keep` old files, never splits a line, and
+    // loses only the oldest lines
+    proptest! {
+        #[test]
+        fn prop_rotation_keeps_recent_whole_lines(
+            lengths in prop::collection::vec(1usize..40, 1..80),
+            max_bytes in 20u64..200,
+            keep in 0usize..4
+        ) {
+            let dir = scratch_dir();
+            let mut file = RotatingFile::open(dir.join("pontoon.log"), max_bytes, keep).unwrap();
+            let lines: Vec<String> = lengths
+                .iter()
+                .enumerate()
+                .map(|(index, len)| format!("{:04}{}", index, "x".repeat(*len)))
+                .collect();
+            for line in &lines {
+                file.write_line(line).unwrap();
+            }
+
+            let mut kept = Vec::new();
+            for n in (0..=keep).rev() {
+                if let Ok(text) = fs::read_to_string(file.rotated_path(n)) {
+                    let file_lines: Vec<&str> = text.lines().collect();
+                    prop_assert!(text.len() as u64 <= max_bytes || file_lines.len() == 1);
+                    kept.extend(file_lines.into_iter().map(str::to_string));
+                }
+            }
+            prop_assert!(!file.rotated_path(keep + 1).exists());
+            prop_assert!(!kept.is_empty());
+            prop_assert_eq!(&kept[..], &lines[lines.len() - kept.len()..]);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reopening_appends_to_the_current_file() {
+        let dir = scratch_dir();
+        let path = dir.join("pontoon.log");
+        RotatingFile::open(&path, 1_000, 2).unwrap().write_line("first").unwrap();
+        RotatingFile::open(&path, 1_000, 2).unwrap().write_line("second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,440 @@
+//! Structured logging to a rotating file, and crash reports
+//!
+//! Each record is one line of `key=value` pairs (logfmt), so the log can be read
+//! by eye or searched with grep. Records go to `pontoon.log` in the user's data
+//! directory once [`init`] has been called; the most recent ones are also kept
+//! in memory for the crash report written by [`crash::install`].
+
+pub mod crash;
+pub mod file;
+
+use crate::game::event::{EventListener, GameEvent};
+use crate::history::{action_name, outcome_name, rank_name, seat_name};
+use file::RotatingFile;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many records are kept in memory for crash reports
+pub const RECENT_RECORDS: usize = 50;
+
+/// How much a record matters; a logger writes its level and everything above it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Error => write!(f, "error"),
+            Level::Warn => write!(f, "warn"),
+            Level::Info => write!(f, "info"),
+            Level::Debug => write!(f, "debug"),
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            other => Err(format!("unknown log level '{}' (use error, warn, info or debug)", other)),
+        }
+    }
+}
+
+/// One line of the log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Time since the Unix epoch
+    pub time: Duration,
+    pub level: Level,
+    /// The part of the program the record comes from, e.g. `engine` or `server`
+    pub target: String,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Record {
+    /// A record stamped with the current time
+    pub fn new(level: Level, target: &str, message: &str) -> Self {
+        Record {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+            level,
+            target: target.to_string(),
+            message: message.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, key: &str, value: impl fmt::Display) -> Self {
+        self.fields.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ts={} level={} target={} msg={}",
+            timestamp(self.time),
+            self.level,
+            quote(&self.target),
+            quote(&self.message)
+        )?;
+        for (key, value) in &self.fields {
+            write!(f, " {}={}", key, quote(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes a value if it is empty or holds spaces, quotes, `=` or control characters
+pub fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.chars().all(|c| !c.is_whitespace() && !c.is_control() && c != '"' && c != '=');
+    if plain {
+        return value.to_string();
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Formats a time since the Unix epoch as UTC, e.g. `2023-11-14T22:13:20.000Z`
+pub fn timestamp(time: Duration) -> String {
+    let secs = time.as_secs();
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    let of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        of_day / 3_600,
+        of_day % 3_600 / 60,
+        of_day % 60,
+        time.subsec_millis()
+    )
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) date
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days-from-civil inverse, counting in 400-year eras from March
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Where and how much to log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    pub dir: PathBuf,
+    pub level: Level,
+    /// Size at which `pontoon.log` is rolled over
+    pub max_bytes: u64,
+    /// Rolled-over files to keep besides the current one
+    pub keep: usize,
+}
+
+impl LogConfig {
+    /// Logs at `info` to the user's data directory, or the level in `$PONTOON_LOG`
+    pub fn from_env() -> Option<Self> {
+        let level = std::env::var("PONTOON_LOG")
+            .ok()
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(Level::Info);
+        Some(LogConfig {
+            dir: data_dir()?,
+            level,
+            max_bytes: 1024 * 1024,
+            keep: 4,
+        })
+    }
+}
+
+/// Returns the directory Pontoon keeps its logs and crash reports in
+///
+/// `$PONTOON_DATA_DIR` wins if set; otherwise the platform's usual place for
+/// application data, e.g. `~/.local/share/pontoon` on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = var("PONTOON_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = if cfg!(windows) {
+        PathBuf::from(var("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var("HOME")?).join("Library/Application Support")
+    } else {
+        match var("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(var("HOME")?).join(".local/share"),
+        }
+    };
+    Some(base.join("pontoon"))
+}
+
+struct Logger {
+    file: Option<RotatingFile>,
+    level: Level,
+    recent: VecDeque<String>,
+    seed: Option<u64>,
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    file: None,
+    level: Level::Info,
+    recent: VecDeque::new(),
+    seed: None,
+});
+
+fn with_logger<T>(f: impl FnOnce(&mut Logger) -> T) -> T {
+    let mut logger = LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(&mut logger)
+}
+
+/// Starts writing records to `pontoon.log` in the configured directory
+///
+/// Returns the path of the log file. Until this is called records are only
+/// kept in memory.
+pub fn init(config: &LogConfig) -> io::Result<PathBuf> {
+    let file = RotatingFile::open(config.dir.join("pontoon.log"), config.max_bytes, config.keep)?;
+    let path = file.path().to_path_buf();
+    with_logger(|logger| {
+        logger.file = Some(file);
+        logger.level = config.level;
+    });
+    Ok(path)
+}
+
+/// Logs a record, writing it to the file if its level is enabled
+///
+/// A log file that cannot be written to is dropped rather than failing the game.
+pub fn log(record: Record) {
+    let line = record.to_string();
+    with_logger(|logger| {
+        if logger.recent.len() == RECENT_RECORDS {
+            logger.recent.pop_front();
+        }
+        logger.recent.push_back(line.clone());
+        if record.level <= logger.level {
+            let failed = logger.file.as_mut().is_some_and(|file| file.write_line(&line).is_err());
+            if failed {
+                logger.file = None;
+            }
+        }
+    });
+}
+
+pub fn error(target: &str, message: &str) {
+    log(Record::new(Level::Error, target, message));
+}
+
+pub fn warn(target: &str, message: &str) {
+    log(Record::new(Level::Warn, target, message));
+}
+
+pub fn info(target: &str, message: &str) {
+    log(Record::new(Level::Info, target, message));
+}
+
+/// Notes the deck seed in play, so a crash can be reproduced
+pub fn set_seed(seed: u64) {
+    with_logger(|logger| logger.seed = Some(seed));
+    log(Record::new(Level::Info, "engine", "seed").with("seed", seed));
+}
+
+/// The most recent records, oldest first
+pub fn recent() -> Vec<String> {
+    with_logger(|logger| logger.recent.iter().cloned().collect())
+}
+
+/// Logs every engine event at `info`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogListener;
+
+impl EventListener for LogListener {
+    fn on_event(&mut self, event: &GameEvent) {
+        log(event_record(event));
+    }
+}
+
+/// Describes an engine event as a record
+pub fn event_record(event: &GameEvent) -> Record {
+    let record = |message: &str| Record::new(Level::Info, "engine", message);
+    let cards = |cards: &[crate::models::card::Card]| {
+        cards.iter().map(|card| card.notation()).collect::<Vec<_>>().join(",")
+    };
+    match event {
+        GameEvent::DeckReshuffled { cards } => record("deck reshuffled").with("cards", cards),
+        GameEvent::ShoeReplenished { cards } => record("shoe replenished").with("cards", cards),
+        GameEvent::RoundStarted { stake, practice } => {
+            record("round started").with("stake", stake).with("practice", practice)
+        }
+        GameEvent::CardDealt {
+            seat,
+            card,
+            face_up,
+        } => record("card dealt")
+            .with("seat", seat_name(*seat))
+            .with("card", card.notation())
+            .with("face_up", face_up),
+        GameEvent::TurnStarted { seat } => record("turn started").with("seat", seat_name(*seat)),
+        GameEvent::SplitHandStarted => record("split hand started"),
+        GameEvent::ActionTaken { seat, action } => record("action taken")
+            .with("seat", seat_name(*seat))
+            .with("action", action_name(*action)),
+        GameEvent::CardRevealed { seat, card } => record("card revealed")
+            .with("seat", seat_name(*seat))
+            .with("card", card.notation()),
+        GameEvent::HandBust { seat } => record("hand bust").with("seat", seat_name(*seat)),
+        GameEvent::RoundSettled(settlement) => record("round settled")
+            .with("outcome", outcome_name(settlement.outcome))
+            .with("player", rank_name(settlement.player_rank))
+            .with("banker", rank_name(settlement.banker_rank))
+            .with("net", settlement.net),
+        GameEvent::ActionUndone {
+            seat,
+            action,
+            returned,
+            ..
+        } => record("action undone")
+            .with("seat", seat_name(*seat))
+            .with("action", action_name(*action))
+            .with("returned", cards(returned)),
+        GameEvent::ActionRedone {
+            seat,
+            action,
+            dealt,
+            ..
+        } => record("action redone")
+            .with("seat", seat_name(*seat))
+            .with("action", action_name(*action))
+            .with("dealt", cards(dealt)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::round::Seat;
+    use crate::models::card::{Card, Rank, Suit};
+    use proptest::prelude::*;
+
+    /// Undoes `quote`, returning None if the text is not a single value
+    fn unquote(text: &str) -> Option<String> {
+        let Some(inner) = text.strip_prefix('"') else {
+            return Some(text.to_string());
+        };
+        let inner = inner.strip_suffix('"')?;
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return None,
+                '\\' => match chars.next()? {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'u' => {
+                        let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                        value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                    }
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        Some(value)
+    }
+
+    // Property: A record is always one line, and each value reads back as written
+    proptest! {
+        #[test]
+        fn prop_records_are_single_lines(message in any::<String>(), value in any::<String>()) {
+            let line = Record::new(Level::Warn, "test", &message).with("value", &value).to_string();
+            prop_assert!(!line.contains('\n') && !line.contains('\r'));
+            let quoted_value = quote(&value);
+            let ending = format!(" value={}", quoted_value);
+            prop_assert!(line.ends_with(&ending));
+            prop_assert_eq!(unquote(&quote(&message)), Some(message));
+            prop_assert_eq!(unquote(&quoted_value), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_record_format() {
+        let record = Record {
+            time: Duration::from_millis(1_700_000_000_250),
+            level: Level::Info,
+            target: "engine".to_string(),
+            message: "card dealt".to_string(),
+            fields: Vec::new(),
+        };
+        let event = GameEvent::CardDealt {
+            seat: Seat::Banker,
+            card: Card::new(Rank::Ace, Suit::Hearts),
+            face_up: false,
+        };
+        let record = Record {
+            fields: event_record(&event).fields,
+            ..record
+        };
+        assert_eq!(
+            record.to_string(),
+            "ts=2023-11-14T22:13:20.250Z level=info target=engine msg=\"card dealt\" \
+             seat=banker card=Ah face_up=false"
+        );
+    }
+
+    #[test]
+    fn test_timestamps_are_utc_dates() {
+        assert_eq!(timestamp(Duration::ZERO), "1970-01-01T00:00:00.000Z");
+        assert_eq!(timestamp(Duration::from_secs(951_782_400)), "2000-02-29T00:00:00.000Z");
+        assert_eq!(timestamp(Duration::from_secs(4_102_444_799)), "2099-12-31T23:59:59.000Z");
+    }
+
+    #[test]
+    fn test_recent_records_are_bounded() {
+        for n in 0..RECENT_RECORDS + 10 {
+            log(Record::new(Level::Debug, "test", "filler").with("n", n));
+        }
+        let recent = recent();
+        assert_eq!(recent.len(), RECENT_RECORDS);
+    }
+}
@@ -1,35 +1,518 @@
+use pontoon::bot::process::{ExternalBot, DEFAULT_TIMEOUT};
+use pontoon::bot::{play_match, MatchConfig};
+use pontoon::controller::GameController;
+use pontoon::drill::{CountDrill, DrillConfig};
+use pontoon::error::{PontoonError, Result};
+use pontoon::freezeout::FreezeoutGame;
+use pontoon::game::freezeout::{FreezeoutConfig, StakeSchedule};
+use pontoon::game::counting::{system_by_name, systems};
+use pontoon::game::rules::Rules;
+use pontoon::game::strategy::{strategy_by_name, strategy_names};
+use pontoon::game::timer::{TimeoutAction, TurnLimit};
+use pontoon::history::replay;
+use pontoon::history::{self, HistoryWriter};
+use pontoon::logging::{self, Level, LogConfig, LogListener, Record};
 use pontoon::models::deck::Deck;
-use pontoon::models::hand::Hand;
+use pontoon::models::deck_type::DeckType;
+use pontoon::net::client::Client;
+use pontoon::net::server::{Server, ServerConfig, DEFAULT_PORT};
 use pontoon::ui::display::Display;
+use pontoon::ui::input::{Input, TimedInput};
+use pontoon::viewer::ReplayViewer;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "Usage:
+  pontoon [GAME OPTIONS]      Play Pontoon against the banker, or Blackjack
+                              against the dealer with --rules blackjack
+  pontoon drill [OPTIONS]     Practise card counting
+  pontoon freezeout [OPTIONS] Play a tournament against built-in strategies
+  pontoon replay <FILE> [--verify-only]
+                              Step through a hand history, checking it against the engine
+  pontoon bot [OPTIONS] <PROGRAM> [ARGS...]
+                              Play a bot program over the protocol in BOT_PROTOCOL.md
+  pontoon serve [OPTIONS]     Host a table for players on the local network
+  pontoon join <HOST[:PORT]> [--name <NAME>]
+                              Take a seat at a hosted table
+  pontoon watch <HOST[:PORT]> Watch a hosted table without playing
+
+Game options:
+  --rules <NAME>    Rule preset, from the list below (default standard)
+  --history <FILE>  Append every round to a hand history file
+  --practice        Allow undo/redo of moves; practice hands are not scored
+  --odds            Show the unseen cards and the odds of each rank before each move
+  --seed <N>        Shoe seed, to deal the same game again (default: random)
+  --deck <NAME>     Deck preset: standard, spanish (no tens) or jokers (two wild
+                    jokers) (default standard)
+
+Drill options:
+  --system <NAME>   Counting system (default hi-lo)
+  --decks <N>       Decks in the shoe (default 6)
+  --cards <N>       Cards to flash (default 60)
+  --every <N>       Ask for the count every N cards (default 10)
+  --delay-ms <N>    How long each card is shown (default 700)
+
+Freezeout options:
+  --chips <N>       Chips every player starts with (default 500)
+  --levels <LIST>   Minimum stake at each level (default 10,20,30,50,75,100,150,200,300,500)
+  --every <N>       Rounds played at each level (default 10)
+  --opponents <LIST>
+                    Strategies to play against (default basic,stick-on-16,stick-on-18)
+  --seed <N>        Shoe seed (default: random)
+  --decks <N>       Decks in the shoe (default 1)
+  --rules <NAME>    Rule preset without Blackjack options (default standard)
+  --max-rounds <N>  End the tournament after N rounds (default: never)
+  --turn-seconds <N>
+                    Time allowed for each decision (default: no limit)
+  --on-timeout <stick|advisor>
+                    Move played for you when out of time (default stick)
+
+Bot options:
+  --rounds <N>      Rounds to play (default 1000)
+  --seed <N>        Shoe seed (default 0)
+  --decks <N>       Decks in the shoe (default 1)
+  --rules <NAME>    Rule preset (default standard)
+  --timeout-ms <N>  Time allowed for each answer (default 1000)
+  --max-faults <N>  Refused answers allowed before disqualification (default 10)
+
+Serve options:
+  --bind <ADDR>     Address to listen on (default 0.0.0.0)
+  --port <N>        Port to listen on (default 7021)
+  --decks <N>       Decks in the shoe (default 1)
+  --rules <NAME>    Rule preset without Blackjack options (default standard)
+  --stake <N>       Stake on every hand (default 10)
+  --players <N>     Players to wait for before the first deal (default 1)
+  --rounds <N>      Close the table after N rounds (default: never)
+  --spectator-delay-ms <N>
+                    Run the spectator feed N ms behind play, showing hole
+                    cards once the hand is over (default 0: live)
+  --turn-seconds <N>
+                    Time allowed for each decision (default: no limit)
+  --on-timeout <stick|advisor>
+                    Move played for a player out of time (default stick)";
 
 fn main() {
-    // Create display
+    logging::crash::install();
+    if let Some(config) = LogConfig::from_env() {
+        // Playing on without a log file is better than refusing to start
+        let _ = logging::init(&config);
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    logging::log(
+        Record::new(Level::Info, "cli", "started")
+            .with("version", env!("CARGO_PKG_VERSION"))
+            .with("args", args.join(" ")),
+    );
+    let result = match args.first().map(String::as_str) {
+        Some("drill") => drill(&args[1..]),
+        Some("freezeout") => freezeout(&args[1..]),
+        Some("replay") => replay(&args[1..]),
+        Some("bot") => bot(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        Some("join") => join(&args[1..]),
+        Some("watch") => watch(&args[1..]),
+        Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            println!("\nRule presets:");
+            for rules in Rules::presets() {
+                println!("  {:<12}      {}", rules.name, rules.description);
+            }
+            Ok(())
+        }
+        _ => play(&args),
+    };
+    if let Err(error) = result {
+        logging::error("cli", &error.to_string());
+        eprintln!("{}", explain(&error));
+        process::exit(error.exit_code());
+    }
+}
+
+/// Puts an error into words for the player
+fn explain(error: &PontoonError) -> String {
+    match error {
+        PontoonError::DeckExhausted => {
+            "The shoe ran out of cards part way through a round; try again with more decks."
+                .to_string()
+        }
+        PontoonError::IllegalAction { .. } => format!("The game could not go on: {}.", error),
+        PontoonError::InsufficientFunds { .. } => format!("Not enough chips to play: {}.", error),
+        PontoonError::ParseError(_) => format!("Could not understand the input: {}", error),
+        PontoonError::InvalidConfig(_) => format!("Invalid settings: {}", error),
+        PontoonError::Protocol(_) => format!("The other side did not follow the rules: {}", error),
+        PontoonError::Io(_) => format!("Could not read or write: {}", error),
+    }
+}
+
+/// Names what was being opened, read or reached when an I/O error happened
+fn about<E: Into<PontoonError>>(what: &str) -> impl Fn(E) -> PontoonError + '_ {
+    move |error| match error.into() {
+        PontoonError::Io(error) => {
+            io::Error::new(error.kind(), format!("{}: {}", what, error)).into()
+        }
+        error => error,
+    }
+}
+
+fn play(args: &[String]) -> Result<()> {
+    let mut history_path = None;
+    let mut practice = false;
+    let mut odds = false;
+    let mut seed = None;
+    let mut deck_type = DeckType::standard();
+    let mut rules = Rules::standard();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--history" => {
+                history_path = Some(
+                    args.next()
+                        .unwrap_or_else(|| fail("Missing value for --history"))
+                        .clone(),
+                )
+            }
+            "--rules" => {
+                let value = args.next().unwrap_or_else(|| fail("Missing value for --rules"));
+                rules = Rules::preset(value)
+                    .unwrap_or_else(|| fail(&format!("Unknown rules preset '{}'", value)));
+            }
+            "--practice" => practice = true,
+            "--odds" => odds = true,
+            "--seed" => {
+                let value = args.next().unwrap_or_else(|| fail("Missing value for --seed"));
+                seed = Some(parse_number(flag, value));
+            }
+            "--deck" => {
+                let value = args.next().unwrap_or_else(|| fail("Missing value for --deck"));
+                deck_type = DeckType::preset(value)
+                    .unwrap_or_else(|| fail(&format!("Unknown deck preset '{}'", value)));
+            }
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+
+    let deck = match seed {
+        Some(seed) => Deck::of_type_seeded(&deck_type, 1, seed),
+        None => Deck::of_type(&deck_type, 1),
+    };
+    let history = match history_path {
+        Some(path) => Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|file| {
+                    let (seed, decks) = (deck.seed(), deck.deck_count());
+                    HistoryWriter::new(Box::new(file), seed, decks, rules.name, deck_type.name)
+                })
+                .map_err(about(&format!("hand history '{}'", path)))?,
+        ),
+        None => None,
+    };
+
+    logging::set_seed(deck.seed());
+    let mut game = GameController::new(Display::new(), Input::stdin(), deck, rules);
+    game.subscribe(Box::new(LogListener));
+    if let Some(history) = history {
+        game = game.with_history(history);
+    }
+    if practice {
+        game = game.with_practice();
+    }
+    if odds {
+        game = game.with_odds();
+    }
+    game.run()
+}
+
+fn drill(args: &[String]) -> Result<()> {
+    let mut config = DrillConfig::default();
+    let mut decks = 6;
+    let mut system_name = "hi-lo".to_string();
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| fail(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--system" => system_name = value.clone(),
+            "--decks" => decks = parse_number(flag, value),
+            "--cards" => config.cards = parse_number(flag, value),
+            "--every" => config.quiz_every = parse_number(flag, value),
+            "--delay-ms" => config.delay = Duration::from_millis(parse_number(flag, value)),
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+    if decks == 0 {
+        fail("--decks must be at least 1");
+    }
+
+    let system = system_by_name(&system_name).unwrap_or_else(|| {
+        let names: Vec<_> = systems().iter().map(|system| system.name()).collect();
+        fail(&format!(
+            "Unknown counting system '{}' (choose from {})",
+            system_name,
+            names.join(", ")
+        ))
+    });
+
+    let deck = Deck::new_shoe(decks);
+    let mut drill = CountDrill::new(Display::new(), Input::stdin(), deck, system, config);
+    drill.run()?;
+    Ok(())
+}
+
+fn freezeout(args: &[String]) -> Result<()> {
+    let mut config = FreezeoutConfig::default();
+    let mut levels = config.schedule.levels().to_vec();
+    let mut every = config.schedule.rounds_per_level();
+    let mut opponents = "basic,stick-on-16,stick-on-18".to_string();
+    let mut turn_seconds: Option<u64> = None;
+    let mut on_timeout = TimeoutAction::Stick;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| fail(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--chips" => config.starting_chips = parse_number(flag, value),
+            "--levels" => {
+                levels = value.split(',').map(|level| parse_number(flag, level)).collect()
+            }
+            "--every" => every = parse_number(flag, value),
+            "--opponents" => opponents = value.clone(),
+            "--seed" => config.seed = Some(parse_number(flag, value)),
+            "--decks" => config.decks = parse_number(flag, value),
+            "--rules" => {
+                config.rules = Rules::preset(value)
+                    .unwrap_or_else(|| fail(&format!("Unknown rules preset '{}'", value)))
+            }
+            "--max-rounds" => config.max_rounds = Some(parse_number(flag, value)),
+            "--turn-seconds" => turn_seconds = Some(parse_number(flag, value)),
+            "--on-timeout" => {
+                on_timeout = value.parse().unwrap_or_else(|reason: String| fail(&reason))
+            }
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+    if config.decks == 0 {
+        fail("--decks must be at least 1");
+    }
+    if turn_seconds == Some(0) {
+        fail("--turn-seconds must be at least 1");
+    }
+    config.turn_limit =
+        turn_seconds.map(|seconds| TurnLimit::new(Duration::from_secs(seconds), on_timeout));
+    config.schedule = StakeSchedule::new(levels, every)?;
+    let opponents: Vec<_> = opponents
+        .split(',')
+        .map(|name| {
+            strategy_by_name(name).unwrap_or_else(|| {
+                fail(&format!(
+                    "Unknown strategy '{}' (choose from {})",
+                    name,
+                    strategy_names().join(", ")
+                ))
+            })
+        })
+        .collect();
+    // Seats run from 1 to 6, and the player takes the first
+    if opponents.len() > 5 {
+        fail("A tournament table has room for at most 5 opponents");
+    }
+
+    if let Some(seed) = config.seed {
+        logging::set_seed(seed);
+    }
+    FreezeoutGame::new(Display::new(), TimedInput::stdin(), config, opponents)?.run()?;
+    Ok(())
+}
+
+fn replay(args: &[String]) -> Result<()> {
+    let (path, verify_only) = match args {
+        [path] => (path, false),
+        [path, flag] if flag == "--verify-only" => (path, true),
+        _ => fail("replay needs a hand history file"),
+    };
+
+    let in_file = |error| PontoonError::ParseError(format!("{}: {}", path, error));
+    let text = fs::read_to_string(path).map_err(about(&format!("'{}'", path)))?;
+    let entries = history::parse(&text).map_err(|error| in_file(error.to_string()))?;
+    let games = replay::games(&entries).map_err(|error| in_file(error.to_string()))?;
+
     let display = Display::new();
-    
-    // Show welcome
-    display.show_welcome();
-    display.show_message("Welcome to Pontoon!");
-    display.show_message("Dealing initial cards...");
-    
-    // Create and shuffle deck
-    let mut deck = Deck::new();
-    
-    // Create hands for player and banker
-    let mut player_hand = Hand::new();
-    let mut banker_hand = Hand::new();
-    
-    // Deal initial cards (2 to each)
-    player_hand.add_card(deck.deal().expect("Deck should have cards"));
-    banker_hand.add_card(deck.deal().expect("Deck should have cards"));
-    player_hand.add_card(deck.deal().expect("Deck should have cards"));
-    banker_hand.add_card(deck.deal().expect("Deck should have cards"));
-    
-    // Display hands
-    display.show_separator();
-    display.show_player_hand(&player_hand);
-    display.show_banker_hand_hidden(&banker_hand);
-    display.show_separator();
-    
-    display.show_message(&format!("\nCards remaining in deck: {}", deck.cards_remaining()));
-    display.show_message("\n[Story 1 Demo Complete - Initial Deal Working!]");
+    let mut mismatches = Vec::new();
+    for (index, game) in games.iter().enumerate() {
+        mismatches.extend(replay::verify(index, game)?);
+    }
+    let rounds: usize = games.iter().map(|game| game.rounds.len()).sum();
+    if mismatches.is_empty() {
+        display.show_message(&format!("✔ All {} recorded rounds match the engine.", rounds));
+    } else {
+        display.show_message(&format!("✘ {} of {} rounds differ from the engine:", mismatches.len(), rounds));
+        for mismatch in &mismatches {
+            display.show_message(&format!("  {}", mismatch));
+        }
+    }
+
+    if verify_only {
+        process::exit(if mismatches.is_empty() { 0 } else { 1 });
+    }
+    ReplayViewer::new(display, Input::stdin(), games).run();
+    Ok(())
+}
+
+fn bot(args: &[String]) -> Result<()> {
+    let mut config = MatchConfig::default();
+    let mut timeout = DEFAULT_TIMEOUT;
+
+    // Options come first; the program and its own arguments follow
+    let mut rest = args;
+    while let [flag, tail @ ..] = rest {
+        if flag == "--" {
+            rest = tail;
+            break;
+        }
+        if !flag.starts_with("--") {
+            break;
+        }
+        let [value, tail @ ..] = tail else {
+            fail(&format!("Missing value for {}", flag))
+        };
+        match flag.as_str() {
+            "--rounds" => config.rounds = parse_number(flag, value),
+            "--seed" => config.seed = parse_number(flag, value),
+            "--decks" => config.decks = parse_number(flag, value),
+            "--rules" => {
+                config.rules =
+                    Rules::preset(value).unwrap_or_else(|| fail(&format!("Unknown rules preset '{}'", value)))
+            }
+            "--timeout-ms" => timeout = Duration::from_millis(parse_number(flag, value)),
+            "--max-faults" => config.max_faults = parse_number(flag, value),
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+        rest = tail;
+    }
+    if config.decks == 0 {
+        fail("--decks must be at least 1");
+    }
+    let [program, program_args @ ..] = rest else {
+        fail("bot needs a program to run")
+    };
+
+    let mut bot = ExternalBot::spawn(program, program_args, timeout)
+        .map_err(about(&format!("could not start '{}'", program)))?;
+    logging::set_seed(config.seed);
+    let report = play_match(&mut bot, &config)?;
+    print!("{}", report);
+    if report.disqualified.is_some() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn serve(args: &[String]) -> Result<()> {
+    let mut config = ServerConfig::default();
+    let mut bind = "0.0.0.0".to_string();
+    let mut port = DEFAULT_PORT;
+    let mut turn_seconds: Option<u64> = None;
+    let mut on_timeout = TimeoutAction::Stick;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| fail(&format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--bind" => bind = value.clone(),
+            "--port" => port = parse_number(flag, value),
+            "--decks" => config.decks = parse_number(flag, value),
+            "--rules" => {
+                config.rules =
+                    Rules::preset(value).unwrap_or_else(|| fail(&format!("Unknown rules preset '{}'", value)))
+            }
+            "--stake" => config.stake = parse_number(flag, value),
+            "--players" => config.min_players = parse_number(flag, value),
+            "--rounds" => config.rounds = Some(parse_number(flag, value)),
+            "--spectator-delay-ms" => {
+                config.spectator_delay = Duration::from_millis(parse_number(flag, value))
+            }
+            "--turn-seconds" => turn_seconds = Some(parse_number(flag, value)),
+            "--on-timeout" => {
+                on_timeout = value.parse().unwrap_or_else(|reason: String| fail(&reason))
+            }
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+    if config.decks == 0 {
+        fail("--decks must be at least 1");
+    }
+    if config.min_players > config.max_players {
+        fail(&format!("--players must be at most {}", config.max_players));
+    }
+    if turn_seconds == Some(0) {
+        fail("--turn-seconds must be at least 1");
+    }
+    config.turn_limit =
+        turn_seconds.map(|seconds| TurnLimit::new(Duration::from_secs(seconds), on_timeout));
+
+    let server = Server::bind((bind.as_str(), port), config)
+        .map_err(about(&format!("could not listen on {}:{}", bind, port)))?;
+    let addr = server.local_addr().map(|addr| addr.to_string()).unwrap_or(bind);
+    Display::new().show_message(&format!("Hosting a Pontoon table on {}", addr));
+    server.run()
+}
+
+fn join(args: &[String]) -> Result<()> {
+    let [address, rest @ ..] = args else {
+        fail("join needs a server address")
+    };
+    let name = match rest {
+        [] => std::env::var("USER").unwrap_or_else(|_| "player".to_string()),
+        [flag, name] if flag == "--name" => name.clone(),
+        _ => fail("join takes only --name <NAME> after the address"),
+    };
+    let address = with_default_port(address);
+    let client = Client::connect(address.as_str(), &name);
+    follow_table(&address, client)
+}
+
+fn watch(args: &[String]) -> Result<()> {
+    let [address] = args else {
+        fail("watch takes just a server address")
+    };
+    let address = with_default_port(address);
+    let client = Client::watch(address.as_str());
+    follow_table(&address, client)
+}
+
+fn with_default_port(address: &str) -> String {
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
+
+fn follow_table(address: &str, client: Result<Client>) -> Result<()> {
+    let mut client = client.map_err(about(&format!("could not connect to {}", address)))?;
+    client
+        .run(&Display::new(), &mut Input::stdin())
+        .map_err(about(&format!("lost the connection to {}", address)))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("Invalid number '{}' for {}", value, flag)))
+}
+
+fn fail(message: &str) -> ! {
+    logging::warn("cli", message);
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
 }
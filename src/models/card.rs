@@ -1,4 +1,5 @@
 use std::fmt;
+use std::str::FromStr;
 
 /// Represents the rank of a playing card
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,13 +17,17 @@ pub enum Rank {
     Jack,
     Queen,
     King,
+    /// A wild card, worth whatever suits the hand best; only some decks include jokers
+    Joker,
 }
 
 impl Rank {
     /// Returns the base value of the rank (Ace=1, Face cards=10, others=face value)
+    ///
+    /// A Joker's base value is its lowest, 1; hand evaluation counts it higher where that helps.
     pub fn base_value(&self) -> u8 {
         match self {
-            Rank::Ace => 1,
+            Rank::Ace | Rank::Joker => 1,
             Rank::Two => 2,
             Rank::Three => 3,
             Rank::Four => 4,
@@ -35,7 +40,7 @@ impl Rank {
         }
     }
 
-    /// Returns all possible ranks in order
+    /// Returns the thirteen natural ranks in order, leaving out the Joker
     pub fn all() -> [Rank; 13] {
         [
             Rank::Ace,
@@ -53,6 +58,39 @@ impl Rank {
             Rank::King,
         ]
     }
+
+    /// Returns the one-character symbol used in compact notation (A, 2-9, T, J, Q, K, X)
+    pub fn symbol(&self) -> char {
+        match self {
+            Rank::Ace => 'A',
+            Rank::Two => '2',
+            Rank::Three => '3',
+            Rank::Four => '4',
+            Rank::Five => '5',
+            Rank::Six => '6',
+            Rank::Seven => '7',
+            Rank::Eight => '8',
+            Rank::Nine => '9',
+            Rank::Ten => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Joker => 'X',
+        }
+    }
+
+    /// Returns the rank for a compact notation symbol
+    pub fn from_symbol(symbol: char) -> Option<Rank> {
+        Rank::all()
+            .into_iter()
+            .chain([Rank::Joker])
+            .find(|rank| rank.symbol() == symbol)
+    }
+
+    /// Returns true for the wild Joker
+    pub fn is_wild(&self) -> bool {
+        *self == Rank::Joker
+    }
 }
 
 impl fmt::Display for Rank {
@@ -71,6 +109,7 @@ impl fmt::Display for Rank {
             Rank::Jack => "Jack",
             Rank::Queen => "Queen",
             Rank::King => "King",
+            Rank::Joker => "Joker",
         };
         write!(f, "{}", rank_str)
     }
@@ -90,6 +129,26 @@ impl Suit {
     pub fn all() -> [Suit; 4] {
         [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades]
     }
+
+    /// Returns true for Hearts and Diamonds
+    pub fn is_red(&self) -> bool {
+        matches!(self, Suit::Hearts | Suit::Diamonds)
+    }
+
+    /// Returns the one-character symbol used in compact notation (h, d, c, s)
+    pub fn symbol(&self) -> char {
+        match self {
+            Suit::Hearts => 'h',
+            Suit::Diamonds => 'd',
+            Suit::Clubs => 'c',
+            Suit::Spades => 's',
+        }
+    }
+
+    /// Returns the suit for a compact notation symbol
+    pub fn from_symbol(symbol: char) -> Option<Suit> {
+        Suit::all().into_iter().find(|suit| suit.symbol() == symbol)
+    }
 }
 
 impl fmt::Display for Suit {
@@ -131,11 +190,49 @@ impl Card {
     pub fn suit(&self) -> Suit {
         self.suit
     }
+
+    /// Returns the two-character compact notation, rank then suit (e.g. "Ah", "Td")
+    pub fn notation(&self) -> String {
+        format!("{}{}", self.rank.symbol(), self.suit.symbol())
+    }
 }
 
 impl fmt::Display for Card {
+    /// Writes "Queen of Spades", or "Red Joker" / "Black Joker" since jokers have no suit to name
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} of {}", self.rank, self.suit)
+        match self.rank {
+            Rank::Joker if self.suit.is_red() => write!(f, "Red Joker"),
+            Rank::Joker => write!(f, "Black Joker"),
+            _ => write!(f, "{} of {}", self.rank, self.suit),
+        }
+    }
+}
+
+/// Error returned when text is not a card in compact notation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCardError(pub String);
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a card (expected e.g. Ah, Td, 7c)", self.0)
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    /// Parses compact notation such as "Qs"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(rank), Some(suit), None) => match (Rank::from_symbol(rank), Suit::from_symbol(suit)) {
+                (Some(rank), Some(suit)) => Ok(Card::new(rank, suit)),
+                _ => Err(ParseCardError(s.to_string())),
+            },
+            _ => Err(ParseCardError(s.to_string())),
+        }
     }
 }
 
@@ -176,12 +273,16 @@ mod tests {
         (any_rank(), any_suit()).prop_map(|(rank, suit)| Card::new(rank, suit))
     }
 
+    fn any_joker() -> impl Strategy<Value = Card> {
+        any_suit().prop_map(|suit| Card::new(Rank::Joker, suit))
+    }
+
     // Property: All card values are between 1 and 10
     proptest! {
         #[test]
         fn prop_card_value_in_valid_range(card in any_card()) {
             let value = card.base_value();
-            prop_assert!(value >= 1 && value <= 10, "Card value {} out of range [1,10]", value);
+            prop_assert!((1..=10).contains(&value), "Card value {} out of range [1,10]", value);
         }
     }
 
@@ -294,6 +395,36 @@ mod tests {
         }
     }
 
+    // Property: Compact notation round-trips through parsing
+    proptest! {
+        #[test]
+        fn prop_notation_round_trip(card in any_card()) {
+            let notation = card.notation();
+            prop_assert_eq!(notation.chars().count(), 2);
+            prop_assert_eq!(notation.parse::<Card>(), Ok(card));
+        }
+    }
+
+    // Property: Jokers round-trip through notation and are named by colour
+    proptest! {
+        #[test]
+        fn prop_joker_notation_round_trip(joker in any_joker()) {
+            prop_assert_eq!(joker.notation().parse::<Card>(), Ok(joker));
+            prop_assert!(joker.to_string().ends_with(" Joker"));
+            prop_assert!(joker.rank().is_wild());
+        }
+    }
+
+    // Property: Text that is not two valid symbols never parses
+    proptest! {
+        #[test]
+        fn prop_invalid_notation_rejected(text in "[a-zA-Z0-9]{0,4}") {
+            if let Ok(card) = text.parse::<Card>() {
+                prop_assert_eq!(card.notation(), text);
+            }
+        }
+    }
+
     // Structural tests (not property-based)
     #[test]
     fn test_all_ranks_count() {
@@ -301,6 +432,14 @@ mod tests {
         assert_eq!(ranks.len(), 13, "Should have exactly 13 ranks");
     }
 
+    #[test]
+    fn test_joker_is_not_a_natural_rank() {
+        assert!(!Rank::all().contains(&Rank::Joker));
+        assert_eq!(Card::new(Rank::Joker, Suit::Diamonds).to_string(), "Red Joker");
+        assert_eq!(Card::new(Rank::Joker, Suit::Clubs).to_string(), "Black Joker");
+        assert_eq!(Rank::Joker.base_value(), 1);
+    }
+
     #[test]
     fn test_all_suits_count() {
         let suits = Suit::all();
@@ -0,0 +1,193 @@
+use super::card::{Card, Rank};
+use super::hand::Hand;
+
+/// How many cards of each rank are still to come, from the shoe or as a viewer sees it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Composition {
+    /// Indexed in `Rank::all` order, with jokers last
+    counts: [u32; 14],
+}
+
+impl Composition {
+    /// A full shoe of `decks` standard decks
+    pub fn full(decks: usize) -> Self {
+        let mut counts = [4 * decks as u32; 14];
+        counts[JOKER] = 0;
+        Composition { counts }
+    }
+
+    /// Exactly these cards
+    pub fn of_cards(cards: &[Card]) -> Self {
+        let mut composition = Composition { counts: [0; 14] };
+        for card in cards {
+            composition.counts[rank_index(card.rank())] += 1;
+        }
+        composition
+    }
+
+    /// What a viewer can work out: a full shoe of `decks` decks less every card they have seen
+    pub fn unseen(decks: usize, seen: &[Card]) -> Self {
+        Self::full(decks).without(seen)
+    }
+
+    /// This composition less every card in `seen`, such as a full shoe of any deck type
+    /// less the cards a viewer has seen
+    pub fn without(mut self, seen: &[Card]) -> Self {
+        for card in seen {
+            self.remove(card);
+        }
+        self
+    }
+
+    /// Takes one card out, if any of its rank are left
+    pub fn remove(&mut self, card: &Card) {
+        let count = &mut self.counts[rank_index(card.rank())];
+        *count = count.saturating_sub(1);
+    }
+
+    /// Returns how many cards of `rank` are left
+    pub fn count(&self, rank: Rank) -> u32 {
+        self.counts[rank_index(rank)]
+    }
+
+    /// Returns how many cards are left in all
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the chance that the next card is of `rank`; 0 if nothing is left
+    pub fn probability(&self, rank: Rank) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => f64::from(self.count(rank)) / f64::from(total),
+        }
+    }
+
+    /// Returns every rank with the chance that it comes next, in `Rank::all` order, then the
+    /// Joker if any are left
+    pub fn rank_probabilities(&self) -> Vec<(Rank, f64)> {
+        let jokers = (self.counts[JOKER] > 0).then_some(Rank::Joker);
+        Rank::all()
+            .into_iter()
+            .chain(jokers)
+            .map(|rank| (rank, self.probability(rank)))
+            .collect()
+    }
+
+    /// Returns the chance that the next card busts `hand`; 0 if nothing is left
+    pub fn bust_probability(&self, hand: &Hand) -> f64 {
+        // A soft hand can always fall back to counting its Ace as 1, so only the hard total matters
+        let hard: u32 = hand.cards().iter().map(|card| u32::from(card.base_value())).sum();
+        // A Joker left is counted as its base value of 1, like an Ace
+        let busting: u32 = Rank::all()
+            .iter()
+            .chain(&[Rank::Joker])
+            .filter(|rank| hard + u32::from(rank.base_value()) > 21)
+            .map(|rank| self.count(*rank))
+            .sum();
+        match self.total() {
+            0 => 0.0,
+            total => f64::from(busting) / f64::from(total),
+        }
+    }
+}
+
+/// Slot for jokers, after the natural ranks
+const JOKER: usize = 13;
+
+fn rank_index(rank: Rank) -> usize {
+    match rank {
+        Rank::Joker => JOKER,
+        _ => Rank::all()
+            .iter()
+            .position(|r| *r == rank)
+            .expect("Rank::all contains every natural rank"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::Suit;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+
+    fn any_rank() -> impl Strategy<Value = Rank> {
+        (0..13usize).prop_map(|i| Rank::all()[i])
+    }
+
+    // Property: The rank chances of anything left sum to one
+    proptest! {
+        #[test]
+        fn prop_rank_probabilities_sum_to_one(seed in any::<u64>(), dealt in 0usize..52) {
+            let mut deck = Deck::new_seeded(seed);
+            for _ in 0..dealt {
+                deck.deal().unwrap();
+            }
+            let composition = deck.composition();
+            prop_assert_eq!(composition.total() as usize, 52 - dealt);
+            let sum: f64 = composition.rank_probabilities().iter().map(|(_, p)| p).sum();
+            prop_assert!((sum - 1.0).abs() < 1e-9);
+        }
+    }
+
+    // Property: A viewer who has seen every dealt card knows exactly what is left
+    proptest! {
+        #[test]
+        fn prop_unseen_matches_the_deck_when_all_is_seen(
+            decks in 1usize..=6,
+            seed in any::<u64>(),
+            dealt in 0usize..40,
+        ) {
+            let mut deck = Deck::new_shoe_seeded(decks, seed);
+            for _ in 0..dealt {
+                deck.deal().unwrap();
+            }
+            prop_assert_eq!(Composition::unseen(decks, deck.cards_dealt()), deck.composition());
+        }
+    }
+
+    // Property: Bust chances are probabilities
+    proptest! {
+        #[test]
+        fn prop_bust_probability_in_unit_range(
+            ranks in prop::collection::vec(any_rank(), 1..6),
+            decks in 1usize..=4,
+        ) {
+            let mut hand = Hand::new();
+            for rank in &ranks {
+                hand.add_card(Card::new(*rank, Suit::Hearts));
+            }
+            let p = Composition::unseen(decks, hand.cards()).bust_probability(&hand);
+            prop_assert!((0.0..=1.0).contains(&p));
+        }
+    }
+
+    #[test]
+    fn test_counts_and_odds_of_a_full_shoe() {
+        let shoe = Composition::full(2);
+        assert_eq!(shoe.total(), 104);
+        assert_eq!(shoe.count(Rank::Queen), 8);
+        assert!((shoe.probability(Rank::Ace) - 1.0 / 13.0).abs() < 1e-12);
+        assert_eq!(Composition::of_cards(&[]).probability(Rank::Ace), 0.0);
+    }
+
+    #[test]
+    fn test_jokers_are_listed_only_while_some_are_left() {
+        let joker = Card::new(Rank::Joker, Suit::Hearts);
+        let shoe = Composition::of_cards(&[joker, Card::new(Rank::Two, Suit::Clubs)]);
+        assert_eq!(shoe.rank_probabilities().len(), 14);
+        assert!((shoe.probability(Rank::Joker) - 0.5).abs() < 1e-12);
+        assert_eq!(shoe.without(&[joker]).rank_probabilities().len(), 13);
+    }
+
+    #[test]
+    fn test_bust_probability_on_hard_20_from_two_decks() {
+        let mut hand = Hand::new();
+        hand.add_card(Card::new(Rank::King, Suit::Hearts));
+        hand.add_card(Card::new(Rank::Queen, Suit::Hearts));
+        // Only the eight Aces avoid busting
+        let p = Composition::unseen(2, hand.cards()).bust_probability(&hand);
+        assert!((p - 94.0 / 102.0).abs() < 1e-12);
+    }
+}
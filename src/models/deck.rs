@@ -1,37 +1,106 @@
 use super::card::{Card, Rank, Suit};
+use super::composition::Composition;
+use super::deck_type::DeckType;
+use super::shuffle::{ShuffleRoutine, ShuffleStep};
+use crate::error::PontoonError;
 use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 
-/// Represents a deck of 52 playing cards
+/// Cards held back from play before a reshuffle, per deck in the shoe
+const RESHUFFLE_THRESHOLD_PER_DECK: usize = 15;
+
+/// Represents a deck of playing cards, or a shoe of several decks of the same type
+#[derive(Clone)]
 pub struct Deck {
     cards: Vec<Card>,
+    /// Every card in the shoe, unshuffled, for gathering back up on a reshuffle
+    full: Vec<Card>,
+    /// Cards dealt since the shoe was last gathered, whether still in play or discarded
+    dealt: Vec<Card>,
+    decks: usize,
+    seed: u64,
     rng: StdRng,
 }
 
 impl Deck {
     /// Creates a new standard 52-card deck and shuffles it
     pub fn new() -> Self {
+        Self::new_shoe(1)
+    }
+
+    /// Creates a new deck with a specific seed (for testing)
+    pub fn new_seeded(seed: u64) -> Self {
+        Self::new_shoe_seeded(1, seed)
+    }
+
+    /// Creates a shuffled shoe of `decks` standard decks
+    ///
+    /// The seed is drawn from entropy and kept, so the game can be recorded and replayed.
+    pub fn new_shoe(decks: usize) -> Self {
+        Self::new_shoe_seeded(decks, StdRng::from_entropy().gen())
+    }
+
+    /// Creates a shuffled shoe of `decks` standard decks with a specific seed
+    pub fn new_shoe_seeded(decks: usize, seed: u64) -> Self {
+        Self::of_type_seeded(&DeckType::standard(), decks, seed)
+    }
+
+    /// Creates a shuffled shoe of `decks` decks of the given type, such as Spanish decks
+    pub fn of_type(deck_type: &DeckType, decks: usize) -> Self {
+        Self::of_type_seeded(deck_type, decks, StdRng::from_entropy().gen())
+    }
+
+    /// Creates a shuffled shoe of `decks` decks of the given type with a specific seed
+    pub fn of_type_seeded(deck_type: &DeckType, decks: usize, seed: u64) -> Self {
+        assert!(decks > 0, "A shoe needs at least one deck");
+        let full = deck_type.cards().repeat(decks);
         let mut deck = Deck {
-            cards: Self::create_standard_deck(),
-            rng: StdRng::from_entropy(),
+            cards: full.clone(),
+            full,
+            dealt: Vec::new(),
+            decks,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
         };
         deck.shuffle();
         deck
     }
 
-    /// Creates a new deck with a specific seed (for testing)
-    pub fn new_seeded(seed: u64) -> Self {
+    /// Creates a deck of exactly these cards, shuffled with a specific seed
+    ///
+    /// For experiments with small or unusual decks; it counts as a single deck.
+    pub fn from_cards(cards: Vec<Card>, seed: u64) -> Self {
         let mut deck = Deck {
-            cards: Self::create_standard_deck(),
+            full: cards.clone(),
+            cards,
+            dealt: Vec::new(),
+            decks: 1,
+            seed,
             rng: StdRng::seed_from_u64(seed),
         };
         deck.shuffle();
         deck
     }
 
+    /// Creates a deck holding these cards in this order, next card first, unshuffled
+    ///
+    /// For following a known order, such as a gathered discard pile, through a shuffle
+    /// routine. Reshuffling gathers the cards back into this order first.
+    pub fn stacked(cards: Vec<Card>, seed: u64) -> Self {
+        let cards: Vec<Card> = cards.into_iter().rev().collect();
+        Deck {
+            full: cards.clone(),
+            cards,
+            dealt: Vec::new(),
+            decks: 1,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
     /// Creates a standard 52-card deck (unshuffled)
-    fn create_standard_deck() -> Vec<Card> {
+    pub fn create_standard_deck() -> Vec<Card> {
         let mut cards = Vec::with_capacity(52);
         for suit in Suit::all() {
             for rank in Rank::all() {
@@ -46,10 +115,61 @@ impl Deck {
         self.cards.shuffle(&mut self.rng);
     }
 
+    /// Gathers every card back into the shoe and shuffles, keeping the same random stream
+    pub fn reshuffle(&mut self) {
+        self.gather();
+        self.shuffle();
+    }
+
+    /// Rearranges the deck with one physical shuffle step, such as a riffle
+    pub fn shuffle_step(&mut self, step: ShuffleStep) {
+        step.apply(&mut self.cards, &mut self.rng);
+    }
+
+    /// Rearranges the deck with each step of a dealer's routine in turn
+    pub fn shuffle_with(&mut self, routine: &ShuffleRoutine) {
+        for step in routine.steps() {
+            self.shuffle_step(*step);
+        }
+    }
+
+    /// Gathers every card back in new-deck order, then shuffles them with a routine
+    ///
+    /// Starting from a known order shows how much of it the routine leaves behind.
+    pub fn reshuffle_with(&mut self, routine: &ShuffleRoutine) {
+        self.gather();
+        self.shuffle_with(routine);
+    }
+
+    fn gather(&mut self) {
+        self.cards = self.full.clone();
+        self.dealt.clear();
+    }
+
+    /// Shuffles the discards back into the shoe, leaving out the cards still in play
+    ///
+    /// Every dealt card not in `in_play` is a discard. They go under any cards still
+    /// waiting to be dealt. Returns how many cards were added.
+    pub fn replenish(&mut self, in_play: &[Card]) -> usize {
+        let mut discards = std::mem::take(&mut self.dealt);
+        for card in in_play {
+            if let Some(index) = discards.iter().position(|dealt| dealt == card) {
+                self.dealt.push(discards.remove(index));
+            }
+        }
+        let added = discards.len();
+        discards.shuffle(&mut self.rng);
+        discards.append(&mut self.cards);
+        self.cards = discards;
+        added
+    }
+
     /// Deals one card from the top of the deck
-    /// Returns None if the deck is empty
-    pub fn deal(&mut self) -> Option<Card> {
-        self.cards.pop()
+    /// Returns `DeckExhausted` if the deck is empty
+    pub fn deal(&mut self) -> Result<Card, PontoonError> {
+        let card = self.cards.pop().ok_or(PontoonError::DeckExhausted)?;
+        self.dealt.push(card);
+        Ok(card)
     }
 
     /// Returns the number of cards remaining in the deck
@@ -57,12 +177,63 @@ impl Deck {
         self.cards.len()
     }
 
-    /// Returns true if the deck needs reshuffling (< 15 cards)
+    /// Returns the cards still to be dealt, next card first
+    pub fn cards_in_deal_order(&self) -> Vec<Card> {
+        self.cards.iter().rev().copied().collect()
+    }
+
+    /// Returns how many of each rank are still to be dealt
+    pub fn composition(&self) -> Composition {
+        Composition::of_cards(&self.cards)
+    }
+
+    /// Returns how many of each rank the whole shoe holds, dealt or not
+    pub fn shoe_composition(&self) -> Composition {
+        Composition::of_cards(&self.full)
+    }
+
+    /// Returns the cards dealt since the last reshuffle, in play or discarded, oldest first
+    pub fn cards_dealt(&self) -> &[Card] {
+        &self.dealt
+    }
+
+    /// Returns the seed the deck's shuffles are drawn from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of decks in the shoe
+    pub fn deck_count(&self) -> usize {
+        self.decks
+    }
+
+    /// Returns the number of decks' worth of cards left, for true count conversion
+    pub fn decks_remaining(&self) -> f64 {
+        let deck_size = (self.full.len() / self.decks).max(1);
+        self.cards.len() as f64 / deck_size as f64
+    }
+
+    /// Returns true if the deck needs reshuffling (< 15 cards per deck in the shoe)
     pub fn needs_reshuffle(&self) -> bool {
-        self.cards.len() < 15
+        self.cards.len() < RESHUFFLE_THRESHOLD_PER_DECK * self.decks
     }
 }
 
+/// Derives the seed of one of several shoes dealt from the same base seed
+///
+/// Seed and stream are hashed together with SplitMix64, so neighbouring streams, or
+/// neighbouring base seeds, do not deal related shuffles.
+pub fn mix_seed(seed: u64, stream: u64) -> u64 {
+    splitmix64(splitmix64(seed).wrapping_add(stream))
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 impl Default for Deck {
     fn default() -> Self {
         Self::new()
@@ -87,13 +258,13 @@ mod tests {
         fn prop_dealing_reduces_count(deal_count in 0usize..=52) {
             let mut deck = Deck::new();
             for _ in 0..deal_count {
-                deck.deal();
+                let _ = deck.deal();
             }
             prop_assert_eq!(deck.cards_remaining(), 52 - deal_count);
         }
     }
 
-    // Property: Can deal exactly 52 cards, then get None
+    // Property: Can deal exactly 52 cards, then get DeckExhausted
     proptest! {
         #[test]
         fn prop_can_deal_all_52_cards(seed in any::<u64>()) {
@@ -103,12 +274,12 @@ mod tests {
             // Deal all 52 cards
             for _ in 0..52 {
                 let card = deck.deal();
-                prop_assert!(card.is_some(), "Should be able to deal card");
+                prop_assert!(card.is_ok(), "Should be able to deal card");
                 dealt_cards.push(card.unwrap());
             }
             
-            // 53rd deal should return None
-            prop_assert_eq!(deck.deal(), None);
+            // 53rd deal should find the deck exhausted
+            prop_assert!(matches!(deck.deal(), Err(PontoonError::DeckExhausted)));
             prop_assert_eq!(deck.cards_remaining(), 0);
         }
     }
@@ -121,8 +292,8 @@ mod tests {
             let mut deck2 = Deck::new_seeded(seed);
             
             for _ in 0..deal_count {
-                let card1 = deck1.deal();
-                let card2 = deck2.deal();
+                let card1 = deck1.deal().ok();
+                let card2 = deck2.deal().ok();
                 prop_assert_eq!(card1, card2, "Same seed should produce same sequence");
             }
         }
@@ -134,7 +305,7 @@ mod tests {
         fn prop_needs_reshuffle_threshold(deal_count in 0usize..=52) {
             let mut deck = Deck::new();
             for _ in 0..deal_count {
-                deck.deal();
+                let _ = deck.deal();
             }
             
             let remaining = deck.cards_remaining();
@@ -155,7 +326,7 @@ mod tests {
             
             // Deal all cards
             for _ in 0..52 {
-                if let Some(card) = deck.deal() {
+                if let Ok(card) = deck.deal() {
                     dealt_cards.push(card);
                 }
             }
@@ -181,7 +352,7 @@ mod tests {
             let mut dealt_cards = Vec::new();
             
             // Deal all cards
-            while let Some(card) = deck.deal() {
+            while let Ok(card) = deck.deal() {
                 dealt_cards.push(card);
             }
             
@@ -200,20 +371,88 @@ mod tests {
         }
     }
 
-    // Property: Dealing from empty deck always returns None
+    // Property: Dealing from empty deck always returns DeckExhausted
     proptest! {
         #[test]
-        fn prop_empty_deck_always_none(extra_deals in 1usize..=10) {
+        fn prop_empty_deck_always_exhausted(extra_deals in 1usize..=10) {
             let mut deck = Deck::new();
             
             // Empty the deck
             for _ in 0..52 {
-                deck.deal();
+                let _ = deck.deal();
             }
             
             // Try dealing more times
             for _ in 0..extra_deals {
-                prop_assert_eq!(deck.deal(), None, "Empty deck should always return None");
+                prop_assert!(
+                    matches!(deck.deal(), Err(PontoonError::DeckExhausted)),
+                    "Empty deck should always be exhausted"
+                );
+            }
+        }
+    }
+
+    // Property: Reshuffling restores a full deck and stays deterministic for a seed
+    proptest! {
+        #[test]
+        fn prop_reshuffle_restores_full_deck(seed in any::<u64>(), deal_count in 0usize..=52) {
+            let mut deck1 = Deck::new_seeded(seed);
+            let mut deck2 = Deck::new_seeded(seed);
+            for _ in 0..deal_count {
+                let _ = deck1.deal();
+                let _ = deck2.deal();
+            }
+            deck1.reshuffle();
+            deck2.reshuffle();
+
+            prop_assert_eq!(deck1.cards_remaining(), 52);
+            while let Ok(card) = deck1.deal() {
+                prop_assert_eq!(Some(card), deck2.deal().ok());
+            }
+        }
+    }
+
+    // Property: The deal order lists exactly the cards that will be dealt, in order
+    proptest! {
+        #[test]
+        fn prop_deal_order_matches_deals(seed in any::<u64>(), skip in 0usize..52) {
+            let mut deck = Deck::new_seeded(seed);
+            for _ in 0..skip {
+                let _ = deck.deal();
+            }
+            let order = deck.cards_in_deal_order();
+            prop_assert_eq!(order.len(), deck.cards_remaining());
+            for expected in order {
+                prop_assert_eq!(deck.deal().ok(), Some(expected));
+            }
+        }
+    }
+
+    // Property: An unseeded deck can be reproduced from its seed
+    #[test]
+    fn prop_unseeded_deck_reproducible_from_seed() {
+        let deck = Deck::new();
+        let copy = Deck::new_seeded(deck.seed());
+        assert_eq!(deck.cards_in_deal_order(), copy.cards_in_deal_order());
+    }
+
+    // Property: A shoe holds every card exactly once per deck
+    proptest! {
+        #[test]
+        fn prop_shoe_has_each_card_per_deck(decks in 1usize..=8, seed in any::<u64>()) {
+            let mut shoe = Deck::new_shoe_seeded(decks, seed);
+            prop_assert_eq!(shoe.cards_remaining(), 52 * decks);
+            prop_assert_eq!(shoe.deck_count(), decks);
+
+            let mut dealt = Vec::new();
+            while let Ok(card) = shoe.deal() {
+                dealt.push(card);
+            }
+            for rank in Rank::all() {
+                for suit in Suit::all() {
+                    let copies = dealt.iter().filter(|c| **c == Card::new(rank, suit)).count();
+                    prop_assert_eq!(copies, decks, "Wrong number of {}", Card::new(rank, suit));
+                }
             }
         }
     }
@@ -242,4 +481,128 @@ mod tests {
             prop_assert!(!all_match, "Different seeds produced identical first 5 cards - shuffling may be broken");
         }
     }
+
+    /// Every card in the shoe, in play or discarded, by name in sorted order
+    fn every_card(deck: &Deck) -> Vec<String> {
+        let mut cards: Vec<String> = deck
+            .cards_in_deal_order()
+            .iter()
+            .chain(deck.cards_dealt())
+            .map(Card::notation)
+            .collect();
+        cards.sort();
+        cards
+    }
+
+    // Property: Replenishing never duplicates or loses a card, and never brings back a card
+    // still in play
+    proptest! {
+        #[test]
+        fn prop_replenish_keeps_every_card_once(
+            seed in any::<u64>(),
+            decks in 1usize..=3,
+            rounds in prop::collection::vec((1usize..40, 0usize..12), 1..8)
+        ) {
+            let mut deck = Deck::new_shoe_seeded(decks, seed);
+            let full = every_card(&deck);
+            for (deals, keep) in rounds {
+                let mut in_play = Vec::new();
+                for _ in 0..deals {
+                    match deck.deal() {
+                        Ok(card) => in_play.push(card),
+                        Err(_) => {
+                            let added = deck.replenish(&in_play);
+                            prop_assert_eq!(added + in_play.len(), 52 * decks);
+                            if let Ok(card) = deck.deal() {
+                                in_play.push(card);
+                            }
+                        }
+                    }
+                    prop_assert_eq!(every_card(&deck), full.clone());
+                }
+                // The round ends and only some of its cards stay on the table
+                in_play.truncate(keep);
+                let before = deck.cards_remaining();
+                let added = deck.replenish(&in_play);
+                prop_assert_eq!(deck.cards_remaining(), before + added);
+                prop_assert_eq!(deck.cards_dealt().len(), in_play.len());
+                prop_assert_eq!(every_card(&deck), full.clone());
+                if decks == 1 {
+                    let shoe = deck.cards_in_deal_order();
+                    prop_assert!(in_play.iter().all(|card| !shoe.contains(card)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_replenish_puts_discards_under_the_shoe() {
+        let mut deck = Deck::new_seeded(3);
+        let first = deck.deal().unwrap();
+        let second = deck.deal().unwrap();
+        let waiting = deck.cards_in_deal_order();
+        assert_eq!(deck.replenish(&[second]), 1);
+        let order = deck.cards_in_deal_order();
+        assert_eq!(&order[..50], &waiting[..]);
+        assert_eq!(order[50], first);
+        assert_eq!(deck.cards_dealt(), &[second]);
+    }
+
+    // Property: A deck built from chosen cards reshuffles back to exactly those cards
+    proptest! {
+        #[test]
+        fn prop_from_cards_reshuffles_the_same_cards(seed in any::<u64>(), size in 1usize..=52) {
+            let cards: Vec<Card> = Deck::create_standard_deck().into_iter().take(size).collect();
+            let mut deck = Deck::from_cards(cards.clone(), seed);
+            prop_assert_eq!(deck.cards_remaining(), size);
+            let _ = deck.deal();
+            deck.reshuffle();
+            let mut order: Vec<String> =
+                deck.cards_in_deal_order().iter().map(Card::notation).collect();
+            let mut expected: Vec<String> = cards.iter().map(Card::notation).collect();
+            order.sort();
+            expected.sort();
+            prop_assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn test_routines_start_from_new_deck_order() {
+        let mut deck = Deck::new_seeded(45);
+        let _ = deck.deal();
+        deck.reshuffle_with(&ShuffleRoutine::new(Vec::new()));
+        let new_deck: Vec<Card> = Deck::create_standard_deck().into_iter().rev().collect();
+        assert_eq!(deck.cards_in_deal_order(), new_deck);
+        assert!(deck.cards_dealt().is_empty());
+
+        let routine: ShuffleRoutine = "riffle x3, strip, riffle, cut".parse().unwrap();
+        let mut again = Deck::new_seeded(45);
+        let _ = again.deal();
+        deck.reshuffle_with(&routine);
+        again.reshuffle_with(&ShuffleRoutine::new(Vec::new()));
+        again.reshuffle_with(&routine);
+        assert_eq!(deck.cards_in_deal_order(), again.cards_in_deal_order());
+        assert_ne!(deck.cards_in_deal_order(), new_deck);
+    }
+
+    #[test]
+    fn test_stacked_deck_deals_in_the_given_order() {
+        let order: Vec<Card> =
+            ["Ah", "Td", "7c"].iter().map(|card| card.parse().unwrap()).collect();
+        let mut deck = Deck::stacked(order.clone(), 1);
+        assert_eq!(deck.deal().unwrap(), order[0]);
+        deck.reshuffle_with(&ShuffleRoutine::new(Vec::new()));
+        assert_eq!(deck.cards_in_deal_order(), order);
+    }
+
+    // Property: Mixed seeds of neighbouring streams, and of neighbouring base seeds, differ
+    proptest! {
+        #[test]
+        fn prop_mixed_seeds_are_distinct(seed in any::<u64>(), stream in any::<u64>()) {
+            let mixed = mix_seed(seed, stream);
+            prop_assert_eq!(mixed, mix_seed(seed, stream));
+            prop_assert_ne!(mixed, mix_seed(seed, stream.wrapping_add(1)));
+            prop_assert_ne!(mixed, mix_seed(seed.wrapping_add(1), stream));
+        }
+    }
 }
@@ -0,0 +1,124 @@
+use super::card::{Card, Rank, Suit};
+use super::composition::Composition;
+
+/// The cards that make up each deck in the shoe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeckType {
+    /// Short name used to select the preset on the command line
+    pub name: &'static str,
+    /// Ranks included in each of the four suits
+    pub ranks: Vec<Rank>,
+    /// Wild jokers added to each deck, red and black in turn
+    pub jokers: usize,
+}
+
+impl DeckType {
+    /// The standard 52-card deck
+    pub fn standard() -> Self {
+        DeckType {
+            name: "standard",
+            ranks: Rank::all().to_vec(),
+            jokers: 0,
+        }
+    }
+
+    /// The 48-card Spanish deck, as in Spanish 21: the four Tens are stripped out but the
+    /// court cards stay, so ten-valued cards are scarcer
+    pub fn spanish() -> Self {
+        DeckType {
+            name: "spanish",
+            ranks: Rank::all().into_iter().filter(|rank| *rank != Rank::Ten).collect(),
+            jokers: 0,
+        }
+    }
+
+    /// A standard deck with both of its jokers left in as wild cards
+    pub fn with_jokers() -> Self {
+        DeckType {
+            name: "jokers",
+            jokers: 2,
+            ..Self::standard()
+        }
+    }
+
+    /// Returns all built-in deck presets
+    pub fn presets() -> Vec<DeckType> {
+        vec![Self::standard(), Self::spanish(), Self::with_jokers()]
+    }
+
+    /// Looks up a built-in preset by name
+    pub fn preset(name: &str) -> Option<DeckType> {
+        Self::presets().into_iter().find(|deck_type| deck_type.name == name)
+    }
+
+    /// Returns one deck in new-deck order: suit by suit, then the jokers
+    pub fn cards(&self) -> Vec<Card> {
+        let mut cards = Vec::with_capacity(self.size());
+        for suit in Suit::all() {
+            for rank in &self.ranks {
+                cards.push(Card::new(*rank, suit));
+            }
+        }
+        for joker in 0..self.jokers {
+            let suit = if joker % 2 == 0 { Suit::Hearts } else { Suit::Spades };
+            cards.push(Card::new(Rank::Joker, suit));
+        }
+        cards
+    }
+
+    /// Returns the number of cards in one deck
+    pub fn size(&self) -> usize {
+        self.ranks.len() * Suit::all().len() + self.jokers
+    }
+
+    /// Returns how many of each rank a full shoe of `decks` of these decks holds
+    pub fn composition(&self, decks: usize) -> Composition {
+        Composition::of_cards(&self.cards().repeat(decks))
+    }
+}
+
+impl Default for DeckType {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+
+    fn any_preset() -> impl Strategy<Value = DeckType> {
+        (0..DeckType::presets().len()).prop_map(|i| DeckType::presets()[i].clone())
+    }
+
+    // Property: A shoe holds each preset's cards once per deck
+    proptest! {
+        #[test]
+        fn prop_shoe_holds_every_card_of_its_type(
+            deck_type in any_preset(),
+            decks in 1usize..=4,
+            seed in any::<u64>(),
+        ) {
+            let deck = Deck::of_type_seeded(&deck_type, decks, seed);
+            prop_assert_eq!(deck.cards_remaining(), deck_type.size() * decks);
+            prop_assert_eq!(deck.composition(), deck_type.composition(decks));
+        }
+    }
+
+    #[test]
+    fn test_preset_sizes() {
+        assert_eq!(DeckType::standard().cards(), Deck::create_standard_deck());
+        assert_eq!(DeckType::spanish().size(), 48);
+        assert_eq!(DeckType::spanish().composition(1).count(Rank::Ten), 0);
+        assert_eq!(DeckType::with_jokers().size(), 54);
+        assert_eq!(DeckType::with_jokers().composition(3).count(Rank::Joker), 6);
+    }
+
+    #[test]
+    fn test_preset_lookup() {
+        assert_eq!(DeckType::preset("spanish"), Some(DeckType::spanish()));
+        assert_eq!(DeckType::preset("tarot"), None);
+    }
+}
@@ -1,7 +1,7 @@
 use super::card::Card;
 
 /// Represents a hand of cards
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Hand {
     cards: Vec<Card>,
 }
@@ -27,6 +27,11 @@ impl Hand {
         &self.cards
     }
 
+    /// Takes back the last card added, if any
+    pub fn take_last(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
     /// Clears all cards from the hand
     pub fn clear(&mut self) {
         self.cards.clear();
@@ -47,12 +52,12 @@ mod tests {
 
     // Generator for any card
     fn any_card() -> impl Strategy<Value = Card> {
-        let ranks = vec![
+        let ranks = [
             Rank::Ace, Rank::Two, Rank::Three, Rank::Four, Rank::Five,
             Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten,
             Rank::Jack, Rank::Queen, Rank::King,
         ];
-        let suits = vec![Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
+        let suits = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
         
         (0..13usize, 0..4usize).prop_map(move |(r, s)| {
             Card::new(ranks[r], suits[s])
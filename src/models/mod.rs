@@ -1,3 +1,6 @@
 pub mod card;
+pub mod composition;
 pub mod deck;
+pub mod deck_type;
 pub mod hand;
+pub mod shuffle;
@@ -0,0 +1,281 @@
+use rand::seq::index;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// Chance that an overhand shuffle breaks the deck between two cards
+pub const OVERHAND_BREAK: f64 = 0.2;
+
+/// Fewest and most packets pulled off in a strip
+pub const STRIP_PACKETS: (usize, usize) = (4, 7);
+
+/// One movement of a dealer's hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleStep {
+    /// A Gilbert-Shannon-Reeds riffle: a binomial cut, then cards drop from each half
+    /// with chance proportional to the cards left in it
+    Riffle,
+    /// Small packets of about five cards slide off one at a time, reversing their order
+    Overhand,
+    /// The deck is stripped into four to seven packets of random size, reversing their order
+    Strip,
+    /// A binomial cut near the middle, then the halves swap
+    Cut,
+}
+
+impl ShuffleStep {
+    pub fn all() -> [ShuffleStep; 4] {
+        [ShuffleStep::Riffle, ShuffleStep::Overhand, ShuffleStep::Strip, ShuffleStep::Cut]
+    }
+
+    /// Rearranges `cards` as this step would; anything standing in for cards, such as their
+    /// positions, moves just the same
+    pub fn apply<T: Copy, R: Rng>(self, cards: &mut Vec<T>, rng: &mut R) {
+        if cards.len() < 2 {
+            return;
+        }
+        match self {
+            ShuffleStep::Riffle => riffle(cards, rng),
+            ShuffleStep::Overhand => {
+                let breaks = (1..cards.len()).filter(|_| rng.gen_bool(OVERHAND_BREAK)).collect();
+                reverse_packets(cards, breaks);
+            }
+            ShuffleStep::Strip => {
+                let packets = rng.gen_range(STRIP_PACKETS.0..=STRIP_PACKETS.1).min(cards.len());
+                let mut breaks: Vec<usize> = index::sample(rng, cards.len() - 1, packets - 1)
+                    .into_iter()
+                    .map(|gap| gap + 1)
+                    .collect();
+                breaks.sort_unstable();
+                reverse_packets(cards, breaks);
+            }
+            ShuffleStep::Cut => {
+                let cut = binomial_cut(cards.len(), rng);
+                cards.rotate_left(cut);
+            }
+        }
+    }
+}
+
+/// Splits the deck where a fair coin tossed once per card says, as a riffler's cut does
+fn binomial_cut<R: Rng>(cards: usize, rng: &mut R) -> usize {
+    (0..cards).filter(|_| rng.gen_bool(0.5)).count()
+}
+
+fn riffle<T: Copy, R: Rng>(cards: &mut Vec<T>, rng: &mut R) {
+    let right = cards.split_off(binomial_cut(cards.len(), rng));
+    let left = std::mem::take(cards);
+    let (mut l, mut r) = (0, 0);
+    while l < left.len() || r < right.len() {
+        let (left_over, right_over) = (left.len() - l, right.len() - r);
+        if rng.gen_range(0..left_over + right_over) < left_over {
+            cards.push(left[l]);
+            l += 1;
+        } else {
+            cards.push(right[r]);
+            r += 1;
+        }
+    }
+}
+
+/// Cuts the deck before each index in `breaks` (ascending) and stacks the packets in
+/// reverse order, keeping the order within each packet
+fn reverse_packets<T: Copy>(cards: &mut Vec<T>, breaks: Vec<usize>) {
+    let mut bounds = vec![0];
+    bounds.extend(breaks);
+    bounds.push(cards.len());
+    let packets: Vec<Vec<T>> =
+        bounds.windows(2).map(|w| cards[w[0]..w[1]].to_vec()).collect();
+    cards.clear();
+    for packet in packets.into_iter().rev() {
+        cards.extend(packet);
+    }
+}
+
+impl fmt::Display for ShuffleStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShuffleStep::Riffle => write!(f, "riffle"),
+            ShuffleStep::Overhand => write!(f, "overhand"),
+            ShuffleStep::Strip => write!(f, "strip"),
+            ShuffleStep::Cut => write!(f, "cut"),
+        }
+    }
+}
+
+impl FromStr for ShuffleStep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "riffle" => Ok(ShuffleStep::Riffle),
+            "overhand" => Ok(ShuffleStep::Overhand),
+            "strip" => Ok(ShuffleStep::Strip),
+            "cut" => Ok(ShuffleStep::Cut),
+            other => Err(format!(
+                "unknown shuffle '{}' (use riffle, overhand, strip or cut)",
+                other
+            )),
+        }
+    }
+}
+
+/// A dealer's shuffle made of steps done in order, such as "riffle x3, strip, riffle, cut"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShuffleRoutine {
+    steps: Vec<ShuffleStep>,
+}
+
+impl ShuffleRoutine {
+    pub fn new(steps: Vec<ShuffleStep>) -> Self {
+        ShuffleRoutine { steps }
+    }
+
+    /// The same step `times` times, such as seven riffles
+    pub fn repeat(step: ShuffleStep, times: usize) -> Self {
+        ShuffleRoutine::new(vec![step; times])
+    }
+
+    pub fn steps(&self) -> &[ShuffleStep] {
+        &self.steps
+    }
+
+    /// Returns how many riffles the routine does
+    pub fn riffles(&self) -> usize {
+        self.steps.iter().filter(|step| **step == ShuffleStep::Riffle).count()
+    }
+}
+
+impl fmt::Display for ShuffleRoutine {
+    /// Writes runs of the same step as "riffle x3"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut runs: Vec<(ShuffleStep, usize)> = Vec::new();
+        for step in &self.steps {
+            match runs.last_mut() {
+                Some((last, count)) if last == step => *count += 1,
+                _ => runs.push((*step, 1)),
+            }
+        }
+        let runs: Vec<String> = runs
+            .into_iter()
+            .map(|(step, count)| match count {
+                1 => step.to_string(),
+                _ => format!("{} x{}", step, count),
+            })
+            .collect();
+        write!(f, "{}", runs.join(", "))
+    }
+}
+
+impl FromStr for ShuffleRoutine {
+    type Err = String;
+
+    /// Parses comma-separated steps, each optionally repeated with "x3" or "×3"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for part in s.split(',') {
+            let mut words = part.split_whitespace();
+            let step: ShuffleStep = match words.next() {
+                Some(word) => word.parse()?,
+                None => return Err(format!("'{}' has an empty shuffle step", s)),
+            };
+            let times = match words.next() {
+                None => 1,
+                Some(word) => word
+                    .strip_prefix('x')
+                    .or_else(|| word.strip_prefix('×'))
+                    .and_then(|count| count.parse().ok())
+                    .ok_or_else(|| format!("'{}' is not a repeat count (e.g. x3)", word))?,
+            };
+            if let Some(extra) = words.next() {
+                return Err(format!("unexpected '{}' after '{}'", extra, part.trim()));
+            }
+            steps.extend(std::iter::repeat_n(step, times));
+        }
+        Ok(ShuffleRoutine::new(steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::Card;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn any_step() -> impl Strategy<Value = ShuffleStep> {
+        prop_oneof![
+            Just(ShuffleStep::Riffle),
+            Just(ShuffleStep::Overhand),
+            Just(ShuffleStep::Strip),
+            Just(ShuffleStep::Cut),
+        ]
+    }
+
+    fn notations(cards: &[Card]) -> Vec<String> {
+        let mut notations: Vec<String> = cards.iter().map(Card::notation).collect();
+        notations.sort();
+        notations
+    }
+
+    // Property: Every step only rearranges the cards
+    proptest! {
+        #[test]
+        fn prop_steps_keep_every_card(
+            step in any_step(),
+            size in 0usize..=52,
+            seed in any::<u64>(),
+        ) {
+            let original: Vec<Card> = Deck::create_standard_deck().into_iter().take(size).collect();
+            let mut cards = original.clone();
+            step.apply(&mut cards, &mut StdRng::seed_from_u64(seed));
+            prop_assert_eq!(notations(&cards), notations(&original));
+        }
+    }
+
+    // Property: A riffle keeps each half in order, leaving at most two rising sequences
+    proptest! {
+        #[test]
+        fn prop_riffle_interleaves_two_ordered_halves(seed in any::<u64>()) {
+            let original = Deck::create_standard_deck();
+            let mut cards = original.clone();
+            ShuffleStep::Riffle.apply(&mut cards, &mut StdRng::seed_from_u64(seed));
+            let position = |card: &Card| cards.iter().position(|c| c == card).unwrap();
+            let restarts =
+                original.windows(2).filter(|w| position(&w[1]) < position(&w[0])).count();
+            prop_assert!(restarts <= 1);
+        }
+    }
+
+    // Property: A routine prints and parses back to the same steps
+    proptest! {
+        #[test]
+        fn prop_routine_round_trips(steps in prop::collection::vec(any_step(), 1..12)) {
+            let routine = ShuffleRoutine::new(steps);
+            prop_assert_eq!(routine.to_string().parse::<ShuffleRoutine>(), Ok(routine));
+        }
+    }
+
+    #[test]
+    fn test_routine_parses_repeats() {
+        let routine: ShuffleRoutine = "riffle ×3, strip, riffle, cut".parse().unwrap();
+        assert_eq!(routine.steps().len(), 6);
+        assert_eq!(routine.riffles(), 4);
+        assert_eq!(routine.to_string(), "riffle x3, strip, riffle, cut");
+        assert!("riffle, shuffle".parse::<ShuffleRoutine>().is_err());
+        assert!("riffle x".parse::<ShuffleRoutine>().is_err());
+        assert!("riffle,, cut".parse::<ShuffleRoutine>().is_err());
+    }
+
+    #[test]
+    fn test_strip_reverses_a_few_packets() {
+        let original = Deck::create_standard_deck();
+        let mut cards = original.clone();
+        ShuffleStep::Strip.apply(&mut cards, &mut StdRng::seed_from_u64(3));
+        let position = |card: &Card| original.iter().position(|c| c == card).unwrap();
+        let breaks = cards.windows(2).filter(|w| position(&w[1]) != position(&w[0]) + 1).count();
+        assert!((STRIP_PACKETS.0 - 1..STRIP_PACKETS.1).contains(&breaks), "{} breaks", breaks);
+    }
+}
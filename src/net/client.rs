@@ -0,0 +1,395 @@
+use super::protocol::{ClientMessage, Place, ServerMessage};
+use crate::error;
+use crate::game::rules::Settlement;
+use crate::game::strategy::Action;
+use crate::game::view::{HandView, SeenCard};
+use crate::ui::display::Display;
+use crate::ui::input::{Command, Input};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// The table as pieced together from the server's lines
+///
+/// It is a pure fold over `ServerMessage`s, so a spectator who catches up from a
+/// snapshot ends up with the same table as one who saw every line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteTable {
+    /// Player name at each occupied seat
+    pub names: BTreeMap<usize, String>,
+    /// Number of the round being shown, once one has been dealt
+    pub round: Option<u32>,
+    pub hands: BTreeMap<usize, HandView>,
+    pub banker: HandView,
+    /// The seat that must act next, if any
+    pub turn: Option<usize>,
+    /// Settlements of the current round, by seat
+    pub settlements: BTreeMap<usize, Settlement>,
+}
+
+impl RemoteTable {
+    /// Folds one line from the server into the table
+    pub fn apply(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::Joined { seat, name } => {
+                self.names.insert(*seat, name.clone());
+            }
+            ServerMessage::Left { seat } => {
+                self.names.remove(seat);
+            }
+            ServerMessage::Round { number, seats } => {
+                *self = RemoteTable {
+                    names: std::mem::take(&mut self.names),
+                    round: Some(*number),
+                    hands: seats.iter().map(|seat| (*seat, HandView::default())).collect(),
+                    ..RemoteTable::default()
+                };
+            }
+            ServerMessage::Deal { place, card } => {
+                let seen = card.map_or(SeenCard::FaceDown, SeenCard::FaceUp);
+                match place {
+                    Place::Seat(seat) => self.hands.entry(*seat).or_default().push(seen),
+                    Place::Banker => self.banker.push(seen),
+                }
+            }
+            ServerMessage::Turn { seat, .. } => self.turn = Some(*seat),
+            ServerMessage::Reveal { card } => {
+                self.turn = None;
+                // A delayed feed already shows the hole card face up
+                self.banker.reveal(*card);
+            }
+            ServerMessage::Settle {
+                seat,
+                outcome,
+                player,
+                banker,
+                net,
+            } => {
+                self.turn = None;
+                self.settlements.insert(
+                    *seat,
+                    Settlement {
+                        outcome: *outcome,
+                        player_rank: *player,
+                        banker_rank: *banker,
+                        net: *net,
+                    },
+                );
+            }
+            // Whatever came before is repeated in the snapshot that follows
+            ServerMessage::Watching { .. } => *self = RemoteTable::default(),
+            ServerMessage::Welcome { .. }
+            | ServerMessage::Shuffle
+            | ServerMessage::Replenish { .. }
+            | ServerMessage::Action { .. }
+            | ServerMessage::Warn { .. }
+            | ServerMessage::Timeout { .. }
+            | ServerMessage::Error { .. }
+            | ServerMessage::Bye => {}
+        }
+    }
+
+    /// Returns the name of the player at a seat
+    pub fn name(&self, seat: usize) -> String {
+        self.names.get(&seat).cloned().unwrap_or_else(|| format!("Seat {}", seat))
+    }
+}
+
+/// Plays at or watches a remote table, drawing what the server sends with the usual `Display`
+///
+/// The client only knows what the server has told it, so the banker's face-down
+/// card is shown as hidden until the server reveals it.
+pub struct Client {
+    writer: TcpStream,
+    /// Lines from the server, read on a thread of their own
+    incoming: Receiver<io::Result<String>>,
+    /// Lines taken off `incoming` early, while checking for a timeout
+    backlog: VecDeque<io::Result<String>>,
+    /// The client's seat; None for a spectator
+    seat: Option<usize>,
+    table: RemoteTable,
+    rounds: u32,
+    net: i64,
+}
+
+impl Client {
+    /// Connects to a server and asks for a seat under `name`
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> error::Result<Self> {
+        Self::open(
+            addr,
+            ClientMessage::Join {
+                name: name.to_string(),
+            },
+        )
+    }
+
+    /// Connects to a server as a spectator
+    pub fn watch(addr: impl ToSocketAddrs) -> error::Result<Self> {
+        Self::open(addr, ClientMessage::Watch)
+    }
+
+    fn open(addr: impl ToSocketAddrs, hello: ClientMessage) -> error::Result<Self> {
+        let writer = TcpStream::connect(addr)?;
+        let reader = writer.try_clone()?;
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || read_lines(reader, sender));
+        let mut client = Client {
+            writer,
+            incoming,
+            backlog: VecDeque::new(),
+            seat: None,
+            table: RemoteTable::default(),
+            rounds: 0,
+            net: 0,
+        };
+        client.send(&hello)?;
+        Ok(client)
+    }
+
+    /// Returns the table as this client last saw it
+    pub fn table(&self) -> &RemoteTable {
+        &self.table
+    }
+
+    /// Returns the rounds this client has been settled in
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// Returns this client's winnings so far
+    pub fn net(&self) -> i64 {
+        self.net
+    }
+
+    /// Follows the table until the server closes it or the player quits
+    ///
+    /// Spectators never read from `input`.
+    pub fn run<R: BufRead>(
+        &mut self,
+        display: &Display,
+        input: &mut Input<R>,
+    ) -> error::Result<()> {
+        while let Some(line) = self.backlog.pop_front().or_else(|| self.incoming.recv().ok()) {
+            let message: ServerMessage = match line?.parse() {
+                Ok(message) => message,
+                Err(reason) => {
+                    display.show_message(&format!("Unreadable line from the server: {}", reason));
+                    continue;
+                }
+            };
+            if let ServerMessage::Left { seat } = &message {
+                display.show_message(&format!("{} leaves the table.", self.table.name(*seat)));
+            }
+            let first_settlement = self.table.settlements.is_empty();
+            self.table.apply(&message);
+            match message {
+                ServerMessage::Welcome {
+                    seat, rules, stake, ..
+                } => {
+                    self.seat = Some(seat);
+                    display.show_message(&format!(
+                        "Seated at seat {} ({} rules, stake {}). Waiting for the deal...",
+                        seat, rules, stake
+                    ));
+                }
+                ServerMessage::Watching { delay_ms, .. } => {
+                    let delay = if delay_ms == 0 {
+                        "live".to_string()
+                    } else {
+                        format!("{:.1}s behind, hole cards shown", delay_ms as f64 / 1000.0)
+                    };
+                    display.show_message(&format!("Watching the table ({}).", delay));
+                }
+                ServerMessage::Joined { seat, name } => {
+                    if Some(seat) != self.seat {
+                        display.show_message(&format!("{} sits down at seat {}.", name, seat));
+                    }
+                }
+                ServerMessage::Shuffle => display.show_message("The shoe is reshuffled."),
+                ServerMessage::Replenish { cards } => display.show_message(&format!(
+                    "The shoe ran out; {} discards are shuffled back in.",
+                    cards
+                )),
+                ServerMessage::Round { number, .. } => {
+                    display.show_separator();
+                    display.show_message(&format!("Round {}", number));
+                }
+                ServerMessage::Left { .. }
+                | ServerMessage::Deal { .. }
+                | ServerMessage::Reveal { .. } => {}
+                ServerMessage::Turn { seat, legal } if Some(seat) == self.seat => {
+                    self.show_table(display);
+                    loop {
+                        let choices = if legal.contains(&Action::Stick) {
+                            "(t)wist or (s)tick"
+                        } else {
+                            "(t)wist"
+                        };
+                        display.show_prompt(&format!("Your turn: {}, or (q)uit?", choices));
+                        let command = input.read_command();
+                        if command == Command::Quit {
+                            self.send(&ClientMessage::Quit)?;
+                            return Ok(());
+                        }
+                        if self.timed_out(seat) {
+                            break;
+                        }
+                        let action = match command {
+                            Command::Twist => Action::Twist,
+                            Command::Stick => Action::Stick,
+                            _ => {
+                                display.show_message("Please enter 't', 's' or 'q'.");
+                                continue;
+                            }
+                        };
+                        self.send(&ClientMessage::Action(action))?;
+                        break;
+                    }
+                }
+                ServerMessage::Turn { seat, .. } => {
+                    if self.seat.is_none() {
+                        self.show_table(display);
+                    }
+                    display.show_message(&format!("Waiting for {}...", self.table.name(seat)));
+                }
+                ServerMessage::Action { seat, action } => {
+                    if Some(seat) != self.seat {
+                        let name = self.table.name(seat);
+                        display.show_message(&format!("{} chooses to {}.", name, action));
+                    }
+                }
+                ServerMessage::Settle { seat, net, .. } => {
+                    let settlement = self.table.settlements[&seat];
+                    if Some(seat) == self.seat {
+                        self.show_table(display);
+                        display.show_settlement(&settlement);
+                        self.rounds += 1;
+                        self.net += net;
+                    } else {
+                        if self.seat.is_none() && first_settlement {
+                            self.show_table(display);
+                        }
+                        display.show_message(&format!("{}: {:+}", self.table.name(seat), net));
+                    }
+                }
+                ServerMessage::Warn { .. } => {}
+                ServerMessage::Timeout { seat, action } => {
+                    if Some(seat) != self.seat {
+                        let name = self.table.name(seat);
+                        display.show_message(&format!(
+                            "{} ran out of time; {} was played for them.",
+                            name, action
+                        ));
+                    }
+                }
+                ServerMessage::Error { message } => {
+                    display.show_message(&format!("Server: {}", message))
+                }
+                ServerMessage::Bye => break,
+            }
+        }
+        if self.seat.is_some() {
+            display.show_message(&format!(
+                "The table has closed. You played {} rounds, net {:+}.",
+                self.rounds, self.net
+            ));
+        } else {
+            display.show_message("The table has closed.");
+        }
+        Ok(())
+    }
+
+    /// Returns true if the server has already played for `seat` because it ran out of time
+    ///
+    /// Looks through the lines that came in while the player was deciding.
+    fn timed_out(&mut self, seat: usize) -> bool {
+        self.backlog.extend(self.incoming.try_iter());
+        self.backlog.iter().any(|line| {
+            let message = line.as_ref().ok().and_then(|line| line.parse().ok());
+            matches!(message, Some(ServerMessage::Timeout { seat: to, .. }) if to == seat)
+        })
+    }
+
+    /// Shows the other players' hands, the banker's cards as seen, then this player's hand
+    fn show_table(&self, display: &Display) {
+        for (seat, hand) in &self.table.hands {
+            if Some(*seat) != self.seat {
+                display.show_other_hand(&self.table.name(*seat), hand);
+            }
+        }
+        display.show_banker_view(&self.table.banker);
+        if let Some(hand) = self.seat.and_then(|seat| self.table.hands.get(&seat)?.hand()) {
+            display.show_player_hand(&hand);
+        }
+    }
+
+    fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        writeln!(self.writer, "{}", message)?;
+        self.writer.flush()
+    }
+}
+
+/// Passes the server's lines on, showing the player's time warnings as they arrive
+///
+/// The main thread may be waiting on the keyboard when a warning comes in, so this
+/// thread shows them itself.
+fn read_lines(stream: TcpStream, sender: Sender<io::Result<String>>) {
+    let display = Display::new();
+    let mut seat = None;
+    for line in BufReader::new(stream).lines() {
+        if let Ok(text) = &line {
+            match text.parse() {
+                Ok(ServerMessage::Welcome { seat: mine, .. }) => seat = Some(mine),
+                Ok(ServerMessage::Warn { seat: to, left_ms }) if Some(to) == seat => {
+                    display.show_turn_warning(Duration::from_millis(left_ms))
+                }
+                Ok(ServerMessage::Timeout { seat: to, action }) if Some(to) == seat => {
+                    display.show_timed_out(action);
+                    display.show_message("Press Enter to carry on.");
+                }
+                _ => {}
+            }
+        }
+        if sender.send(line).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::server::{Server, ServerConfig};
+    use std::io::Cursor;
+    use std::thread;
+
+    #[test]
+    fn test_client_plays_remote_rounds_from_scripted_input() {
+        let server = Server::bind(
+            "127.0.0.1:0",
+            ServerConfig {
+                seed: Some(5),
+                rounds: Some(3),
+                ..ServerConfig::default()
+            },
+        )
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || server.run());
+
+        let mut client = Client::connect(addr, "fred").unwrap();
+        // Twisting is always legal, so the client never runs out of moves
+        let mut input = Input::new(Cursor::new("t\n".repeat(20)));
+        client.run(&Display::new(), &mut input).unwrap();
+        handle.join().unwrap().unwrap();
+        assert_eq!(client.rounds(), 3);
+        assert_eq!(client.seat, Some(1));
+        // The hole card was revealed, so the banker's hand is whole again
+        assert_eq!(client.table().banker.face_down(), 0);
+        assert!(client.table().banker.cards().len() >= 2);
+        assert_eq!(client.table().settlements.len(), 1);
+    }
+}
@@ -0,0 +1,11 @@
+//! Playing over a local network: a table server and a client that joins it
+//!
+//! Server and clients exchange the text lines in `NET_PROTOCOL.md` over TCP. The
+//! server owns the deck and deals every round, so a client never learns a card it
+//! could not see at a real table. Spectators follow the table through a feed
+//! that can run behind play so hole cards are shown without giving them away.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+pub mod spectator;
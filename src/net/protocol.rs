@@ -0,0 +1,457 @@
+//! The text lines exchanged between a table server and its clients, described in `NET_PROTOCOL.md`
+
+use crate::game::rules::{HandRank, Outcome};
+use crate::game::strategy::Action;
+use crate::history::{action_name, outcome_name, parse_rank, rank_name};
+use crate::models::card::Card;
+use std::fmt;
+use std::str::FromStr;
+
+/// Version sent in the `welcome` line
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Written in place of a card that is dealt face down
+pub const HIDDEN_CARD: &str = "??";
+
+/// Where a card is dealt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Place {
+    Seat(usize),
+    Banker,
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Place::Seat(seat) => write!(f, "{}", seat),
+            Place::Banker => write!(f, "banker"),
+        }
+    }
+}
+
+impl FromStr for Place {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "banker" => Ok(Place::Banker),
+            _ => number(text).map(Place::Seat),
+        }
+    }
+}
+
+/// A line sent from the server to a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    /// Accepts a `join`, giving the client its seat
+    Welcome {
+        version: u32,
+        seat: usize,
+        rules: String,
+        stake: u32,
+    },
+    /// Accepts a `watch`; the feed runs `delay_ms` behind the table
+    Watching { version: u32, delay_ms: u64 },
+    /// A player has taken a seat
+    Joined { seat: usize, name: String },
+    /// A player has left the table
+    Left { seat: usize },
+    /// The shoe has been reshuffled
+    Shuffle,
+    /// The shoe ran out mid-round and `cards` discards were shuffled back in
+    Replenish { cards: usize },
+    /// A new round is being dealt to these seats
+    Round { number: u32, seats: Vec<usize> },
+    /// A card has been dealt; `None` is the banker's face-down card
+    Deal { place: Place, card: Option<Card> },
+    /// The player at `seat` must act
+    Turn { seat: usize, legal: Vec<Action> },
+    /// The player at `seat` has acted
+    Action { seat: usize, action: Action },
+    /// The player at `seat` has `left_ms` left to act
+    Warn { seat: usize, left_ms: u64 },
+    /// The player at `seat` ran out of time and `action` was played for them
+    Timeout { seat: usize, action: Action },
+    /// The banker has turned over the face-down card
+    Reveal { card: Card },
+    /// How a seat's hand was settled
+    Settle {
+        seat: usize,
+        outcome: Outcome,
+        player: HandRank,
+        banker: HandRank,
+        net: i64,
+    },
+    /// The last line from this client was refused
+    Error { message: String },
+    /// The server is closing the connection
+    Bye,
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerMessage::Welcome {
+                version,
+                seat,
+                rules,
+                stake,
+            } => write!(f, "welcome {} seat={} rules={} stake={}", version, seat, rules, stake),
+            ServerMessage::Watching { version, delay_ms } => {
+                write!(f, "watching {} delay={}", version, delay_ms)
+            }
+            ServerMessage::Joined { seat, name } => write!(f, "join {} {}", seat, name),
+            ServerMessage::Left { seat } => write!(f, "leave {}", seat),
+            ServerMessage::Shuffle => write!(f, "shuffle"),
+            ServerMessage::Replenish { cards } => write!(f, "replenish {}", cards),
+            ServerMessage::Round { number, seats } => {
+                let seats: Vec<String> = seats.iter().map(usize::to_string).collect();
+                write!(f, "round {} seats={}", number, seats.join(","))
+            }
+            ServerMessage::Deal { place, card } => match card {
+                Some(card) => write!(f, "deal {} {}", place, card.notation()),
+                None => write!(f, "deal {} {}", place, HIDDEN_CARD),
+            },
+            ServerMessage::Turn { seat, legal } => {
+                let legal: Vec<&str> = legal.iter().map(|action| action_name(*action)).collect();
+                write!(f, "turn {} legal={}", seat, legal.join(","))
+            }
+            ServerMessage::Action { seat, action } => {
+                write!(f, "action {} {}", seat, action_name(*action))
+            }
+            ServerMessage::Warn { seat, left_ms } => write!(f, "warn {} left={}", seat, left_ms),
+            ServerMessage::Timeout { seat, action } => {
+                write!(f, "timeout {} {}", seat, action_name(*action))
+            }
+            ServerMessage::Reveal { card } => write!(f, "reveal banker {}", card.notation()),
+            ServerMessage::Settle {
+                seat,
+                outcome,
+                player,
+                banker,
+                net,
+            } => write!(
+                f,
+                "settle {} {} player={} banker={} net={}",
+                seat,
+                outcome_name(*outcome),
+                rank_name(*player),
+                rank_name(*banker),
+                net
+            ),
+            ServerMessage::Error { message } => write!(f, "error {}", message),
+            ServerMessage::Bye => write!(f, "bye"),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut words = rest.split_whitespace();
+        let message = match keyword {
+            "welcome" => ServerMessage::Welcome {
+                version: number(next(&mut words)?)?,
+                seat: number(field(&mut words, "seat")?)?,
+                rules: field(&mut words, "rules")?.to_string(),
+                stake: number(field(&mut words, "stake")?)?,
+            },
+            "watching" => ServerMessage::Watching {
+                version: number(next(&mut words)?)?,
+                delay_ms: number(field(&mut words, "delay")?)?,
+            },
+            "join" => {
+                let seat = number(next(&mut words)?)?;
+                let name = words.by_ref().collect::<Vec<_>>().join(" ");
+                if name.is_empty() {
+                    return Err("join needs a name".to_string());
+                }
+                ServerMessage::Joined { seat, name }
+            }
+            "leave" => ServerMessage::Left {
+                seat: number(next(&mut words)?)?,
+            },
+            "shuffle" => ServerMessage::Shuffle,
+            "replenish" => ServerMessage::Replenish {
+                cards: number(next(&mut words)?)?,
+            },
+            "round" => ServerMessage::Round {
+                number: number(next(&mut words)?)?,
+                seats: list(field(&mut words, "seats")?, number)?,
+            },
+            "deal" => {
+                let place = next(&mut words)?.parse()?;
+                let card = match next(&mut words)? {
+                    HIDDEN_CARD => None,
+                    text => Some(card(text)?),
+                };
+                ServerMessage::Deal { place, card }
+            }
+            "turn" => ServerMessage::Turn {
+                seat: number(next(&mut words)?)?,
+                legal: list(field(&mut words, "legal")?, action)?,
+            },
+            "action" => ServerMessage::Action {
+                seat: number(next(&mut words)?)?,
+                action: action(next(&mut words)?)?,
+            },
+            "warn" => ServerMessage::Warn {
+                seat: number(next(&mut words)?)?,
+                left_ms: number(field(&mut words, "left")?)?,
+            },
+            "timeout" => ServerMessage::Timeout {
+                seat: number(next(&mut words)?)?,
+                action: action(next(&mut words)?)?,
+            },
+            "reveal" => {
+                if next(&mut words)? != "banker" {
+                    return Err("only the banker's card is revealed".to_string());
+                }
+                ServerMessage::Reveal {
+                    card: card(next(&mut words)?)?,
+                }
+            }
+            "settle" => ServerMessage::Settle {
+                seat: number(next(&mut words)?)?,
+                outcome: outcome(next(&mut words)?)?,
+                player: rank(field(&mut words, "player")?)?,
+                banker: rank(field(&mut words, "banker")?)?,
+                net: number(field(&mut words, "net")?)?,
+            },
+            "error" => {
+                return Ok(ServerMessage::Error {
+                    message: rest.trim().to_string(),
+                })
+            }
+            "bye" => ServerMessage::Bye,
+            _ => return Err(format!("cannot read '{}'", line)),
+        };
+        match words.next() {
+            Some(extra) => Err(format!("unexpected '{}' in '{}'", extra, line)),
+            None => Ok(message),
+        }
+    }
+}
+
+/// A line sent from a client to the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// Asks for a seat under the given name
+    Join { name: String },
+    /// Asks to watch the table without taking a seat
+    Watch,
+    /// Answers a `turn` for this client's seat
+    Action(Action),
+    /// Leaves the table
+    Quit,
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientMessage::Join { name } => write!(f, "join {}", name),
+            ClientMessage::Watch => write!(f, "watch"),
+            ClientMessage::Action(action) => write!(f, "{}", action_name(*action)),
+            ClientMessage::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for ClientMessage {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match keyword {
+            "join" => {
+                let name = rest.split_whitespace().collect::<Vec<_>>().join(" ");
+                if name.is_empty() {
+                    Err("join needs a name".to_string())
+                } else {
+                    Ok(ClientMessage::Join { name })
+                }
+            }
+            "twist" | "t" if rest.is_empty() => Ok(ClientMessage::Action(Action::Twist)),
+            "stick" | "s" if rest.is_empty() => Ok(ClientMessage::Action(Action::Stick)),
+            "watch" if rest.is_empty() => Ok(ClientMessage::Watch),
+            "quit" if rest.is_empty() => Ok(ClientMessage::Quit),
+            _ => Err(format!("cannot read '{}'", line)),
+        }
+    }
+}
+
+fn next<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<&'a str, String> {
+    words.next().ok_or_else(|| "line ended early".to_string())
+}
+
+/// Reads the next `key=value` word, checking the key
+fn field<'a>(words: &mut impl Iterator<Item = &'a str>, key: &str) -> Result<&'a str, String> {
+    let word = next(words)?;
+    match word.split_once('=') {
+        Some((found, value)) if found == key => Ok(value),
+        _ => Err(format!("expected {}=, got '{}'", key, word)),
+    }
+}
+
+/// Reads a comma-separated list; an empty value is an empty list
+fn list<T>(text: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split(',').map(parse).collect()
+}
+
+fn number<T: FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("'{}' is not a number", text))
+}
+
+fn card(text: &str) -> Result<Card, String> {
+    text.parse().map_err(|error: crate::models::card::ParseCardError| error.to_string())
+}
+
+fn action(text: &str) -> Result<Action, String> {
+    match text {
+        "twist" => Ok(Action::Twist),
+        "stick" => Ok(Action::Stick),
+        "double" => Ok(Action::Double),
+        "split" => Ok(Action::Split),
+        "surrender" => Ok(Action::Surrender),
+        _ => Err(format!("unknown action '{}'", text)),
+    }
+}
+
+fn outcome(text: &str) -> Result<Outcome, String> {
+    match text {
+        "win" => Ok(Outcome::Win),
+        "lose" => Ok(Outcome::Lose),
+        "push" => Ok(Outcome::Push),
+        _ => Err(format!("unknown outcome '{}'", text)),
+    }
+}
+
+fn rank(text: &str) -> Result<HandRank, String> {
+    parse_rank(text).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::card::{Rank, Suit};
+    use proptest::prelude::*;
+
+    fn any_card() -> impl Strategy<Value = Card> {
+        (0usize..13, 0usize..4)
+            .prop_map(|(rank, suit)| Card::new(Rank::all()[rank], Suit::all()[suit]))
+    }
+
+    fn any_action() -> impl Strategy<Value = Action> {
+        prop_oneof![Just(Action::Twist), Just(Action::Stick)]
+    }
+
+    fn any_rank() -> impl Strategy<Value = HandRank> {
+        prop_oneof![
+            Just(HandRank::Bust),
+            (4u8..=21).prop_map(HandRank::Total),
+            Just(HandRank::FiveCardTrick),
+            Just(HandRank::Pontoon),
+        ]
+    }
+
+    fn any_server_message() -> impl Strategy<Value = ServerMessage> {
+        prop_oneof![
+            (1usize..7, 1u32..1000).prop_map(|(seat, stake)| ServerMessage::Welcome {
+                version: PROTOCOL_VERSION,
+                seat,
+                rules: "standard".to_string(),
+                stake,
+            }),
+            (1usize..7, "[A-Za-z]{1,8}( [A-Za-z]{1,8})?")
+                .prop_map(|(seat, name)| ServerMessage::Joined { seat, name }),
+            (1usize..7).prop_map(|seat| ServerMessage::Left { seat }),
+            (0u64..10_000).prop_map(|delay_ms| ServerMessage::Watching {
+                version: PROTOCOL_VERSION,
+                delay_ms,
+            }),
+            Just(ServerMessage::Shuffle),
+            (1usize..300).prop_map(|cards| ServerMessage::Replenish { cards }),
+            (1u32..1000, prop::collection::vec(1usize..7, 0..6))
+                .prop_map(|(number, seats)| ServerMessage::Round { number, seats }),
+            (
+                prop_oneof![(1usize..7).prop_map(Place::Seat), Just(Place::Banker)],
+                prop::option::of(any_card())
+            )
+                .prop_map(|(place, card)| ServerMessage::Deal { place, card }),
+            (1usize..7, prop::collection::vec(any_action(), 1..3))
+                .prop_map(|(seat, legal)| ServerMessage::Turn { seat, legal }),
+            (1usize..7, any_action())
+                .prop_map(|(seat, action)| ServerMessage::Action { seat, action }),
+            (1usize..7, 0u64..120_000)
+                .prop_map(|(seat, left_ms)| ServerMessage::Warn { seat, left_ms }),
+            (1usize..7, any_action())
+                .prop_map(|(seat, action)| ServerMessage::Timeout { seat, action }),
+            any_card().prop_map(|card| ServerMessage::Reveal { card }),
+            (
+                1usize..7,
+                prop_oneof![Just(Outcome::Win), Just(Outcome::Lose), Just(Outcome::Push)],
+                any_rank(),
+                any_rank(),
+                -40i64..40
+            )
+                .prop_map(|(seat, outcome, player, banker, net)| ServerMessage::Settle {
+                    seat,
+                    outcome,
+                    player,
+                    banker,
+                    net,
+                }),
+            "[a-z]{1,8}( [a-z]{1,8}){0,3}".prop_map(|message| ServerMessage::Error { message }),
+            Just(ServerMessage::Bye),
+        ]
+    }
+
+    // Property: Every server line reads back as the message that wrote it
+    proptest! {
+        #[test]
+        fn prop_server_lines_round_trip(message in any_server_message()) {
+            prop_assert_eq!(message.to_string().parse::<ServerMessage>(), Ok(message));
+        }
+    }
+
+    // Property: Every client line reads back as the message that wrote it
+    proptest! {
+        #[test]
+        fn prop_client_lines_round_trip(
+            message in prop_oneof![
+                "[A-Za-z]{1,8}( [A-Za-z]{1,8})?".prop_map(|name| ClientMessage::Join { name }),
+                any_action().prop_map(ClientMessage::Action),
+                Just(ClientMessage::Watch),
+                Just(ClientMessage::Quit),
+            ]
+        ) {
+            prop_assert_eq!(message.to_string().parse::<ClientMessage>(), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_face_down_card_is_never_named() {
+        let line = ServerMessage::Deal {
+            place: Place::Banker,
+            card: None,
+        }
+        .to_string();
+        assert_eq!(line, "deal banker ??");
+        assert_eq!(
+            "turn 2 legal=twist,stick".parse(),
+            Ok(ServerMessage::Turn {
+                seat: 2,
+                legal: vec![Action::Twist, Action::Stick]
+            })
+        );
+        assert!("deal banker".parse::<ServerMessage>().is_err());
+        assert!("stick now".parse::<ClientMessage>().is_err());
+    }
+}
@@ -0,0 +1,851 @@
+use super::protocol::{ClientMessage, Place, ServerMessage, PROTOCOL_VERSION};
+use super::spectator::SpectatorFeed;
+use crate::error::{self, PontoonError};
+use crate::game::banker::StandOn;
+use crate::game::rules::Rules;
+use crate::game::shared::SharedRound;
+use crate::game::strategy::Action;
+use crate::game::timer::{Clock, SystemClock, TimerEvent, TurnLimit, TurnTimer, CLOCK_CHECK};
+use crate::game::view::{SeenCard, Viewer};
+use crate::logging::{self, Level, Record};
+use crate::models::deck::Deck;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Port the server listens on when none is given
+pub const DEFAULT_PORT: u16 = 7021;
+
+
+/// Settings for a hosted table
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub rules: Rules,
+    pub stake: u32,
+    pub decks: usize,
+    /// Seed for the shoe; None shuffles from entropy
+    pub seed: Option<u64>,
+    /// Rounds to deal before closing the table; None plays until stopped
+    pub rounds: Option<u32>,
+    /// Players that must be seated before a round is dealt
+    pub min_players: usize,
+    pub max_players: usize,
+    /// How far the spectator feed runs behind play; zero keeps it live with the hole card hidden
+    pub spectator_delay: Duration,
+    /// Time allowed for each decision; None waits for ever
+    pub turn_limit: Option<TurnLimit>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            rules: Rules::standard(),
+            stake: 10,
+            decks: 1,
+            seed: None,
+            rounds: None,
+            min_players: 1,
+            max_players: 6,
+            spectator_delay: Duration::ZERO,
+            turn_limit: None,
+        }
+    }
+}
+
+/// Something that happened on a connection, passed from its reader thread to the table
+enum Incoming {
+    Connected(usize, TcpStream),
+    Line(usize, Result<ClientMessage, String>),
+    Disconnected(usize),
+}
+
+/// A connected client, seated once it has sent `join`
+struct Connection {
+    stream: TcpStream,
+    seat: Option<usize>,
+}
+
+/// Hosts one table over TCP
+///
+/// The server owns the `Deck`: clients are only ever sent the cards they could see
+/// at a real table, and the banker's face-down card goes out as `??` until it is
+/// turned over. Spectators get the same lines from a `SpectatorFeed`; a delayed
+/// feed deals the hole card face up only if the hand is over by the time it goes out.
+pub struct Server {
+    listener: TcpListener,
+    config: ServerConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl Server {
+    /// Listens on `addr`; port 0 picks a free port
+    ///
+    /// Fails with `InvalidConfig` for rules with Blackjack options, which a shared table
+    /// does not offer.
+    pub fn bind(addr: impl ToSocketAddrs, config: ServerConfig) -> error::Result<Self> {
+        config.rules.check_shared()?;
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            config,
+            clock: Arc::new(SystemClock::new()),
+        })
+    }
+
+    /// Times turns by `clock` instead of the wall clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the address the server is listening on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts players and deals rounds until `config.rounds` have been played
+    pub fn run(self) -> error::Result<()> {
+        let (sender, incoming) = mpsc::channel();
+        let listener = self.listener.try_clone()?;
+        thread::spawn(move || accept(listener, sender));
+        Host::new(self.config, self.clock, incoming).run()
+    }
+}
+
+/// Hands each new connection a reader thread that forwards its lines
+fn accept(listener: TcpListener, sender: Sender<Incoming>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else { continue };
+        let Ok(reader) = stream.try_clone() else { continue };
+        if sender.send(Incoming::Connected(id, stream)).is_err() {
+            return;
+        }
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if sender.send(Incoming::Line(id, line.parse())).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(Incoming::Disconnected(id));
+        });
+    }
+}
+
+/// The table itself: the deck, the seats and the connections
+struct Host {
+    config: ServerConfig,
+    deck: Deck,
+    incoming: Receiver<Incoming>,
+    connections: BTreeMap<usize, Connection>,
+    /// Player name at each occupied seat
+    names: BTreeMap<usize, String>,
+    feed: SpectatorFeed,
+    clock: Arc<dyn Clock>,
+}
+
+impl Host {
+    fn new(config: ServerConfig, clock: Arc<dyn Clock>, incoming: Receiver<Incoming>) -> Self {
+        let deck = match config.seed {
+            Some(seed) => Deck::new_shoe_seeded(config.decks, seed),
+            None => Deck::new_shoe(config.decks),
+        };
+        logging::set_seed(deck.seed());
+        Host {
+            feed: SpectatorFeed::start(config.spectator_delay),
+            config,
+            deck,
+            incoming,
+            connections: BTreeMap::new(),
+            names: BTreeMap::new(),
+            clock,
+        }
+    }
+
+    fn run(mut self) -> error::Result<()> {
+        let mut number = 0;
+        while self.config.rounds.is_none_or(|rounds| number < rounds) {
+            while self.names.len() < self.config.min_players.max(1) {
+                let event = self.receive()?;
+                self.handle(event, None);
+            }
+            while let Ok(event) = self.incoming.try_recv() {
+                self.handle(event, None);
+            }
+            if self.names.is_empty() {
+                continue;
+            }
+            number += 1;
+            self.play_round(number)?;
+        }
+        self.feed.publish(&ServerMessage::Bye);
+        for connection in self.connections.values_mut() {
+            let _ = writeln!(connection.stream, "{}", ServerMessage::Bye);
+            let _ = connection.stream.shutdown(Shutdown::Both);
+        }
+        self.feed.close();
+        Ok(())
+    }
+
+    fn play_round(&mut self, number: u32) -> error::Result<()> {
+        let seats: Vec<usize> = self.names.keys().copied().collect();
+        if self.deck.needs_reshuffle() {
+            self.deck.reshuffle();
+            self.broadcast(&ServerMessage::Shuffle);
+        }
+        let mut round = SharedRound::deal(
+            &mut self.deck,
+            self.config.rules.clone(),
+            self.config.stake,
+            &seats,
+        )?;
+        self.announce_replenished(&mut round);
+        self.broadcast(&ServerMessage::Round {
+            number,
+            seats: seats.clone(),
+        });
+        // Every line is read off the table as an onlooker sees it, so the hole card cannot leak
+        let view = round.view(Viewer::Spectator);
+        for pass in 0..2 {
+            for seat in &view.seats {
+                self.broadcast(&ServerMessage::Deal {
+                    place: Place::Seat(seat.seat),
+                    card: seat.hand.cards()[pass].card(),
+                });
+            }
+            let seen = ServerMessage::Deal {
+                place: Place::Banker,
+                card: view.banker.cards()[pass].card(),
+            };
+            // Only a delayed feed may show the hole card, and only once the hand is over
+            let face_down = view.banker.cards()[pass].card().is_none();
+            if face_down && !self.config.spectator_delay.is_zero() {
+                self.tell_connections(&seen);
+                self.feed.publish_hole_card(round.banker_hand().cards()[pass]);
+            } else {
+                self.broadcast(&seen);
+            }
+        }
+
+        let mut prompt = true;
+        // Times the decision being waited on; a refused answer does not restart it
+        let mut timer = None;
+        while let Some(seat) = round.turn() {
+            if !self.names.contains_key(&seat) {
+                round.stand_down();
+                prompt = true;
+                timer = None;
+                continue;
+            }
+            if timer.is_none() {
+                timer = self
+                    .config
+                    .turn_limit
+                    .as_ref()
+                    .map(|limit| TurnTimer::start(limit, &*self.clock));
+            }
+            if prompt {
+                self.broadcast(&ServerMessage::Turn {
+                    seat,
+                    legal: round.legal_actions(),
+                });
+                prompt = false;
+            }
+            let turn = match self.wait(seat, timer.as_mut())? {
+                Some(event) => self.handle(event, Some(seat)),
+                None => {
+                    let on_timeout = self.config.turn_limit.as_ref().map(|l| l.on_timeout);
+                    let action = on_timeout
+                        .and_then(|on_timeout| on_timeout.choose(&round.view(Viewer::Seat(seat))))
+                        .expect("Only a running turn can time out");
+                    logging::log(Record::new(Level::Info, "server", "turn timed out")
+                        .with("seat", seat)
+                        .with("action", action));
+                    self.broadcast(&ServerMessage::Timeout { seat, action });
+                    Some(Turn::Act(action))
+                }
+            };
+            match turn {
+                Some(Turn::Act(action)) => match round.apply(&mut self.deck, action) {
+                    Ok(dealt) => {
+                        prompt = true;
+                        timer = None;
+                        self.broadcast(&ServerMessage::Action { seat, action });
+                        self.announce_replenished(&mut round);
+                        if let Some(card) = dealt {
+                            self.broadcast(&ServerMessage::Deal {
+                                place: Place::Seat(seat),
+                                card: Some(card),
+                            });
+                        }
+                    }
+                    Err(PontoonError::IllegalAction { .. }) => {
+                        self.send_to_seat(
+                            seat,
+                            &ServerMessage::Error {
+                                message: format!("{} is not a legal action", action),
+                            },
+                        );
+                        prompt = true;
+                    }
+                    Err(error) => return Err(error),
+                },
+                Some(Turn::Leave) => {
+                    round.stand_down();
+                    prompt = true;
+                    timer = None;
+                }
+                None => {}
+            }
+        }
+
+        round.play_banker(&mut self.deck, &StandOn::house(&self.config.rules))?;
+        let view = round.view(Viewer::Spectator);
+        let banker = view.banker.cards();
+        if let Some(SeenCard::FaceUp(card)) = banker.get(1) {
+            self.broadcast(&ServerMessage::Reveal { card: *card });
+        }
+        self.announce_replenished(&mut round);
+        for seen in banker.iter().skip(2) {
+            self.broadcast(&ServerMessage::Deal {
+                place: Place::Banker,
+                card: seen.card(),
+            });
+        }
+        for seat in &view.seats {
+            let settlement = seat.settlement.expect("The banker has played");
+            self.broadcast(&ServerMessage::Settle {
+                seat: seat.seat,
+                outcome: settlement.outcome,
+                player: settlement.player_rank,
+                banker: settlement.banker_rank,
+                net: settlement.net,
+            });
+        }
+        Ok(())
+    }
+
+    /// Tells everyone the shoe was topped up from the discards, if it has been since last asked
+    fn announce_replenished(&mut self, round: &mut SharedRound) {
+        for cards in round.take_replenished() {
+            let record = Record::new(Level::Info, "server", "shoe replenished");
+            logging::log(record.with("cards", cards));
+            self.broadcast(&ServerMessage::Replenish { cards });
+        }
+    }
+
+    /// Waits for the next event from any connection
+    fn receive(&self) -> io::Result<Incoming> {
+        self.incoming
+            .recv()
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the listener has stopped"))
+    }
+
+    /// Waits for the next event while `seat` decides; None once their time has run out
+    ///
+    /// Warnings from the timer are sent to everyone as they fall due.
+    fn wait(&mut self, seat: usize, timer: Option<&mut TurnTimer>) -> io::Result<Option<Incoming>> {
+        let Some(timer) = timer else {
+            return self.receive().map(Some);
+        };
+        loop {
+            match timer.poll(&*self.clock) {
+                Some(TimerEvent::Expired) => return Ok(None),
+                Some(TimerEvent::Warning(left)) => self.broadcast(&ServerMessage::Warn {
+                    seat,
+                    left_ms: left.as_millis() as u64,
+                }),
+                None => {}
+            }
+            let until_next = timer.until_next(&*self.clock).unwrap_or(Duration::ZERO);
+            match self.incoming.recv_timeout(until_next.min(CLOCK_CHECK)) {
+                Ok(event) => return Ok(Some(event)),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::BrokenPipe,
+                        "the listener has stopped",
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Applies one event; returns what the player at `turn` did, if it was them
+    fn handle(&mut self, event: Incoming, turn: Option<usize>) -> Option<Turn> {
+        match event {
+            Incoming::Connected(id, stream) => {
+                self.connections.insert(id, Connection { stream, seat: None });
+                None
+            }
+            Incoming::Line(id, Err(reason)) => {
+                logging::log(Record::new(Level::Warn, "server", &reason).with("connection", id));
+                self.send(id, &ServerMessage::Error { message: reason });
+                None
+            }
+            Incoming::Line(id, Ok(ClientMessage::Join { name })) => {
+                self.join(id, name);
+                None
+            }
+            Incoming::Line(id, Ok(ClientMessage::Watch)) => {
+                self.watch(id);
+                None
+            }
+            Incoming::Line(id, Ok(ClientMessage::Action(action))) => {
+                let seat = self.connections.get(&id).and_then(|connection| connection.seat);
+                if seat.is_some() && seat == turn {
+                    return Some(Turn::Act(action));
+                }
+                let message = if seat.is_none() {
+                    "join the table first"
+                } else {
+                    "it is not your turn"
+                };
+                self.send(
+                    id,
+                    &ServerMessage::Error {
+                        message: message.to_string(),
+                    },
+                );
+                None
+            }
+            Incoming::Line(id, Ok(ClientMessage::Quit)) => {
+                self.send(id, &ServerMessage::Bye);
+                self.leave(id, turn)
+            }
+            Incoming::Disconnected(id) => self.leave(id, turn),
+        }
+    }
+
+    fn join(&mut self, id: usize, name: String) {
+        let Some(connection) = self.connections.get(&id) else { return };
+        if connection.seat.is_some() {
+            self.send(
+                id,
+                &ServerMessage::Error {
+                    message: "you are already seated".to_string(),
+                },
+            );
+            return;
+        }
+        let free = (1..=self.config.max_players).find(|seat| !self.names.contains_key(seat));
+        let Some(seat) = free else {
+            self.send(
+                id,
+                &ServerMessage::Error {
+                    message: "the table is full".to_string(),
+                },
+            );
+            self.send(id, &ServerMessage::Bye);
+            self.drop_connection(id);
+            return;
+        };
+        self.send(
+            id,
+            &ServerMessage::Welcome {
+                version: PROTOCOL_VERSION,
+                seat,
+                rules: self.config.rules.name.to_string(),
+                stake: self.config.stake,
+            },
+        );
+        let seated: Vec<(usize, String)> =
+            self.names.iter().map(|(seat, name)| (*seat, name.clone())).collect();
+        for (seat, name) in seated {
+            self.send(id, &ServerMessage::Joined { seat, name });
+        }
+        if let Some(connection) = self.connections.get_mut(&id) {
+            connection.seat = Some(seat);
+        }
+        logging::log(Record::new(Level::Info, "server", "player joined")
+            .with("seat", seat)
+            .with("name", &name));
+        self.names.insert(seat, name.clone());
+        self.broadcast(&ServerMessage::Joined { seat, name });
+    }
+
+    /// Hands a connection over to the spectator feed
+    fn watch(&mut self, id: usize) {
+        let seated = match self.connections.get(&id) {
+            Some(connection) => connection.seat.is_some(),
+            None => return,
+        };
+        if seated {
+            self.send(
+                id,
+                &ServerMessage::Error {
+                    message: "you are already seated".to_string(),
+                },
+            );
+            return;
+        }
+        // Its reader thread carries on, but nothing it sends can reach the table now
+        if let Some(connection) = self.connections.remove(&id) {
+            self.feed.watch(connection.stream);
+        }
+    }
+
+    /// Drops a connection, freeing its seat; returns `Turn::Leave` if it was their turn
+    fn leave(&mut self, id: usize, turn: Option<usize>) -> Option<Turn> {
+        let seat = self.drop_connection(id)?;
+        self.names.remove(&seat);
+        logging::log(Record::new(Level::Info, "server", "player left").with("seat", seat));
+        self.broadcast(&ServerMessage::Left { seat });
+        (Some(seat) == turn).then_some(Turn::Leave)
+    }
+
+    /// Closes a connection, returning the seat it held
+    fn drop_connection(&mut self, id: usize) -> Option<usize> {
+        let connection = self.connections.remove(&id)?;
+        let _ = connection.stream.shutdown(Shutdown::Both);
+        connection.seat
+    }
+
+    fn send(&mut self, id: usize, message: &ServerMessage) {
+        if let Some(connection) = self.connections.get_mut(&id) {
+            // A failed write shows up as a disconnect from the reader thread
+            let _ = writeln!(connection.stream, "{}", message);
+        }
+    }
+
+    fn send_to_seat(&mut self, seat: usize, message: &ServerMessage) {
+        let id = self
+            .connections
+            .iter()
+            .find(|(_, connection)| connection.seat == Some(seat))
+            .map(|(id, _)| *id);
+        if let Some(id) = id {
+            self.send(id, message);
+        }
+    }
+
+    /// Sends a line to every seated player and to the spectators
+    fn broadcast(&mut self, message: &ServerMessage) {
+        self.tell_connections(message);
+        self.feed.publish(message);
+    }
+
+    /// Sends a line to every seated player, but not to the spectators
+    ///
+    /// A connection that has not sent `join` yet is sent nothing, so `welcome` is always
+    /// the first line a player receives.
+    fn tell_connections(&mut self, message: &ServerMessage) {
+        for connection in self.connections.values_mut() {
+            if connection.seat.is_some() {
+                let _ = writeln!(connection.stream, "{}", message);
+            }
+        }
+    }
+}
+
+/// What the player whose turn it is did
+enum Turn {
+    Act(Action),
+    Leave,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::timer::{ManualClock, TimeoutAction};
+    use crate::models::card::Card;
+    use crate::net::client::RemoteTable;
+    use std::io::{BufReader, Lines};
+    use std::net::TcpStream;
+    use std::time::Instant;
+
+    /// Starts a server on a free loopback port
+    fn serve(config: ServerConfig) -> (SocketAddr, thread::JoinHandle<error::Result<()>>) {
+        let server = Server::bind("127.0.0.1:0", config).unwrap();
+        let addr = server.local_addr().unwrap();
+        (addr, thread::spawn(move || server.run()))
+    }
+
+    /// A bare client that twists below `stick_on` and records every line it is sent
+    fn play(addr: SocketAddr, name: &str, stick_on: u8) -> Vec<ServerMessage> {
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, "join {}", name).unwrap();
+        let mut seat = 0;
+        let mut hand: Vec<Card> = Vec::new();
+        let mut seen = Vec::new();
+        for line in BufReader::new(stream).lines() {
+            let message: ServerMessage = line.unwrap().parse().unwrap();
+            match &message {
+                ServerMessage::Welcome { seat: mine, .. } => seat = *mine,
+                ServerMessage::Round { .. } => hand.clear(),
+                ServerMessage::Deal {
+                    place: Place::Seat(to),
+                    card: Some(card),
+                } if *to == seat => hand.push(*card),
+                ServerMessage::Turn { seat: to, legal } if *to == seat => {
+                    let mut held = crate::models::hand::Hand::new();
+                    hand.iter().for_each(|card| held.add_card(*card));
+                    let total = crate::game::rules::HandValue::of(&held).total;
+                    let action = if total >= stick_on && legal.contains(&Action::Stick) {
+                        Action::Stick
+                    } else {
+                        Action::Twist
+                    };
+                    writeln!(writer, "{}", ClientMessage::Action(action)).unwrap();
+                }
+                _ => {}
+            }
+            let bye = message == ServerMessage::Bye;
+            seen.push(message);
+            if bye {
+                break;
+            }
+        }
+        seen
+    }
+
+    /// Connects a spectator and waits until the feed has taken it on
+    fn spectate(addr: SocketAddr) -> Lines<BufReader<TcpStream>> {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        writeln!(stream, "watch").unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let first = lines.next().unwrap().unwrap();
+        assert!(first.starts_with("watching"), "got {}", first);
+        lines
+    }
+
+    /// Follows a spectator's lines to the end, returning the table and every line seen
+    fn follow(lines: Lines<BufReader<TcpStream>>) -> (RemoteTable, Vec<ServerMessage>) {
+        let mut table = RemoteTable::default();
+        let mut seen = Vec::new();
+        for line in lines {
+            let message: ServerMessage = line.unwrap().parse().unwrap();
+            table.apply(&message);
+            seen.push(message);
+        }
+        (table, seen)
+    }
+
+    #[test]
+    fn test_two_players_share_a_table_without_seeing_the_hole_card() {
+        let (addr, server) = serve(ServerConfig {
+            seed: Some(21),
+            rounds: Some(8),
+            min_players: 2,
+            ..ServerConfig::default()
+        });
+        let first = thread::spawn(move || play(addr, "alice", 15));
+        let second = thread::spawn(move || play(addr, "bob", 18));
+        let first = first.join().unwrap();
+        let second = second.join().unwrap();
+        server.join().unwrap().unwrap();
+
+        for seen in [&first, &second] {
+            let settles = seen.iter().filter(|m| matches!(m, ServerMessage::Settle { .. }));
+            assert_eq!(settles.count(), 16);
+            // Within each round the hole card is named only by `reveal`
+            let mut named: Vec<Card> = Vec::new();
+            for message in seen {
+                match message {
+                    ServerMessage::Round { .. } => named.clear(),
+                    ServerMessage::Deal { card: Some(card), .. } => named.push(*card),
+                    ServerMessage::Reveal { card } => assert!(!named.contains(card)),
+                    _ => {}
+                }
+            }
+        }
+        let from_first_round = |seen: &[ServerMessage]| {
+            let start = seen.iter().position(|m| matches!(m, ServerMessage::Round { .. }));
+            seen[start.unwrap()..].to_vec()
+        };
+        assert_eq!(from_first_round(&first), from_first_round(&second));
+    }
+
+    #[test]
+    fn test_moves_before_joining_are_refused() {
+        let (addr, server) = serve(ServerConfig {
+            seed: Some(3),
+            rounds: Some(1),
+            ..ServerConfig::default()
+        });
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        writeln!(writer, "stick").unwrap();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "error join the table first"
+        );
+        writeln!(writer, "join carol").unwrap();
+        assert_eq!(lines.next().unwrap().unwrap(), "welcome 1 seat=1 rules=standard stake=10");
+        // Twist until the round is over, then expect the server to close the table
+        let mut seen = Vec::new();
+        for line in lines {
+            let message: ServerMessage = line.unwrap().parse().unwrap();
+            if let ServerMessage::Turn { .. } = message {
+                writeln!(writer, "twist").unwrap();
+            }
+            seen.push(message);
+        }
+        assert_eq!(seen.last(), Some(&ServerMessage::Bye));
+        assert!(seen.iter().any(|m| matches!(m, ServerMessage::Settle { seat: 1, .. })));
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_leaving_mid_turn_moves_play_on() {
+        let (addr, server) = serve(ServerConfig {
+            seed: Some(8),
+            rounds: Some(2),
+            ..ServerConfig::default()
+        });
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, "join dave").unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let first: ServerMessage = lines.next().unwrap().unwrap().parse().unwrap();
+        assert!(matches!(first, ServerMessage::Welcome { .. }), "got {:?}", first);
+        for line in lines {
+            if line.unwrap().starts_with("round") {
+                break;
+            }
+        }
+        writeln!(writer, "quit").unwrap();
+        drop(writer);
+        // The table carries on for whoever joins next, and mid-round lines are not sent
+        // to them before their welcome
+        let seen = play(addr, "erin", 17);
+        let seat = match seen[0] {
+            ServerMessage::Welcome { seat, .. } => seat,
+            ref other => panic!("expected welcome first, got {:?}", other),
+        };
+        let rounds: Vec<&ServerMessage> =
+            seen.iter().filter(|m| matches!(m, ServerMessage::Round { .. })).collect();
+        assert_eq!(
+            rounds.last(),
+            Some(&&ServerMessage::Round {
+                number: 2,
+                seats: vec![seat]
+            })
+        );
+        assert_eq!(seen.last(), Some(&ServerMessage::Bye));
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_delayed_spectators_see_the_hole_card_and_late_ones_catch_up() {
+        let delay = Duration::from_millis(300);
+        let (addr, server) = serve(ServerConfig {
+            seed: Some(13),
+            rounds: Some(4),
+            spectator_delay: delay,
+            ..ServerConfig::default()
+        });
+        let early = spectate(addr);
+        let early = thread::spawn(move || follow(early));
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, "join fay").unwrap();
+        let mut late = None;
+        let mut played = Vec::new();
+        for line in BufReader::new(stream).lines() {
+            let message: ServerMessage = line.unwrap().parse().unwrap();
+            if let ServerMessage::Turn { .. } = message {
+                // The table waits on this player, so the late spectator arrives mid-round
+                let third = |m: &ServerMessage| matches!(m, ServerMessage::Round { number: 3, .. });
+                if late.is_none() && played.iter().any(third) {
+                    let lines = spectate(addr);
+                    late = Some(thread::spawn(move || follow(lines)));
+                }
+                writeln!(writer, "twist").unwrap();
+            }
+            played.push(message);
+        }
+        let finished = Instant::now();
+        let (early_table, early_seen) = early.join().unwrap();
+        let watched = Instant::now();
+        let (late_table, _) = late.expect("the player had a turn").join().unwrap();
+        server.join().unwrap().unwrap();
+
+        // The feed closes only once its last line, sent a delay after the player's, is out
+        assert!(watched.duration_since(finished) >= delay / 2);
+        assert_eq!(early_seen.last(), Some(&ServerMessage::Bye));
+        assert!(played.contains(&ServerMessage::Deal {
+            place: Place::Banker,
+            card: None
+        }));
+        assert!(!early_seen.iter().any(|m| matches!(m, ServerMessage::Deal { card: None, .. })));
+        assert_eq!(early_table.round, Some(4));
+        assert_eq!(early_table.banker.face_down(), 0);
+        assert_eq!(late_table, early_table);
+    }
+
+    #[test]
+    fn test_live_spectators_see_the_hole_card_only_when_revealed() {
+        let (addr, server) = serve(ServerConfig {
+            seed: Some(2),
+            rounds: Some(2),
+            ..ServerConfig::default()
+        });
+        let spectator = thread::spawn(move || follow(spectate(addr)));
+        let played = play(addr, "gus", 17);
+        let (table, seen) = spectator.join().unwrap();
+        server.join().unwrap().unwrap();
+        let from_first_round = |seen: &[ServerMessage]| {
+            let start = seen.iter().position(|m| matches!(m, ServerMessage::Round { .. }));
+            seen[start.unwrap()..].to_vec()
+        };
+        assert_eq!(from_first_round(&seen), from_first_round(&played));
+        assert_eq!(table.settlements.len(), 1);
+    }
+
+    #[test]
+    fn test_stalled_player_is_warned_then_played_for() {
+        let clock = ManualClock::new();
+        let config = ServerConfig {
+            seed: Some(4),
+            rounds: Some(2),
+            turn_limit: Some(TurnLimit::new(Duration::from_secs(30), TimeoutAction::Stick)),
+            ..ServerConfig::default()
+        };
+        let server = Server::bind("127.0.0.1:0", config)
+            .unwrap()
+            .with_clock(Arc::new(clock.clone()));
+        let addr = server.local_addr().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        writeln!(stream, "join hal").unwrap();
+        let mut lines = BufReader::new(stream)
+            .lines()
+            .map(|line| line.unwrap().parse::<ServerMessage>().unwrap());
+        let legal = loop {
+            if let Some(ServerMessage::Turn { legal, .. }) = lines.next() {
+                break legal;
+            }
+        };
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(lines.next(), Some(ServerMessage::Warn { seat: 1, left_ms: 15_000 }));
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(lines.next(), Some(ServerMessage::Warn { seat: 1, left_ms: 5_000 }));
+        clock.advance(Duration::from_secs(5));
+        let action = if legal.contains(&Action::Stick) { Action::Stick } else { Action::Twist };
+        assert_eq!(lines.next(), Some(ServerMessage::Timeout { seat: 1, action }));
+        assert_eq!(lines.next(), Some(ServerMessage::Action { seat: 1, action }));
+
+        // Every later decision is left to the clock too, and the table still finishes
+        let mut settled = 0;
+        for message in lines {
+            match message {
+                ServerMessage::Turn { .. } => clock.advance(Duration::from_secs(30)),
+                ServerMessage::Settle { seat: 1, .. } => settled += 1,
+                _ => {}
+            }
+        }
+        assert_eq!(settled, 2);
+        server.join().unwrap().unwrap();
+    }
+}
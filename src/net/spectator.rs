@@ -0,0 +1,330 @@
+use super::protocol::{Place, ServerMessage, PROTOCOL_VERSION};
+use crate::models::card::Card;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long a slow spectator may hold up the feed before being dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Holds lines back until `delay` has passed since they were published
+#[derive(Debug, Clone)]
+pub struct DelayLine {
+    delay: Duration,
+    /// Each line with when it falls due, and the banker's hole card if the line deals it
+    pending: VecDeque<(Instant, ServerMessage, Option<Card>)>,
+}
+
+impl DelayLine {
+    pub fn new(delay: Duration) -> Self {
+        DelayLine {
+            delay,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Queues a line published at `at`
+    pub fn push(&mut self, at: Instant, message: ServerMessage) {
+        self.pending.push_back((at + self.delay, message, None));
+    }
+
+    /// Queues the deal of the banker's face-down `card`, published at `at`
+    ///
+    /// The card is shown only if the table has turned it over, or moved on to another
+    /// round, by the time the line falls due. Otherwise a delay shorter than the hand
+    /// would show it to spectators while players are still deciding, so it goes out
+    /// face down and the `reveal` line shows it later.
+    pub fn push_hole_card(&mut self, at: Instant, card: Card) {
+        let face_down = ServerMessage::Deal {
+            place: Place::Banker,
+            card: None,
+        };
+        self.pending.push_back((at + self.delay, face_down, Some(card)));
+    }
+
+    /// Returns when the next line falls due, if any are waiting
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.front().map(|(due, _, _)| *due)
+    }
+
+    /// Removes and returns every line due by `now`, oldest first
+    pub fn release(&mut self, now: Instant) -> Vec<ServerMessage> {
+        let mut released = Vec::new();
+        while let Some((due, _, _)) = self.pending.front() {
+            if *due > now {
+                break;
+            }
+            let Some((_, message, hole_card)) = self.pending.pop_front() else {
+                break;
+            };
+            // Everything still pending was published after this line, so a reveal or a new
+            // round among it means the hand is over at the table
+            let hand_over = self.pending.iter().any(|(_, later, _)| {
+                matches!(later, ServerMessage::Reveal { .. } | ServerMessage::Round { .. })
+            });
+            released.push(match hole_card {
+                Some(card) if hand_over => ServerMessage::Deal {
+                    place: Place::Banker,
+                    card: Some(card),
+                },
+                _ => message,
+            });
+        }
+        released
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// The lines a spectator needs to catch up: who is seated and the round so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    seated: BTreeMap<usize, String>,
+    /// Every line since the current round's `round` line, that line included
+    round: Vec<ServerMessage>,
+}
+
+impl Snapshot {
+    /// Takes note of a line sent to spectators
+    pub fn record(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::Joined { seat, name } => {
+                self.seated.insert(*seat, name.clone());
+            }
+            ServerMessage::Left { seat } => {
+                self.seated.remove(seat);
+            }
+            ServerMessage::Round { .. } => self.round = vec![message.clone()],
+            ServerMessage::Welcome { .. }
+            | ServerMessage::Watching { .. }
+            | ServerMessage::Shuffle
+            | ServerMessage::Replenish { .. }
+            | ServerMessage::Error { .. }
+            | ServerMessage::Bye => {}
+            _ if self.round.is_empty() => {}
+            _ => self.round.push(message.clone()),
+        }
+    }
+
+    /// Returns the lines that rebuild the table as it stands
+    pub fn lines(&self) -> Vec<ServerMessage> {
+        let seated = self.seated.iter().map(|(seat, name)| ServerMessage::Joined {
+            seat: *seat,
+            name: name.clone(),
+        });
+        seated.chain(self.round.iter().cloned()).collect()
+    }
+}
+
+enum FeedCommand {
+    Publish(Instant, ServerMessage),
+    HoleCard(Instant, Card),
+    Watch(TcpStream),
+    Close,
+}
+
+/// Sends the table to any number of spectators from a thread of its own
+///
+/// Lines are held back by the feed's delay, so a stream of the table can show
+/// the hole card from the deal once the hand is over by the time it goes out. A
+/// spectator who connects part way through is first sent a snapshot to catch up from.
+pub struct SpectatorFeed {
+    commands: Sender<FeedCommand>,
+    thread: JoinHandle<()>,
+}
+
+impl SpectatorFeed {
+    /// Starts a feed running `delay` behind the table
+    pub fn start(delay: Duration) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || run(delay, receiver));
+        SpectatorFeed { commands, thread }
+    }
+
+    /// Passes a line on to spectators once the delay has passed
+    pub fn publish(&self, message: &ServerMessage) {
+        let _ = self
+            .commands
+            .send(FeedCommand::Publish(Instant::now(), message.clone()));
+    }
+
+    /// Passes on the deal of the banker's face-down card, shown only if the hand is over
+    /// by the time the delay has passed
+    pub fn publish_hole_card(&self, card: Card) {
+        let _ = self.commands.send(FeedCommand::HoleCard(Instant::now(), card));
+    }
+
+    /// Adds a spectator
+    pub fn watch(&self, stream: TcpStream) {
+        let _ = self.commands.send(FeedCommand::Watch(stream));
+    }
+
+    /// Sends every line still held back, then disconnects the spectators
+    pub fn close(self) {
+        let _ = self.commands.send(FeedCommand::Close);
+        let _ = self.thread.join();
+    }
+}
+
+fn run(delay: Duration, commands: Receiver<FeedCommand>) {
+    let mut line = DelayLine::new(delay);
+    let mut snapshot = Snapshot::default();
+    let mut spectators: Vec<TcpStream> = Vec::new();
+    let mut closing = false;
+    while !(closing && line.is_empty()) {
+        let command = match line.next_due() {
+            Some(due) => commands.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match command {
+            Ok(FeedCommand::Publish(at, message)) => line.push(at, message),
+            Ok(FeedCommand::HoleCard(at, card)) => line.push_hole_card(at, card),
+            Ok(FeedCommand::Watch(mut stream)) => {
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                let welcome = ServerMessage::Watching {
+                    version: PROTOCOL_VERSION,
+                    delay_ms: delay.as_millis() as u64,
+                };
+                let caught_up = std::iter::once(welcome)
+                    .chain(snapshot.lines())
+                    .all(|message| writeln!(stream, "{}", message).is_ok());
+                if caught_up {
+                    spectators.push(stream);
+                }
+            }
+            Ok(FeedCommand::Close) | Err(RecvTimeoutError::Disconnected) => closing = true,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        for message in line.release(Instant::now()) {
+            snapshot.record(&message);
+            spectators.retain_mut(|stream| writeln!(stream, "{}", message).is_ok());
+        }
+    }
+    for stream in spectators {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::{HandRank, Outcome};
+    use crate::game::strategy::Action;
+    use crate::models::card::{Card, Rank, Suit};
+    use crate::net::client::RemoteTable;
+    use crate::net::protocol::Place;
+    use proptest::prelude::*;
+
+    fn any_line() -> impl Strategy<Value = ServerMessage> {
+        let card = (0usize..13, 0usize..4)
+            .prop_map(|(rank, suit)| Card::new(Rank::all()[rank], Suit::all()[suit]));
+        prop_oneof![
+            (1usize..4, "[a-z]{1,6}").prop_map(|(seat, name)| ServerMessage::Joined { seat, name }),
+            (1usize..4).prop_map(|seat| ServerMessage::Left { seat }),
+            Just(ServerMessage::Shuffle),
+            (1usize..100).prop_map(|cards| ServerMessage::Replenish { cards }),
+            (1u32..50, prop::collection::vec(1usize..4, 1..4))
+                .prop_map(|(number, seats)| ServerMessage::Round { number, seats }),
+            (
+                prop_oneof![(1usize..4).prop_map(Place::Seat), Just(Place::Banker)],
+                prop::option::of(card.clone())
+            )
+                .prop_map(|(place, card)| ServerMessage::Deal { place, card }),
+            (1usize..4).prop_map(|seat| ServerMessage::Turn {
+                seat,
+                legal: vec![Action::Twist]
+            }),
+            card.prop_map(|card| ServerMessage::Reveal { card }),
+            (1usize..4, -20i64..20).prop_map(|(seat, net)| ServerMessage::Settle {
+                seat,
+                outcome: Outcome::Win,
+                player: HandRank::Total(20),
+                banker: HandRank::Bust,
+                net,
+            }),
+        ]
+    }
+
+    // Property: A late spectator's snapshot rebuilds the same table as the whole feed
+    proptest! {
+        #[test]
+        fn prop_snapshot_catches_up(lines in prop::collection::vec(any_line(), 0..60)) {
+            let mut snapshot = Snapshot::default();
+            let mut watched = RemoteTable::default();
+            for line in &lines {
+                snapshot.record(line);
+                watched.apply(line);
+            }
+            let mut caught_up = RemoteTable::default();
+            for line in snapshot.lines() {
+                caught_up.apply(&line);
+            }
+            // Before the first round there is no round to catch up on
+            if watched.round.is_some() {
+                prop_assert_eq!(caught_up, watched);
+            } else {
+                prop_assert_eq!(caught_up.names, watched.names);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hole_card_is_shown_only_once_the_hand_is_over() {
+        let start = Instant::now();
+        let card = Card::new(Rank::King, Suit::Spades);
+        let deal = |card| ServerMessage::Deal {
+            place: Place::Banker,
+            card,
+        };
+        let ms = Duration::from_millis;
+        // The hand takes 300ms at the table, so a 100ms delay would give the card away
+        for (delay, shown) in [(500, Some(card)), (100, None)] {
+            let mut line = DelayLine::new(ms(delay));
+            line.push_hole_card(start, card);
+            let mut released = line.release(start + ms(299));
+            line.push(start + ms(300), ServerMessage::Reveal { card });
+            released.extend(line.release(start + ms(300 + delay)));
+            assert_eq!(released, vec![deal(shown), ServerMessage::Reveal { card }]);
+        }
+    }
+
+    // Property: Lines come out in order, and none before its delay has passed
+    proptest! {
+        #[test]
+        fn prop_delay_line_keeps_order_and_time(
+            gaps in prop::collection::vec(0u64..50, 1..30),
+            delay in 0u64..100,
+            checks in prop::collection::vec(0u64..2_000, 1..10)
+        ) {
+            let start = Instant::now();
+            let mut line = DelayLine::new(Duration::from_millis(delay));
+            let mut at = 0;
+            for (index, gap) in gaps.iter().enumerate() {
+                at += gap;
+                line.push(start + Duration::from_millis(at), ServerMessage::Left { seat: index });
+            }
+            let mut checks = checks;
+            checks.sort_unstable();
+            let mut released = Vec::new();
+            for check in checks {
+                for message in line.release(start + Duration::from_millis(check)) {
+                    let ServerMessage::Left { seat } = message else { unreachable!() };
+                    let published: u64 = gaps[..=seat].iter().sum();
+                    prop_assert!(published + delay <= check);
+                    released.push(seat);
+                }
+            }
+            for message in line.release(start + Duration::from_secs(60)) {
+                let ServerMessage::Left { seat } = message else { unreachable!() };
+                released.push(seat);
+            }
+            prop_assert_eq!(released, (0..gaps.len()).collect::<Vec<_>>());
+            prop_assert!(line.is_empty());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+pub mod shuffle;
+pub mod stats;
+pub mod tracking;
+
+use crate::error::Result;
+use crate::game::banker::BankerStrategy;
+use crate::game::round::play_round;
+use crate::game::rules::{HandRank, Outcome, Rules, Settlement};
+use crate::game::strategy::PlayerStrategy;
+use crate::models::deck::{mix_seed, Deck};
+use crate::models::deck_type::DeckType;
+use stats::{mean_interval, proportion_interval, ConfidenceInterval};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+/// Rounds played from each independently seeded deck
+///
+/// Work is handed to threads in batches of this size, and each batch's deck is
+/// seeded from the batch number, so results do not depend on the thread count.
+pub const BATCH_ROUNDS: u64 = 10_000;
+
+/// Settings for a Monte Carlo run
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub rounds: u64,
+    pub seed: u64,
+    pub threads: usize,
+    pub stake: u32,
+    pub rules: Rules,
+    pub deck_type: DeckType,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            rounds: 1_000_000,
+            seed: 0,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            stake: 100,
+            rules: Rules::standard(),
+            deck_type: DeckType::standard(),
+        }
+    }
+}
+
+/// Running totals for a set of settled rounds
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub rounds: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub pushes: u64,
+    pub player_pontoons: u64,
+    pub player_five_card_tricks: u64,
+    pub player_busts: u64,
+    pub sum_net: i128,
+    pub sum_net_squared: u128,
+    /// Number of rounds that ended with each net result, in chips
+    pub payouts: BTreeMap<i64, u64>,
+}
+
+impl Tally {
+    /// Adds one settled round
+    pub fn record(&mut self, settlement: &Settlement) {
+        self.rounds += 1;
+        match settlement.outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Lose => self.losses += 1,
+            Outcome::Push => self.pushes += 1,
+        }
+        match settlement.player_rank {
+            HandRank::Pontoon => self.player_pontoons += 1,
+            HandRank::FiveCardTrick => self.player_five_card_tricks += 1,
+            HandRank::Bust => self.player_busts += 1,
+            HandRank::Total(_) => {}
+        }
+        self.sum_net += i128::from(settlement.net);
+        self.sum_net_squared += settlement.net.unsigned_abs() as u128 * settlement.net.unsigned_abs() as u128;
+        *self.payouts.entry(settlement.net).or_insert(0) += 1;
+    }
+
+    /// Combines another tally into this one
+    pub fn merge(&mut self, other: &Tally) {
+        self.rounds += other.rounds;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.pushes += other.pushes;
+        self.player_pontoons += other.player_pontoons;
+        self.player_five_card_tricks += other.player_five_card_tricks;
+        self.player_busts += other.player_busts;
+        self.sum_net += other.sum_net;
+        self.sum_net_squared += other.sum_net_squared;
+        for (net, count) in &other.payouts {
+            *self.payouts.entry(*net).or_insert(0) += count;
+        }
+    }
+}
+
+/// The outcome of a Monte Carlo run
+#[derive(Debug, Clone)]
+pub struct SimReport {
+    pub player: String,
+    pub banker: String,
+    pub config: SimConfig,
+    pub tally: Tally,
+}
+
+impl SimReport {
+    /// The banker's expected gain per unit staked (negative favours the player)
+    pub fn house_edge(&self) -> ConfidenceInterval {
+        let stake = f64::from(self.config.stake);
+        let player_return = mean_interval(
+            self.tally.rounds,
+            self.tally.sum_net as f64,
+            self.tally.sum_net_squared as f64,
+        );
+        ConfidenceInterval {
+            estimate: -player_return.estimate / stake,
+            low: -player_return.high / stake,
+            high: -player_return.low / stake,
+        }
+    }
+
+    /// Fraction of rounds the player won
+    pub fn win_rate(&self) -> ConfidenceInterval {
+        proportion_interval(self.tally.wins, self.tally.rounds)
+    }
+
+    /// Fraction of rounds the player lost
+    pub fn loss_rate(&self) -> ConfidenceInterval {
+        proportion_interval(self.tally.losses, self.tally.rounds)
+    }
+
+    /// Fraction of rounds that pushed
+    pub fn push_rate(&self) -> ConfidenceInterval {
+        proportion_interval(self.tally.pushes, self.tally.rounds)
+    }
+}
+
+impl fmt::Display for SimReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |interval: ConfidenceInterval| interval.scaled(100.0);
+        writeln!(f, "Rules:       {}", self.config.rules.name)?;
+        writeln!(f, "Deck:        {}", self.config.deck_type.name)?;
+        writeln!(f, "Player:      {}", self.player)?;
+        writeln!(f, "Banker:      {}", self.banker)?;
+        writeln!(f, "Seed:        {}", self.config.seed)?;
+        writeln!(f, "Rounds:      {}", self.tally.rounds)?;
+        writeln!(f)?;
+        writeln!(f, "House edge % {}", percent(self.house_edge()))?;
+        writeln!(f, "Win %        {}", percent(self.win_rate()))?;
+        writeln!(f, "Loss %       {}", percent(self.loss_rate()))?;
+        writeln!(f, "Push %       {}", percent(self.push_rate()))?;
+        writeln!(f)?;
+        writeln!(f, "Player Pontoons:         {}", self.tally.player_pontoons)?;
+        writeln!(f, "Player Five Card Tricks: {}", self.tally.player_five_card_tricks)?;
+        writeln!(f, "Player busts:            {}", self.tally.player_busts)?;
+        writeln!(f)?;
+        writeln!(f, "Payout distribution (units of stake):")?;
+        let stake = f64::from(self.config.stake);
+        for (net, count) in &self.tally.payouts {
+            let share = *count as f64 / self.tally.rounds.max(1) as f64 * 100.0;
+            writeln!(f, "  {:>+6.2}  {:>12}  {:>7.3}%", *net as f64 / stake, count, share)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays `config.rounds` rounds across `config.threads` threads and tallies the results
+///
+/// The same seed always gives the same report, whatever the thread count.
+pub fn run(
+    config: &SimConfig,
+    player: &dyn PlayerStrategy,
+    banker: &dyn BankerStrategy,
+) -> Result<SimReport> {
+    let batches = config.rounds.div_ceil(BATCH_ROUNDS);
+    let next_batch = AtomicU64::new(0);
+    let threads = config.threads.max(1);
+
+    let tally = thread::scope(|scope| -> Result<Tally> {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| -> Result<Tally> {
+                    let mut tally = Tally::default();
+                    loop {
+                        let batch = next_batch.fetch_add(1, Ordering::Relaxed);
+                        if batch >= batches {
+                            break;
+                        }
+                        let rounds = BATCH_ROUNDS.min(config.rounds - batch * BATCH_ROUNDS);
+                        run_batch(config, batch, rounds, player, banker, &mut tally)?;
+                    }
+                    Ok(tally)
+                })
+            })
+            .collect();
+
+        let mut total = Tally::default();
+        for worker in workers {
+            total.merge(&worker.join().expect("Simulation thread panicked")?);
+        }
+        Ok(total)
+    })?;
+
+    Ok(SimReport {
+        player: player.name(),
+        banker: banker.name(),
+        config: config.clone(),
+        tally,
+    })
+}
+
+fn run_batch(
+    config: &SimConfig,
+    batch: u64,
+    rounds: u64,
+    player: &dyn PlayerStrategy,
+    banker: &dyn BankerStrategy,
+    tally: &mut Tally,
+) -> Result<()> {
+    let mut deck = Deck::of_type_seeded(&config.deck_type, 1, mix_seed(config.seed, batch));
+    for _ in 0..rounds {
+        if deck.needs_reshuffle() {
+            deck.reshuffle();
+        }
+        let (_, settlement) = play_round(&mut deck, &config.rules, config.stake, player, banker)?;
+        tally.record(&settlement);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::banker::StandOn;
+    use crate::game::strategy::{BasicStrategy, StickOn};
+    use proptest::prelude::*;
+
+    fn config(rounds: u64, seed: u64, threads: usize) -> SimConfig {
+        SimConfig {
+            rounds,
+            seed,
+            threads,
+            ..SimConfig::default()
+        }
+    }
+
+    // Property: Results are identical for any thread count
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+        #[test]
+        fn prop_thread_count_does_not_change_results(seed in any::<u64>(), threads in 2usize..6) {
+            let rounds = BATCH_ROUNDS * 2 + 17;
+            let single =
+                run(&config(rounds, seed, 1), &BasicStrategy, &StandOn::default()).unwrap();
+            let multi =
+                run(&config(rounds, seed, threads), &BasicStrategy, &StandOn::default()).unwrap();
+            prop_assert_eq!(single.tally, multi.tally);
+        }
+    }
+
+    // Property: Every round is counted exactly once
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn prop_outcomes_sum_to_rounds(rounds in 0u64..3_000, seed in any::<u64>()) {
+            let stick = StickOn { total: 16 };
+            let report = run(&config(rounds, seed, 3), &stick, &StandOn::default()).unwrap();
+            let tally = &report.tally;
+            prop_assert_eq!(tally.rounds, rounds);
+            prop_assert_eq!(tally.wins + tally.losses + tally.pushes, rounds);
+            prop_assert_eq!(tally.payouts.values().sum::<u64>(), rounds);
+        }
+    }
+
+    #[test]
+    fn test_every_deck_preset_plays_out() {
+        for deck_type in DeckType::presets() {
+            let config = SimConfig {
+                deck_type,
+                ..config(2_000, 3, 2)
+            };
+            let report = run(&config, &BasicStrategy, &StandOn::default()).unwrap();
+            assert_eq!(report.tally.rounds, 2_000);
+        }
+    }
+
+    #[test]
+    fn test_every_rule_preset_plays_out() {
+        for rules in Rules::presets() {
+            let banker = StandOn::house(&rules);
+            let config = SimConfig {
+                rules,
+                ..config(2_000, 3, 2)
+            };
+            let report = run(&config, &BasicStrategy, &banker).unwrap();
+            assert_eq!(report.tally.rounds, 2_000);
+        }
+    }
+
+    #[test]
+    fn test_house_edge_favours_banker_for_naive_play() {
+        // Sticking on 21 only busts constantly, so the banker must come out ahead
+        let report =
+            run(&config(20_000, 7, 2), &StickOn { total: 21 }, &StandOn::default()).unwrap();
+        assert!(report.house_edge().low > 0.0);
+    }
+}
@@ -0,0 +1,448 @@
+use super::stats::ChiSquared;
+use crate::models::card::{Card, Rank, Suit};
+use crate::models::deck::Deck;
+use crate::models::shuffle::ShuffleRoutine;
+use std::fmt;
+
+/// Settings for a shuffle quality report
+#[derive(Debug, Clone)]
+pub struct ShuffleReportConfig {
+    /// Shuffles counted by each test
+    pub trials: u64,
+    pub seed: u64,
+    /// A test fails if its p-value is below this
+    pub significance: f64,
+    /// Sizes of the small decks whose every ordering is counted
+    pub small_decks: Vec<usize>,
+    /// A dealer's routine to check instead of `Deck::shuffle`, starting from new-deck order
+    pub routine: Option<ShuffleRoutine>,
+}
+
+impl Default for ShuffleReportConfig {
+    fn default() -> Self {
+        ShuffleReportConfig {
+            trials: 100_000,
+            seed: 0,
+            significance: 0.001,
+            small_decks: vec![3, 4, 5],
+            routine: None,
+        }
+    }
+}
+
+/// One statistical check of a shuffle
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShuffleTest {
+    pub name: String,
+    /// What was counted, in a sentence
+    pub description: String,
+    pub trials: u64,
+    pub result: ChiSquared,
+}
+
+/// The results of every check, with what is needed to repeat them
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShuffleReport {
+    /// The routine checked, or `None` for `Deck::shuffle`
+    pub routine: Option<ShuffleRoutine>,
+    pub seed: u64,
+    pub significance: f64,
+    pub tests: Vec<ShuffleTest>,
+    /// Total variation distance from uniform of the full deck's rising sequence count
+    pub distance: f64,
+}
+
+impl ShuffleReport {
+    /// Returns true if no test found evidence of bias
+    pub fn passed(&self) -> bool {
+        self.tests.iter().all(|test| test.result.passes(self.significance))
+    }
+}
+
+impl fmt::Display for ShuffleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Shuffle quality report")?;
+        let shuffle = match &self.routine {
+            Some(routine) => format!("{}, from new-deck order each trial", routine),
+            None => "Deck::shuffle (Fisher-Yates on StdRng), each trial".to_string(),
+        };
+        writeln!(f, "Shuffle:      {}", shuffle)?;
+        writeln!(f, "Seed:         {}", self.seed)?;
+        writeln!(f, "Significance: {}", self.significance)?;
+        for test in &self.tests {
+            let verdict = if test.result.passes(self.significance) { "PASS" } else { "FAIL" };
+            writeln!(f)?;
+            writeln!(f, "{} ({} shuffles)", test.name, test.trials)?;
+            writeln!(f, "  {}", test.description)?;
+            writeln!(f, "  {}  {}", test.result, verdict)?;
+        }
+        writeln!(f)?;
+        let distance = self.distance;
+        writeln!(f, "Distance from uniform: {:.3} (rising sequences, total variation)", distance)?;
+        if self.passed() {
+            writeln!(f, "Verdict: no evidence of bias at the {} level", self.significance)
+        } else {
+            let level = self.significance;
+            writeln!(f, "Verdict: BIASED - at least one test failed at the {} level", level)
+        }
+    }
+}
+
+/// Runs every check on `Deck::shuffle`, or the configured routine: a full deck, then
+/// each small deck
+pub fn report(config: &ShuffleReportConfig) -> ShuffleReport {
+    let routine = config.routine.as_ref();
+    let mut deck = Deck::new_seeded(config.seed);
+    let mut tests = vec![
+        position_test(config.trials, reshuffles(&mut deck, routine)),
+        adjacent_rank_test(config.trials, reshuffles(&mut deck, routine)),
+    ];
+    // The deck is gathered in this order before every shuffle
+    let start: Vec<Card> = Deck::create_standard_deck().into_iter().rev().collect();
+    let distance =
+        rising_sequence_distance(&start, config.trials, reshuffles(&mut deck, routine));
+    for size in &config.small_decks {
+        let cards = Deck::create_standard_deck().into_iter().take(*size).collect();
+        let mut small = Deck::from_cards(cards, config.seed);
+        tests.push(permutation_test(*size, config.trials, reshuffles(&mut small, routine)));
+    }
+    ShuffleReport {
+        routine: config.routine.clone(),
+        seed: config.seed,
+        significance: config.significance,
+        tests,
+        distance,
+    }
+}
+
+/// Yields the deck's order after each reshuffle, by `routine` if there is one
+fn reshuffles<'a>(
+    deck: &'a mut Deck,
+    routine: Option<&'a ShuffleRoutine>,
+) -> impl FnMut() -> Vec<Card> + 'a {
+    move || {
+        match routine {
+            Some(routine) => deck.reshuffle_with(routine),
+            None => deck.reshuffle(),
+        }
+        deck.cards_in_deal_order()
+    }
+}
+
+/// Returns a card's place in a fresh, unshuffled deck (0 to 51)
+fn card_index(card: &Card) -> usize {
+    let suit = Suit::all()
+        .iter()
+        .position(|suit| *suit == card.suit())
+        .expect("Suit::all contains every suit");
+    let rank = Rank::all()
+        .iter()
+        .position(|rank| *rank == card.rank())
+        .expect("Rank::all contains every rank");
+    suit * 13 + rank
+}
+
+fn rank_index(card: &Card) -> usize {
+    card_index(card) % 13
+}
+
+/// Counts where each card of a 52-card deck lands over `trials` shuffles
+///
+/// Every card should be equally likely in every position. Each shuffle fills every
+/// row and column of the 52 × 52 table once, so it has 51² degrees of freedom.
+pub fn position_test(trials: u64, mut shuffled: impl FnMut() -> Vec<Card>) -> ShuffleTest {
+    let mut observed = vec![0u64; 52 * 52];
+    for _ in 0..trials {
+        let order = shuffled();
+        assert_eq!(order.len(), 52, "The position test needs a 52-card deck");
+        for (position, card) in order.iter().enumerate() {
+            observed[card_index(card) * 52 + position] += 1;
+        }
+    }
+    let expected = vec![trials as f64 / 52.0; 52 * 52];
+    ShuffleTest {
+        name: "Card positions".to_string(),
+        description: "How often each of the 52 cards lands in each of the 52 positions"
+            .to_string(),
+        trials,
+        result: ChiSquared::test(&observed, &expected, 51 * 51),
+    }
+}
+
+/// Counts the ranks of neighbouring cards over `trials` shuffles of a 52-card deck
+///
+/// After a fair shuffle a card's rank says nothing about the next card's, beyond
+/// there being one fewer of its own rank left. Clumps of tens left over from play
+/// would show up here. Every rank appears four times in each shuffle, which fixes the
+/// row and column totals of the 13 × 13 table, leaving 12² degrees of freedom.
+pub fn adjacent_rank_test(trials: u64, mut shuffled: impl FnMut() -> Vec<Card>) -> ShuffleTest {
+    let mut observed = vec![0u64; 13 * 13];
+    for _ in 0..trials {
+        let order = shuffled();
+        assert_eq!(order.len(), 52, "The adjacent rank test needs a 52-card deck");
+        for pair in order.windows(2) {
+            observed[rank_index(&pair[0]) * 13 + rank_index(&pair[1])] += 1;
+        }
+    }
+    let pairs = trials as f64 * 51.0;
+    let expected: Vec<f64> = (0..13 * 13)
+        .map(|cell| {
+            let same_rank = cell / 13 == cell % 13;
+            let ways = if same_rank { 4.0 * 3.0 } else { 4.0 * 4.0 };
+            pairs * ways / (52.0 * 51.0)
+        })
+        .collect();
+    ShuffleTest {
+        name: "Adjacent ranks".to_string(),
+        description: "How often a card of each rank is followed by a card of each rank"
+            .to_string(),
+        trials,
+        result: ChiSquared::test(&observed, &expected, 12 * 12),
+    }
+}
+
+/// Counts every ordering of a `size`-card deck over `trials` shuffles
+///
+/// All `size`! orderings should come up equally often.
+pub fn permutation_test(
+    size: usize,
+    trials: u64,
+    mut shuffled: impl FnMut() -> Vec<Card>,
+) -> ShuffleTest {
+    let orderings: usize = (1..=size).product();
+    let mut observed = vec![0u64; orderings];
+    for _ in 0..trials {
+        let order = shuffled();
+        assert_eq!(order.len(), size, "Every shuffle must hold the same cards");
+        observed[ordering_index(&order)] += 1;
+    }
+    let expected = vec![trials as f64 / orderings as f64; orderings];
+    ShuffleTest {
+        name: format!("Orderings of a {}-card deck", size),
+        description: format!("How often each of the {} orderings comes up", orderings),
+        trials,
+        result: ChiSquared::test(&observed, &expected, orderings as u64 - 1),
+    }
+}
+
+/// Numbers an ordering of distinct cards from 0 to n! - 1 by its Lehmer code
+fn ordering_index(order: &[Card]) -> usize {
+    let indices: Vec<usize> = order.iter().map(card_index).collect();
+    let mut index = 0;
+    for (i, card) in indices.iter().enumerate() {
+        let smaller_later = indices[i + 1..].iter().filter(|later| *later < card).count();
+        index = index * (indices.len() - i) + smaller_later;
+    }
+    index
+}
+
+/// Counts the rising sequences of `after` relative to `before`
+///
+/// A rising sequence is a run of cards that were next to each other in `before` and
+/// are still in the same order, though maybe apart. A riffle can at most double the
+/// count, so a few riffles of a sorted deck leave tell-tale long runs. The cards must
+/// be distinct.
+pub fn rising_sequences(before: &[Card], after: &[Card]) -> usize {
+    let position = |card: &Card| after.iter().position(|c| c == card);
+    let positions: Vec<Option<usize>> = before.iter().map(position).collect();
+    1 + positions.windows(2).filter(|pair| pair[1] < pair[0]).count()
+}
+
+/// Returns the chance of each rising sequence count (1 to `cards`) in a uniformly
+/// random order: the Eulerian numbers divided by `cards`!
+pub fn uniform_rising_sequences(cards: usize) -> Vec<f64> {
+    let mut chances = vec![1.0];
+    for n in 2..=cards {
+        chances = (1..=n)
+            .map(|r| {
+                let same = if r < n { r as f64 * chances[r - 1] } else { 0.0 };
+                let fewer = if r >= 2 { (n - r + 1) as f64 * chances[r - 2] } else { 0.0 };
+                (same + fewer) / n as f64
+            })
+            .collect();
+    }
+    chances
+}
+
+/// Returns the exact total variation distance from uniform after `riffles` riffles of
+/// a sorted deck of `cards` cards
+///
+/// Bayer and Diaconis showed a GSR riffle sequence gives an order with `r` rising
+/// sequences a chance of C(2^k + n - r, n) / 2^(kn), so only the count matters. For 52
+/// cards the distance only drops below a half at seven riffles.
+pub fn riffle_distance(cards: usize, riffles: u32) -> f64 {
+    let hands = 2f64.powi(riffles as i32);
+    let uniform = uniform_rising_sequences(cards);
+    let total: f64 = (1..=cards)
+        .map(|r| {
+            // n! C(2^k + n - r, n) / 2^(kn): the chance relative to uniform's 1/n!
+            let ratio: f64 = (0..cards)
+                .map(|i| ((hands + (cards - r) as f64 - i as f64) / hands).max(0.0))
+                .product();
+            uniform[r - 1] * (ratio - 1.0).abs()
+        })
+        .sum();
+    total / 2.0
+}
+
+/// Estimates how far a shuffle is from uniform by the total variation distance between
+/// its rising sequence counts over `trials` shuffles and those of a uniform order
+///
+/// Each shuffle must start from `start`. For riffles alone this matches
+/// [`riffle_distance`]; for other routines it is a lower bound on the true distance.
+/// Sampling noise adds roughly `1.5 / sqrt(trials)`.
+pub fn rising_sequence_distance(
+    start: &[Card],
+    trials: u64,
+    mut shuffled: impl FnMut() -> Vec<Card>,
+) -> f64 {
+    let mut counts = vec![0u64; start.len()];
+    for _ in 0..trials {
+        counts[rising_sequences(start, &shuffled()) - 1] += 1;
+    }
+    let uniform = uniform_rising_sequences(start.len());
+    let total: f64 = counts
+        .iter()
+        .zip(&uniform)
+        .map(|(count, chance)| (*count as f64 / trials as f64 - chance).abs())
+        .sum();
+    total / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::rngs::StdRng;
+    use crate::models::shuffle::ShuffleStep;
+    use rand::{Rng, SeedableRng};
+
+    const SIGNIFICANCE: f64 = 0.001;
+
+    // Property: Ordering indices number the orderings of a small deck without gaps
+    proptest! {
+        #[test]
+        fn prop_ordering_index_is_a_bijection(size in 1usize..=5) {
+            let cards: Vec<Card> = Deck::create_standard_deck().into_iter().take(size).collect();
+            let mut seen = std::collections::HashSet::new();
+            let mut deck = Deck::from_cards(cards, 1);
+            // Far more shuffles than orderings, so every ordering comes up
+            for _ in 0..2_000 {
+                deck.reshuffle();
+                let index = ordering_index(&deck.cards_in_deal_order());
+                prop_assert!(index < (1..=size).product());
+                seen.insert(index);
+            }
+            prop_assert_eq!(seen.len(), (1..=size).product::<usize>());
+        }
+    }
+
+    #[test]
+    fn test_deck_shuffle_passes_every_test() {
+        let report = report(&ShuffleReportConfig {
+            trials: 4_000,
+            seed: 44,
+            ..ShuffleReportConfig::default()
+        });
+        assert_eq!(report.tests.len(), 5);
+        for test in &report.tests {
+            assert!(test.result.passes(SIGNIFICANCE), "{}: {}", test.name, test.result);
+        }
+        assert!(report.to_string().contains("no evidence of bias"));
+    }
+
+    /// The classic mistake of swapping each card with any card, not only those not yet placed
+    fn naive_shuffle(rng: &mut StdRng, cards: &mut [Card]) {
+        for i in 0..cards.len() {
+            let j = rng.gen_range(0..cards.len());
+            cards.swap(i, j);
+        }
+    }
+
+    #[test]
+    fn test_naive_shuffle_is_caught() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let small: Vec<Card> = Deck::create_standard_deck().into_iter().take(3).collect();
+        let test = permutation_test(3, 6_000, || {
+            let mut cards = small.clone();
+            naive_shuffle(&mut rng, &mut cards);
+            cards
+        });
+        assert!(!test.result.passes(SIGNIFICANCE), "{}", test.result);
+
+        let full = Deck::create_standard_deck();
+        let test = position_test(4_000, || {
+            let mut cards = full.clone();
+            naive_shuffle(&mut rng, &mut cards);
+            cards
+        });
+        assert!(!test.result.passes(SIGNIFICANCE), "{}", test.result);
+    }
+
+    #[test]
+    fn test_a_cut_is_caught_by_adjacent_ranks() {
+        // Cutting a sorted deck keeps neighbours together
+        let mut rng = StdRng::seed_from_u64(8);
+        let full = Deck::create_standard_deck();
+        let test = adjacent_rank_test(500, || {
+            let mut cards = full.clone();
+            cards.rotate_left(rng.gen_range(0..52));
+            cards
+        });
+        assert!(!test.result.passes(SIGNIFICANCE), "{}", test.result);
+    }
+
+    #[test]
+    fn test_riffle_distance_matches_bayer_diaconis() {
+        // Table 1 of Bayer and Diaconis, "Trailing the dovetail shuffle to its lair"
+        let published = [(5, 0.924), (6, 0.614), (7, 0.334), (8, 0.167), (9, 0.085)];
+        for (riffles, distance) in published {
+            let exact = riffle_distance(52, riffles);
+            assert!((exact - distance).abs() < 0.001, "{} riffles: {}", riffles, exact);
+        }
+        assert!(riffle_distance(52, 4) > 0.99);
+    }
+
+    // Property: Rising sequence chances for a uniform order sum to one
+    proptest! {
+        #[test]
+        fn prop_uniform_rising_sequences_sum_to_one(cards in 1usize..=60) {
+            let chances = uniform_rising_sequences(cards);
+            prop_assert_eq!(chances.len(), cards);
+            prop_assert!((chances.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_rising_sequences_of_known_orders() {
+        let sorted = Deck::create_standard_deck();
+        assert_eq!(rising_sequences(&sorted, &sorted), 1);
+        let reversed: Vec<Card> = sorted.iter().rev().copied().collect();
+        assert_eq!(rising_sequences(&sorted, &reversed), 52);
+    }
+
+    #[test]
+    fn test_simulated_riffles_match_the_exact_distance() {
+        let start: Vec<Card> = Deck::create_standard_deck().into_iter().rev().collect();
+        let mut deck = Deck::new_seeded(45);
+        let routine = ShuffleRoutine::repeat(ShuffleStep::Riffle, 7);
+        let riffled = reshuffles(&mut deck, Some(&routine));
+        let distance = rising_sequence_distance(&start, 5_000, riffled);
+        assert!((distance - riffle_distance(52, 7)).abs() < 0.05, "{}", distance);
+
+        let distance = rising_sequence_distance(&start, 5_000, reshuffles(&mut deck, None));
+        assert!(distance < 0.05, "{}", distance);
+    }
+
+    #[test]
+    fn test_three_riffles_fail_the_report() {
+        let report = report(&ShuffleReportConfig {
+            trials: 2_000,
+            routine: Some("riffle x3, cut".parse().unwrap()),
+            ..ShuffleReportConfig::default()
+        });
+        assert!(!report.passed());
+        assert!(report.distance > 0.99);
+        assert!(report.to_string().contains("riffle x3, cut, from new-deck order"));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+
+/// z-score for a two-sided 95% confidence interval
+pub const Z_95: f64 = 1.959_963_984_540_054;
+
+/// An estimate with a 95% confidence interval around it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceInterval {
+    pub estimate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl ConfidenceInterval {
+    /// Builds an interval of `estimate` ± 1.96 standard errors
+    pub fn from_standard_error(estimate: f64, standard_error: f64) -> Self {
+        ConfidenceInterval {
+            estimate,
+            low: estimate - Z_95 * standard_error,
+            high: estimate + Z_95 * standard_error,
+        }
+    }
+
+    /// Returns true if `value` lies inside the interval
+    pub fn contains(&self, value: f64) -> bool {
+        self.low <= value && value <= self.high
+    }
+
+    /// Scales all three values, e.g. to convert a fraction to a percentage
+    pub fn scaled(&self, factor: f64) -> Self {
+        ConfidenceInterval {
+            estimate: self.estimate * factor,
+            low: self.low * factor,
+            high: self.high * factor,
+        }
+    }
+}
+
+impl fmt::Display for ConfidenceInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.4} (95% CI {:.4} to {:.4})", self.estimate, self.low, self.high)
+    }
+}
+
+/// Normal-approximation interval for a proportion of `successes` out of `trials`
+pub fn proportion_interval(successes: u64, trials: u64) -> ConfidenceInterval {
+    if trials == 0 {
+        return ConfidenceInterval::from_standard_error(0.0, 0.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    ConfidenceInterval::from_standard_error(p, (p * (1.0 - p) / n).sqrt())
+}
+
+/// Interval for a mean given the count, sum and sum of squares of the samples
+pub fn mean_interval(count: u64, sum: f64, sum_of_squares: f64) -> ConfidenceInterval {
+    if count == 0 {
+        return ConfidenceInterval::from_standard_error(0.0, 0.0);
+    }
+    let n = count as f64;
+    let mean = sum / n;
+    let variance = if count > 1 {
+        ((sum_of_squares - n * mean * mean) / (n - 1.0)).max(0.0)
+    } else {
+        0.0
+    };
+    ConfidenceInterval::from_standard_error(mean, (variance / n).sqrt())
+}
+
+/// A chi-squared goodness-of-fit test of observed counts against expected ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquared {
+    pub statistic: f64,
+    pub degrees_of_freedom: u64,
+    /// Chance of a statistic at least this large if the counts really follow the expectation
+    pub p_value: f64,
+}
+
+impl ChiSquared {
+    /// Compares each observed count with its expected count
+    ///
+    /// `degrees_of_freedom` is usually the number of cells less one, and less again for
+    /// any totals fixed by how the counts were taken.
+    pub fn test(observed: &[u64], expected: &[f64], degrees_of_freedom: u64) -> Self {
+        assert_eq!(observed.len(), expected.len(), "Every cell needs an expected count");
+        let statistic = observed
+            .iter()
+            .zip(expected)
+            .filter(|(_, expected)| **expected > 0.0)
+            .map(|(observed, expected)| (*observed as f64 - expected).powi(2) / expected)
+            .sum();
+        ChiSquared {
+            statistic,
+            degrees_of_freedom,
+            p_value: chi_squared_p_value(statistic, degrees_of_freedom),
+        }
+    }
+
+    /// Returns true unless the counts are unlikely at the given significance level
+    pub fn passes(&self, significance: f64) -> bool {
+        self.p_value >= significance
+    }
+}
+
+impl fmt::Display for ChiSquared {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chi-squared {:.1} on {} degrees of freedom (p = {:.4})",
+            self.statistic, self.degrees_of_freedom, self.p_value
+        )
+    }
+}
+
+/// Upper-tail probability of the chi-squared distribution
+pub fn chi_squared_p_value(statistic: f64, degrees_of_freedom: u64) -> f64 {
+    if degrees_of_freedom == 0 {
+        return 1.0;
+    }
+    regularized_gamma_q(degrees_of_freedom as f64 / 2.0, statistic.max(0.0) / 2.0)
+}
+
+/// Natural log of the gamma function, by the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let mut series = 1.000_000_000_190_015;
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate() {
+        series += coefficient / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Q(a, x), the regularized upper incomplete gamma function
+///
+/// Uses the series for P(a, x) below `a + 1` and a continued fraction above it.
+fn regularized_gamma_q(a: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-14;
+    const MAX_TERMS: usize = 10_000;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let log_prefix = -x + a * x.ln() - ln_gamma(a);
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..MAX_TERMS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * log_prefix.exp()).clamp(0.0, 1.0)
+    } else {
+        // Modified Lentz evaluation of the continued fraction
+        let tiny = f64::MIN_POSITIVE / EPSILON;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for n in 1..MAX_TERMS {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (log_prefix.exp() * h).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Property: A proportion interval always contains its estimate
+    proptest! {
+        #[test]
+        fn prop_proportion_interval_contains_estimate(trials in 1u64..1_000_000, fraction in 0.0f64..=1.0) {
+            let successes = (trials as f64 * fraction) as u64;
+            let interval = proportion_interval(successes, trials);
+            prop_assert!(interval.contains(interval.estimate));
+            prop_assert!(interval.low <= interval.high);
+        }
+    }
+
+    // Property: Constant samples have a zero-width interval
+    proptest! {
+        #[test]
+        fn prop_constant_samples_zero_width(count in 2u64..10_000, value in -5i64..5) {
+            let v = value as f64;
+            let interval = mean_interval(count, v * count as f64, v * v * count as f64);
+            prop_assert!((interval.estimate - v).abs() < 1e-9);
+            prop_assert!((interval.high - interval.low).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_fair_coin_interval() {
+        let interval = proportion_interval(5_000, 10_000);
+        assert!((interval.estimate - 0.5).abs() < 1e-12);
+        assert!((interval.high - 0.5 - Z_95 * 0.005).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chi_squared_critical_values() {
+        // Textbook 5% critical values
+        for (statistic, degrees_of_freedom) in [(3.841, 1), (18.307, 10), (124.342, 100)] {
+            let p = chi_squared_p_value(statistic, degrees_of_freedom);
+            assert!((p - 0.05).abs() < 1e-3, "df {} gave p = {}", degrees_of_freedom, p);
+        }
+        assert!((chi_squared_p_value(2.0, 2) - (-1.0f64).exp()).abs() < 1e-9);
+        assert_eq!(chi_squared_p_value(0.0, 5), 1.0);
+    }
+
+    // Property: The p-value falls as the statistic grows
+    proptest! {
+        #[test]
+        fn prop_p_value_decreases(
+            statistic in 0.0f64..5_000.0,
+            step in 0.1f64..100.0,
+            df in 1u64..3_000,
+        ) {
+            let p = chi_squared_p_value(statistic, df);
+            let further = chi_squared_p_value(statistic + step, df);
+            prop_assert!((0.0..=1.0).contains(&p));
+            prop_assert!(further <= p + 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_perfect_fit_passes() {
+        let test = ChiSquared::test(&[10, 10, 10], &[10.0, 10.0, 10.0], 2);
+        assert_eq!(test.statistic, 0.0);
+        assert!(test.passes(0.01));
+        let skewed = ChiSquared::test(&[30, 0, 0], &[10.0, 10.0, 10.0], 2);
+        assert!(!skewed.passes(0.01));
+    }
+}
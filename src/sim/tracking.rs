@@ -0,0 +1,495 @@
+use crate::game::counting::{CountingSystem, HiLo};
+use crate::models::card::Card;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::models::shuffle::{ShuffleRoutine, ShuffleStep, OVERHAND_BREAK, STRIP_PACKETS};
+use std::fmt;
+
+/// Where each card of a known order is likely to be after a shuffle routine
+///
+/// Every step moves a card using fresh randomness that depends only on where the card
+/// is, so the chances are exact: one matrix per step, multiplied in order. Positions
+/// are counted in deal order, 0 being the next card.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionChances {
+    /// `chances[from][to]`
+    chances: Vec<Vec<f64>>,
+}
+
+impl PositionChances {
+    /// Works out the chances for a deck of `cards` cards, up to an eight-deck shoe
+    pub fn for_routine(cards: usize, routine: &ShuffleRoutine) -> Self {
+        let binomials = binomials(cards);
+        let mut chances = identity(cards);
+        let mut kernels: Vec<(ShuffleStep, Vec<Vec<f64>>)> = Vec::new();
+        for step in routine.steps() {
+            if !kernels.iter().any(|(done, _)| done == step) {
+                kernels.push((*step, kernel(*step, cards, &binomials)));
+            }
+            let (_, kernel) = kernels.iter().find(|(done, _)| done == step).unwrap();
+            chances = multiply(&chances, kernel);
+        }
+        // The kernels index the deck from the bottom, as `Deck` stores it
+        let last = cards.saturating_sub(1);
+        let chances = (0..cards)
+            .map(|from| (0..cards).map(|to| chances[last - from][last - to]).collect())
+            .collect();
+        PositionChances { chances }
+    }
+
+    pub fn cards(&self) -> usize {
+        self.chances.len()
+    }
+
+    /// Returns the chance that the card at `from` ends up at `to`
+    pub fn chance(&self, from: usize, to: usize) -> f64 {
+        self.chances[from][to]
+    }
+
+    /// Returns where the card at `from` lands on average
+    pub fn expected_position(&self, from: usize) -> f64 {
+        self.chances[from].iter().enumerate().map(|(to, chance)| to as f64 * chance).sum()
+    }
+}
+
+/// Pascal's triangle up to row `n`, as floats so a full shoe fits
+fn binomials(n: usize) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = vec![vec![1.0]];
+    for row in 1..=n {
+        let above = &rows[row - 1];
+        let next = (0..=row)
+            .map(|k| {
+                let left = if k > 0 { above[k - 1] } else { 0.0 };
+                left + above.get(k).copied().unwrap_or(0.0)
+            })
+            .collect();
+        rows.push(next);
+    }
+    rows
+}
+
+fn choose(binomials: &[Vec<f64>], n: usize, k: usize) -> f64 {
+    binomials[n].get(k).copied().unwrap_or(0.0)
+}
+
+fn identity(n: usize) -> Vec<Vec<f64>> {
+    (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect()
+}
+
+fn multiply(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = b.len();
+    a.iter()
+        .map(|row| {
+            let mut product = vec![0.0; n];
+            for (k, chance) in row.iter().enumerate().filter(|(_, chance)| **chance > 0.0) {
+                for (j, cell) in product.iter_mut().enumerate() {
+                    *cell += chance * b[k][j];
+                }
+            }
+            product
+        })
+        .collect()
+}
+
+/// The chances that one step moves a card from each index to each index of a deck
+/// stored bottom card first, matching `ShuffleStep::apply`
+fn kernel(step: ShuffleStep, n: usize, binomials: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    if n < 2 {
+        return identity(n);
+    }
+    let mut kernel = vec![vec![0.0; n]; n];
+    let cut_chance = |cut: usize| choose(binomials, n, cut) / 2f64.powi(n as i32);
+    match step {
+        ShuffleStep::Riffle => {
+            // Every interleaving of the two halves is equally likely. A card that is
+            // `rank` into its half lands at `to` when exactly `rank` of the `to` cards
+            // before it come from its own half.
+            for cut in 0..=n {
+                let interleavings = choose(binomials, n, cut);
+                for (from, row) in kernel.iter_mut().enumerate() {
+                    let (rank, own, other) = if from < cut {
+                        (from, cut, n - cut)
+                    } else {
+                        (from - cut, n - cut, cut)
+                    };
+                    for (to, cell) in row.iter_mut().enumerate().skip(rank).take(other + 1) {
+                        let before = choose(binomials, to, rank);
+                        let after = choose(binomials, n - 1 - to, own - 1 - rank);
+                        *cell += cut_chance(cut) * before * after / interleavings;
+                    }
+                }
+            }
+        }
+        ShuffleStep::Overhand => {
+            for (from, row) in kernel.iter_mut().enumerate() {
+                let below = run_lengths(from, OVERHAND_BREAK);
+                let above = run_lengths(n - 1 - from, OVERHAND_BREAK);
+                for (a, below) in below.iter().enumerate() {
+                    for (b, above) in above.iter().enumerate() {
+                        row[n - 1 - from - b + a] += below * above;
+                    }
+                }
+            }
+        }
+        ShuffleStep::Strip => {
+            let (fewest, most) = STRIP_PACKETS;
+            let gaps = n - 1;
+            let weight = 1.0 / (most - fewest + 1) as f64;
+            for packets in fewest..=most {
+                let breaks = packets.min(n) - 1;
+                let ways = choose(binomials, gaps, breaks);
+                for (from, row) in kernel.iter_mut().enumerate() {
+                    // `a` cards of the card's packet lie below it and `b` above
+                    for a in 0..=from {
+                        for b in 0..=n - 1 - from {
+                            let fixed = usize::from(a < from) + usize::from(b < n - 1 - from);
+                            if fixed > breaks {
+                                continue;
+                            }
+                            let free = gaps - a - b - fixed;
+                            let chance = choose(binomials, free, breaks - fixed) / ways;
+                            row[n - 1 - from - b + a] += weight * chance;
+                        }
+                    }
+                }
+            }
+        }
+        ShuffleStep::Cut => {
+            for cut in 0..=n {
+                for (from, row) in kernel.iter_mut().enumerate() {
+                    row[(from + n - cut % n) % n] += cut_chance(cut);
+                }
+            }
+        }
+    }
+    kernel
+}
+
+/// Chances that a packet holds 0 to `room` more cards on one side of a card, when each
+/// gap breaks with chance `p`
+fn run_lengths(room: usize, p: f64) -> Vec<f64> {
+    (0..=room)
+        .map(|k| {
+            if k < room {
+                (1.0 - p).powi(k as i32) * p
+            } else {
+                (1.0 - p).powi(room as i32)
+            }
+        })
+        .collect()
+}
+
+/// Returns true for the cards a tracker follows: tens and Aces, the Hi-Lo minus cards
+pub fn is_high(card: &Card) -> bool {
+    HiLo.tag(card.rank()) < 0
+}
+
+/// A stretch of the gathered discards rich in high cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clump {
+    /// Position of its first card, 0 being the top of the gathered pile
+    pub start: usize,
+    pub len: usize,
+    pub high_cards: usize,
+}
+
+/// Finds every stretch of `window` cards holding at least `min_high` high cards,
+/// merging stretches that overlap
+pub fn find_clumps(discards: &[Card], window: usize, min_high: usize) -> Vec<Clump> {
+    let mut clumps: Vec<Clump> = Vec::new();
+    if window == 0 || window > discards.len() {
+        return clumps;
+    }
+    for start in 0..=discards.len() - window {
+        let high = discards[start..start + window].iter().filter(|card| is_high(card)).count();
+        if high < min_high {
+            continue;
+        }
+        match clumps.last_mut() {
+            Some(last) if start <= last.start + last.len => last.len = start + window - last.start,
+            _ => clumps.push(Clump { start, len: window, high_cards: 0 }),
+        }
+    }
+    for clump in &mut clumps {
+        let cards = &discards[clump.start..clump.start + clump.len];
+        clump.high_cards = cards.iter().filter(|card| is_high(card)).count();
+    }
+    clumps
+}
+
+/// How many high cards are expected in each equal segment of the next shoe, dealt first
+/// to last, overall and from each clump
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackingForecast {
+    pub high_cards: Vec<f64>,
+    /// One row per clump, in the order given
+    pub clumps: Vec<Vec<f64>>,
+}
+
+/// Returns the segment of the shoe, 0 to `segments - 1`, that a deal position falls in
+fn segment(position: usize, cards: usize, segments: usize) -> usize {
+    position * segments / cards
+}
+
+/// Predicts where the high cards of the gathered `discards` (top card first) end up after
+/// `routine`, split into `segments` parts of the next shoe
+pub fn forecast(
+    discards: &[Card],
+    routine: &ShuffleRoutine,
+    segments: usize,
+    clumps: &[Clump],
+) -> TrackingForecast {
+    let chances = PositionChances::for_routine(discards.len(), routine);
+    let expected = |from: usize| {
+        let mut landing = vec![0.0; segments];
+        for to in 0..discards.len() {
+            landing[segment(to, discards.len(), segments)] += chances.chance(from, to);
+        }
+        landing
+    };
+    tally(discards, segments, clumps, expected)
+}
+
+/// Shuffles the gathered `discards` with `routine` `trials` times and averages where
+/// their high cards land, to check a [`forecast`]
+pub fn simulate(
+    discards: &[Card],
+    routine: &ShuffleRoutine,
+    segments: usize,
+    clumps: &[Clump],
+    trials: u64,
+    seed: u64,
+) -> TrackingForecast {
+    let n = discards.len();
+    let mut landings = vec![vec![0u64; segments]; n];
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..trials {
+        // Shuffle each card's starting place rather than the cards, since a shoe holds
+        // identical cards that a shuffle is free to reorder. Like a `Deck`, the order is
+        // stored bottom card first.
+        let mut order: Vec<usize> = (0..n).rev().collect();
+        for step in routine.steps() {
+            step.apply(&mut order, &mut rng);
+        }
+        for (to, from) in order.into_iter().rev().enumerate() {
+            landings[from][segment(to, n, segments)] += 1;
+        }
+    }
+    let average = |from: usize| {
+        landings[from].iter().map(|count| *count as f64 / trials as f64).collect::<Vec<f64>>()
+    };
+    tally(discards, segments, clumps, average)
+}
+
+/// Sums the landing chances of the high cards, overall and per clump
+fn tally(
+    discards: &[Card],
+    segments: usize,
+    clumps: &[Clump],
+    landing: impl Fn(usize) -> Vec<f64>,
+) -> TrackingForecast {
+    let mut high_cards = vec![0.0; segments];
+    let mut by_clump = vec![vec![0.0; segments]; clumps.len()];
+    for (from, _) in discards.iter().enumerate().filter(|(_, card)| is_high(card)) {
+        let landing = landing(from);
+        for (segment, chance) in landing.iter().enumerate() {
+            high_cards[segment] += chance;
+        }
+        for (clump, row) in clumps.iter().zip(&mut by_clump) {
+            if (clump.start..clump.start + clump.len).contains(&from) {
+                for (segment, chance) in landing.iter().enumerate() {
+                    row[segment] += chance;
+                }
+            }
+        }
+    }
+    TrackingForecast {
+        high_cards,
+        clumps: by_clump,
+    }
+}
+
+/// A forecast set beside a simulation of the same shuffle
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackingReport {
+    pub routine: ShuffleRoutine,
+    pub clumps: Vec<Clump>,
+    pub predicted: TrackingForecast,
+    pub simulated: TrackingForecast,
+    pub trials: u64,
+    /// High cards per segment if the shuffle were perfect
+    pub uniform: f64,
+}
+
+impl TrackingReport {
+    /// Forecasts and simulates the same discards, clumps and routine
+    pub fn run(
+        discards: &[Card],
+        routine: &ShuffleRoutine,
+        segments: usize,
+        clumps: &[Clump],
+        trials: u64,
+        seed: u64,
+    ) -> Self {
+        let high = discards.iter().filter(|card| is_high(card)).count();
+        TrackingReport {
+            routine: routine.clone(),
+            clumps: clumps.to_vec(),
+            predicted: forecast(discards, routine, segments, clumps),
+            simulated: simulate(discards, routine, segments, clumps, trials, seed),
+            trials,
+            uniform: high as f64 / segments as f64,
+        }
+    }
+
+    /// Returns the largest gap between forecast and simulation in any segment
+    pub fn largest_difference(&self) -> f64 {
+        let rows = std::iter::once((&self.predicted.high_cards, &self.simulated.high_cards))
+            .chain(self.predicted.clumps.iter().zip(&self.simulated.clumps));
+        rows.flat_map(|(predicted, simulated)| predicted.iter().zip(simulated))
+            .map(|(predicted, simulated)| (predicted - simulated).abs())
+            .fold(0.0, f64::max)
+    }
+}
+
+impl fmt::Display for TrackingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segments = self.predicted.high_cards.len();
+        writeln!(f, "Shuffle tracking report")?;
+        writeln!(f, "Shuffle:  {}", self.routine)?;
+        writeln!(
+            f,
+            "Segments: {} (high cards per segment if perfectly shuffled: {:.2})",
+            segments, self.uniform
+        )?;
+        let header: Vec<String> = (1..=segments).map(|segment| format!("{:>6}", segment)).collect();
+        writeln!(f)?;
+        writeln!(f, "{:<28}{}", "Segment of the next shoe", header.join(""))?;
+        let row = |f: &mut fmt::Formatter<'_>, label: &str, values: &[f64]| {
+            let cells: Vec<String> = values.iter().map(|value| format!("{:>6.2}", value)).collect();
+            writeln!(f, "{:<28}{}", label, cells.join(""))
+        };
+        row(f, "All high cards (forecast)", &self.predicted.high_cards)?;
+        row(f, "All high cards (simulated)", &self.simulated.high_cards)?;
+        for (i, clump) in self.clumps.iter().enumerate() {
+            writeln!(
+                f,
+                "Clump at {}-{}: {} high cards in {}",
+                clump.start + 1,
+                clump.start + clump.len,
+                clump.high_cards,
+                clump.len
+            )?;
+            row(f, "  forecast", &self.predicted.clumps[i])?;
+            row(f, "  simulated", &self.simulated.clumps[i])?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Largest difference from {} simulated shuffles: {:.3} cards",
+            self.trials,
+            self.largest_difference()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::deck::Deck;
+    use proptest::prelude::*;
+
+    fn any_routine() -> impl Strategy<Value = ShuffleRoutine> {
+        let step = prop_oneof![
+            Just(ShuffleStep::Riffle),
+            Just(ShuffleStep::Overhand),
+            Just(ShuffleStep::Strip),
+            Just(ShuffleStep::Cut),
+        ];
+        prop::collection::vec(step, 0..4).prop_map(ShuffleRoutine::new)
+    }
+
+    // Property: Every card lands somewhere and every position is filled by someone
+    proptest! {
+        #[test]
+        fn prop_position_chances_are_doubly_stochastic(
+            routine in any_routine(),
+            cards in 1usize..=20,
+        ) {
+            let chances = PositionChances::for_routine(cards, &routine);
+            for i in 0..cards {
+                let from: f64 = (0..cards).map(|to| chances.chance(i, to)).sum();
+                let to: f64 = (0..cards).map(|from| chances.chance(from, i)).sum();
+                prop_assert!((from - 1.0).abs() < 1e-9, "row {} sums to {}", i, from);
+                prop_assert!((to - 1.0).abs() < 1e-9, "column {} sums to {}", i, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_shuffle_leaves_every_card_in_place() {
+        let chances = PositionChances::for_routine(52, &ShuffleRoutine::new(Vec::new()));
+        assert_eq!(chances.chance(10, 10), 1.0);
+        assert_eq!(chances.expected_position(10), 10.0);
+    }
+
+    #[test]
+    fn test_strips_send_the_top_towards_the_bottom() {
+        let strip = ShuffleRoutine::repeat(ShuffleStep::Strip, 1);
+        let chances = PositionChances::for_routine(52, &strip);
+        assert!(chances.expected_position(0) > 35.0);
+        assert!(chances.expected_position(51) < 16.0);
+    }
+
+    #[test]
+    fn test_clumps_are_found_and_merged() {
+        let cards: Vec<Card> = ["Th", "Ks", "Ad", "2c", "3c", "4c", "5c", "Qh", "Jd", "As"]
+            .iter()
+            .map(|card| card.parse().unwrap())
+            .collect();
+        let clumps = find_clumps(&cards, 3, 2);
+        assert_eq!(
+            clumps,
+            vec![
+                Clump { start: 0, len: 4, high_cards: 3 },
+                Clump { start: 6, len: 4, high_cards: 3 },
+            ]
+        );
+        assert!(find_clumps(&cards, 11, 1).is_empty());
+    }
+
+    #[test]
+    fn test_forecast_matches_simulation() {
+        let discards = Deck::new_seeded(46).cards_in_deal_order();
+        let clumps = find_clumps(&discards, 8, 5);
+        assert!(!clumps.is_empty());
+        let routine: ShuffleRoutine = "riffle, strip, riffle, cut".parse().unwrap();
+        let report = TrackingReport::run(&discards, &routine, 4, &clumps, 3_000, 7);
+        assert!(report.largest_difference() < 0.25, "{}", report);
+        let total: f64 = report.predicted.high_cards.iter().sum();
+        assert!((total - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_forecast_matches_simulation_for_a_two_deck_shoe() {
+        // Each card appears twice, and riffles are free to swap the twins
+        let discards = Deck::new_shoe_seeded(2, 46).cards_in_deal_order();
+        let clumps = find_clumps(&discards, 8, 5);
+        assert!(!clumps.is_empty());
+        let routine: ShuffleRoutine = "riffle, riffle".parse().unwrap();
+        let report = TrackingReport::run(&discards, &routine, 4, &clumps, 3_000, 7);
+        assert!(report.largest_difference() < 0.25, "{}", report);
+        let total: f64 = report.simulated.high_cards.iter().sum();
+        assert!((total - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_one_riffle_keeps_a_top_clump_near_the_top() {
+        let discards = Deck::new_seeded(3).cards_in_deal_order();
+        let clump = Clump { start: 0, len: 8, high_cards: 0 };
+        let routine = ShuffleRoutine::repeat(ShuffleStep::Riffle, 1);
+        let forecast = forecast(&discards, &routine, 4, &[clump]);
+        let in_clump = discards[..8].iter().filter(|card| is_high(card)).count() as f64;
+        assert!(forecast.clumps[0][0] > 0.7 * in_clump, "{:?}", forecast.clumps[0]);
+    }
+}
@@ -1,4 +1,14 @@
+use crate::game::advisor::Advice;
+use crate::game::freezeout::Standing;
+use crate::game::rules::{HandValue, Outcome, Settlement};
+use crate::game::stats::SessionStats;
+use crate::game::strategy::Action;
+use crate::game::view::{HandView, SeenCard};
+use crate::models::card::{Card, Rank};
+use crate::models::composition::Composition;
 use crate::models::hand::Hand;
+use std::io::Write;
+use std::time::Duration;
 
 /// Handles all game output and formatting
 pub struct Display;
@@ -24,21 +34,193 @@ impl Display {
         for card in hand.cards() {
             println!("│  {}", card);
         }
+        println!("│  Total: {}", HandValue::of(hand).total);
         println!("└─────────────────────────────────────┘");
     }
 
-    /// Shows the banker's hand with one card hidden
-    pub fn show_banker_hand_hidden(&self, hand: &Hand) {
+    /// Shows the player's hands, numbered when a split has made more than one
+    pub fn show_player_hands(&self, hands: &[Hand]) {
+        if let [hand] = hands {
+            return self.show_player_hand(hand);
+        }
+        for (number, hand) in hands.iter().enumerate() {
+            println!("\n┌─ Your Hand {} ───────────────────────┐", number + 1);
+            for card in hand.cards() {
+                println!("│  {}", card);
+            }
+            println!("│  Total: {}", HandValue::of(hand).total);
+            println!("└─────────────────────────────────────┘");
+        }
+    }
+
+    /// Shows each of the player's hands after a split, with how each settled
+    pub fn show_split_hands(&self, hands: &[&Hand], settlements: &[Settlement]) {
+        for (number, (hand, settlement)) in hands.iter().zip(settlements).enumerate() {
+            println!("\n┌─ Your Hand {} ───────────────────────┐", number + 1);
+            for card in hand.cards() {
+                println!("│  {}", card);
+            }
+            println!("│  {}: {:+}", settlement.player_rank, settlement.net);
+            println!("└─────────────────────────────────────┘");
+        }
+    }
+
+    /// Shows the banker's hand as the viewer sees it, face-down cards hidden
+    pub fn show_banker_view(&self, hand: &HandView) {
         println!("\n┌─ Banker's Hand ─────────────────────┐");
-        if let Some(first_card) = hand.cards().first() {
-            println!("│  {}", first_card);
+        self.show_hand_view_cards(hand);
+        println!("└─────────────────────────────────────┘");
+    }
+
+    /// Shows another player's hand at a shared table
+    pub fn show_other_hand(&self, name: &str, hand: &HandView) {
+        println!("\n┌─ {}'s Hand", name);
+        self.show_hand_view_cards(hand);
+        println!("└─────────────────────────────────────┘");
+    }
+
+    fn show_hand_view_cards(&self, hand: &HandView) {
+        for seen in hand.cards() {
+            match seen {
+                SeenCard::FaceUp(card) => println!("│  {}", card),
+                SeenCard::FaceDown => println!("│  [Hidden Card]"),
+            }
+        }
+        if let Some(total) = hand.total() {
+            println!("│  Total: {}", total);
         }
-        if hand.card_count() > 1 {
-            println!("│  [Hidden Card]");
+    }
+
+    /// Shows the banker's whole hand once it is revealed
+    pub fn show_banker_hand(&self, hand: &Hand) {
+        println!("\n┌─ Banker's Hand ─────────────────────┐");
+        for card in hand.cards() {
+            println!("│  {}", card);
         }
+        println!("│  Total: {}", HandValue::of(hand).total);
+        println!("└─────────────────────────────────────┘");
+    }
+
+    /// Shows the advisor's recommendation
+    pub fn show_advice(&self, advice: &Advice) {
+        println!(
+            "\n💡 Advisor: {} ({:.0}% chance the next card busts you)",
+            advice.action,
+            advice.bust_probability * 100.0
+        );
+    }
+
+    /// Shows how many of each rank the player has not seen yet, the chance of each
+    /// coming next, and the chance that twisting busts `hand`
+    pub fn show_composition(&self, composition: &Composition, hand: &Hand) {
+        let ranks = composition.rank_probabilities();
+        let row = |cell: &dyn Fn(Rank, f64) -> String| {
+            let cells: Vec<String> =
+                ranks.iter().map(|(rank, chance)| format!("{:<5}", cell(*rank, *chance))).collect();
+            cells.join("").trim_end().to_string()
+        };
+        println!("\n┌─ Cards to Come ─────────────────────┐");
+        println!("│  {} cards unseen", composition.total());
+        println!("│  Rank  {}", row(&|rank, _| rank.symbol().to_string()));
+        println!("│  Left  {}", row(&|rank, _| composition.count(rank).to_string()));
+        println!("│  Odds  {}", row(&|_, chance| format!("{:.0}%", chance * 100.0)));
+        println!(
+            "│  Twisting busts you {:.0}% of the time",
+            composition.bust_probability(hand) * 100.0
+        );
         println!("└─────────────────────────────────────┘");
     }
 
+    /// Warns the player that time to decide is running out
+    pub fn show_turn_warning(&self, left: Duration) {
+        println!("\n⏳ {}s left to decide!", left.as_secs_f64().ceil());
+    }
+
+    /// Tells the player the move made for them when their time ran out
+    pub fn show_timed_out(&self, action: Action) {
+        println!("\n⌛ Out of time: {} was played for you.", action);
+    }
+
+    /// Shows how the round was settled
+    pub fn show_settlement(&self, settlement: &Settlement) {
+        println!(
+            "\nYour hand: {}   Banker's hand: {}",
+            settlement.player_rank, settlement.banker_rank
+        );
+        match settlement.outcome {
+            Outcome::Win => println!("🎉 You win {}!", settlement.net),
+            Outcome::Lose => println!("Banker wins. You lose {}.", -settlement.net),
+            Outcome::Push => println!("Push - your stake is returned."),
+        }
+    }
+
+    /// Shows the totals for a playing session
+    pub fn show_session_stats(&self, stats: &SessionStats) {
+        if stats.rounds == 0 && stats.practice_hands == 0 {
+            return;
+        }
+        println!("\n┌─ Session ───────────────────────────┐");
+        if stats.practice_hands > 0 {
+            println!(
+                "│  Practice hands: {}   Undos: {}   Redos: {} (not scored)",
+                stats.practice_hands, stats.undos, stats.redos
+            );
+        }
+        println!(
+            "│  Rounds: {}   Won: {}   Lost: {}   Pushed: {}",
+            stats.rounds, stats.wins, stats.losses, stats.pushes
+        );
+        println!(
+            "│  Pontoons: {}   Five Card Tricks: {}",
+            stats.pontoons, stats.five_card_tricks
+        );
+        println!(
+            "│  Busts: you {}, banker {}",
+            stats.player_busts, stats.banker_busts
+        );
+        println!("│  Net: {:+}", stats.net);
+        println!("└─────────────────────────────────────┘");
+    }
+
+    /// Shows a tournament's standings after `round` rounds
+    pub fn show_standings(&self, round: u32, standings: &[Standing]) {
+        println!("\n┌─ Standings ─────────────────────────┐");
+        println!("│  After round {}", round);
+        for standing in standings {
+            let status = match standing.eliminated {
+                Some(out) => format!("out in round {}", out),
+                None => format!("{} chips", standing.chips),
+            };
+            println!("│  {:>2}. {:<16} {}", standing.place, standing.name, status);
+        }
+        println!("└─────────────────────────────────────┘");
+    }
+
+    /// Flashes a card on one line, leaving it up for `delay` before it is wiped
+    pub fn show_flash_card(&self, card: &Card, delay: Duration) {
+        print!("\r  ▶ {:<24}", card.to_string());
+        let _ = std::io::stdout().flush();
+        std::thread::sleep(delay);
+        print!("\r{:<30}\r", "");
+        let _ = std::io::stdout().flush();
+    }
+
+    /// Shows the final score of a counting drill
+    pub fn show_drill_score(&self, correct: usize, asked: usize, average_answer: Duration) {
+        let accuracy = if asked == 0 { 0.0 } else { correct as f64 / asked as f64 * 100.0 };
+        println!("\n┌─ Drill Results ─────────────────────┐");
+        println!("│  Correct:  {} of {}", correct, asked);
+        println!("│  Accuracy: {:.0}%", accuracy);
+        println!("│  Average answer time: {:.1}s", average_answer.as_secs_f64());
+        println!("└─────────────────────────────────────┘");
+    }
+
+    /// Shows a prompt without a trailing newline
+    pub fn show_prompt(&self, prompt: &str) {
+        print!("\n{} ", prompt);
+        let _ = std::io::stdout().flush();
+    }
+
     /// Shows a message
     pub fn show_message(&self, message: &str) {
         println!("\n{}", message);
@@ -0,0 +1,182 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// A command typed by the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Twist,
+    Stick,
+    Double,
+    Split,
+    Surrender,
+    ToggleHints,
+    ToggleOdds,
+    Undo,
+    Redo,
+    Yes,
+    No,
+    Quit,
+    Unknown,
+}
+
+impl Command {
+    /// Parses one line of input (case-insensitive, surrounding whitespace ignored)
+    pub fn parse(line: &str) -> Self {
+        match line.trim().to_lowercase().as_str() {
+            "t" | "twist" => Command::Twist,
+            "s" | "stick" => Command::Stick,
+            "d" | "double" => Command::Double,
+            "p" | "split" => Command::Split,
+            "su" | "surrender" => Command::Surrender,
+            "h" | "hint" | "hints" => Command::ToggleHints,
+            "o" | "odds" => Command::ToggleOdds,
+            "u" | "undo" => Command::Undo,
+            "r" | "redo" => Command::Redo,
+            "y" | "yes" => Command::Yes,
+            "n" | "no" => Command::No,
+            "q" | "quit" | "exit" => Command::Quit,
+            _ => Command::Unknown,
+        }
+    }
+}
+
+/// Reads player commands line by line
+pub struct Input<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> Input<R> {
+    /// Creates an input handler reading from any buffered source
+    pub fn new(reader: R) -> Self {
+        Input { reader }
+    }
+
+    /// Reads the next command; end of input is treated as Quit
+    pub fn read_command(&mut self) -> Command {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) | Err(_) => Command::Quit,
+            Ok(_) => Command::parse(&line),
+        }
+    }
+
+    /// Reads the next line with surrounding whitespace trimmed; None at end of input
+    pub fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    }
+}
+
+impl Input<io::StdinLock<'static>> {
+    /// Creates an input handler reading from standard input
+    pub fn stdin() -> Self {
+        Self::new(io::stdin().lock())
+    }
+}
+
+/// Reads player commands on a background thread, so that waiting for one can be cut short
+pub struct TimedInput {
+    lines: Receiver<String>,
+}
+
+impl TimedInput {
+    /// Reads the lines sent down a channel; the input ends once every sender is gone
+    pub fn new(lines: Receiver<String>) -> Self {
+        TimedInput { lines }
+    }
+
+    /// Starts a thread reading from any buffered source
+    pub fn spawn<R: BufRead + Send + 'static>(reader: R) -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || send_lines(reader, sender));
+        Self::new(lines)
+    }
+
+    /// Starts a thread reading standard input
+    pub fn stdin() -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || send_lines(io::stdin().lock(), sender));
+        Self::new(lines)
+    }
+
+    /// Reads the next command; end of input is treated as Quit
+    pub fn read_command(&self) -> Command {
+        self.lines.recv().map_or(Command::Quit, |line| Command::parse(&line))
+    }
+
+    /// Waits up to `timeout` for the next command; None if none comes in time
+    pub fn read_command_within(&self, timeout: Duration) -> Option<Command> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Some(Command::parse(&line)),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Command::Quit),
+        }
+    }
+}
+
+/// Passes lines on until the input ends or nobody is listening
+fn send_lines<R: BufRead>(reader: R, sender: mpsc::Sender<String>) {
+    for line in reader.lines() {
+        let Ok(line) = line else { return };
+        if sender.send(line).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::io::Cursor;
+
+    // Property: Parsing ignores case and surrounding whitespace
+    proptest! {
+        #[test]
+        fn prop_parse_ignores_case_and_whitespace(
+            word in prop_oneof![
+                Just("twist"), Just("STICK"), Just("h"), Just("Odds"), Just("Quit"), Just("r"),
+                Just("Split"), Just("su")
+            ],
+            before in "[ \t]{0,3}",
+            after in "[ \t\r]{0,3}"
+        ) {
+            let padded = format!("{}{}{}", before, word, after);
+            prop_assert_eq!(Command::parse(&padded), Command::parse(&word.to_lowercase()));
+            prop_assert_ne!(Command::parse(&padded), Command::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_read_commands_then_quit_at_end() {
+        let mut input = Input::new(Cursor::new("t\nstick\nwhat\n"));
+        assert_eq!(input.read_command(), Command::Twist);
+        assert_eq!(input.read_command(), Command::Stick);
+        assert_eq!(input.read_command(), Command::Unknown);
+        assert_eq!(input.read_command(), Command::Quit);
+    }
+
+    #[test]
+    fn test_timed_input_waits_then_quits_at_end() {
+        let (sender, lines) = mpsc::channel();
+        let input = TimedInput::new(lines);
+        assert_eq!(input.read_command_within(Duration::from_millis(10)), None);
+        sender.send("s".to_string()).unwrap();
+        assert_eq!(input.read_command_within(Duration::from_millis(10)), Some(Command::Stick));
+        drop(sender);
+        assert_eq!(input.read_command_within(Duration::from_millis(10)), Some(Command::Quit));
+        assert_eq!(TimedInput::spawn(Cursor::new("t\n")).read_command(), Command::Twist);
+    }
+
+    #[test]
+    fn test_read_line_trims_and_ends() {
+        let mut input = Input::new(Cursor::new("  -3 \n"));
+        assert_eq!(input.read_line(), Some("-3".to_string()));
+        assert_eq!(input.read_line(), None);
+    }
+}
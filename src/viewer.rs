@@ -0,0 +1,221 @@
+use crate::game::round::Seat;
+use crate::game::view::HandView;
+use crate::history::replay::{RecordedGame, TableState};
+use crate::history::Entry;
+use crate::ui::display::Display;
+use crate::ui::input::Input;
+use std::io::BufRead;
+
+/// Steps through recorded rounds one entry at a time, forwards or backwards
+pub struct ReplayViewer<R: BufRead> {
+    display: Display,
+    input: Input<R>,
+    games: Vec<RecordedGame>,
+    /// Every (game, round, step) position, in playing order
+    positions: Vec<(usize, usize, usize)>,
+    position: usize,
+}
+
+impl<R: BufRead> ReplayViewer<R> {
+    /// Creates a viewer positioned at the first entry of the first round
+    pub fn new(display: Display, input: Input<R>, games: Vec<RecordedGame>) -> Self {
+        let mut positions = Vec::new();
+        for (g, game) in games.iter().enumerate() {
+            for (r, round) in game.rounds.iter().enumerate() {
+                for step in 1..=round.entries.len() {
+                    positions.push((g, r, step));
+                }
+            }
+        }
+        ReplayViewer {
+            display,
+            input,
+            games,
+            positions,
+            position: 0,
+        }
+    }
+
+    /// Returns the index of the entry currently shown
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Shows entries until the player quits; Enter or f moves forward, b moves back
+    pub fn run(&mut self) {
+        if self.positions.is_empty() {
+            self.display.show_message("The hand history has no complete rounds.");
+            return;
+        }
+        loop {
+            self.show_current();
+            self.display
+                .show_prompt("[Enter/f] forward  [b] back  [q] quit:");
+            match self.input.read_line().as_deref() {
+                None | Some("q") => break,
+                Some("b") => self.position = self.position.saturating_sub(1),
+                Some("") | Some("f") => {
+                    if self.position + 1 < self.positions.len() {
+                        self.position += 1;
+                    } else {
+                        self.display.show_message("End of the hand history.");
+                    }
+                }
+                Some(_) => self.display.show_message("Please press Enter, f, b or q."),
+            }
+        }
+    }
+
+    fn show_current(&self) {
+        let (g, r, step) = self.positions[self.position];
+        let round = &self.games[g].rounds[r];
+        let state = TableState::after(round, step);
+
+        self.display.show_separator();
+        self.display.show_message(&format!(
+            "Game {} · Round {} · Step {} of {}",
+            g + 1,
+            round.number,
+            step,
+            round.entries.len()
+        ));
+        self.display.show_message(&describe(&round.entries[step - 1]));
+
+        if !state.player.is_empty() {
+            self.display.show_player_hands(&state.player);
+        }
+        if state.banker_hidden {
+            self.display.show_banker_view(&HandView::with_hole_card(&state.banker));
+        } else if state.banker.card_count() > 0 {
+            self.display.show_banker_hand(&state.banker);
+        }
+        if let Some(settlement) = state.settlement {
+            self.display.show_settlement(&settlement);
+        }
+    }
+}
+
+fn seat_label(seat: Seat) -> &'static str {
+    match seat {
+        Seat::Player => "Player",
+        Seat::Banker => "Banker",
+    }
+}
+
+/// Describes one hand history entry in plain words
+pub fn describe(entry: &Entry) -> String {
+    match entry {
+        Entry::Game {
+            seed,
+            decks,
+            rules,
+            deck_type,
+        } => format!(
+            "New game (seed {}, {} {} deck(s), {} rules)",
+            seed, decks, deck_type, rules
+        ),
+        Entry::Round { number, stake, deck } => format!(
+            "Round {} begins with a stake of {} and {} cards in the deck",
+            number,
+            stake,
+            deck.len()
+        ),
+        Entry::Deal { seat, card, face_up: true } => {
+            format!("{} is dealt the {}", seat_label(*seat), card)
+        }
+        Entry::Deal { seat, face_up: false, .. } => {
+            format!("{} is dealt a card face down", seat_label(*seat))
+        }
+        Entry::Action { seat, action } => format!("{} chooses to {}", seat_label(*seat), action),
+        Entry::Reveal { seat, card } => format!("{} turns over the {}", seat_label(*seat), card),
+        Entry::Settle(_) => "The round is settled".to_string(),
+        Entry::End => "End of round".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::replay::RecordedRound;
+    use crate::models::hand::Hand;
+    use std::io::Cursor;
+
+    fn sample_games() -> Vec<RecordedGame> {
+        let text = "game seed=1 decks=1 rules=standard\n\
+                    round 1 stake=10 deck=Ah,Kd,Qs,5c\n\
+                    deal player Ah up\n\
+                    deal banker Kd up\n\
+                    deal player Qs up\n\
+                    deal banker 5c down\n\
+                    reveal banker 5c\n\
+                    settle win player=pontoon banker=15 net=20\n\
+                    end\n";
+        crate::history::replay::games(&crate::history::parse(text).unwrap()).unwrap()
+    }
+
+    fn viewer(script: &str) -> ReplayViewer<Cursor<String>> {
+        ReplayViewer::new(Display::new(), Input::new(Cursor::new(script.to_string())), sample_games())
+    }
+
+    #[test]
+    fn test_forward_and_back() {
+        let mut viewer = viewer("f\nf\n\nb\nq\n");
+        viewer.run();
+        assert_eq!(viewer.position(), 2);
+    }
+
+    #[test]
+    fn test_cannot_move_past_either_end() {
+        let mut start = viewer("b\nb\n");
+        start.run();
+        assert_eq!(start.position(), 0);
+
+        let mut end = viewer(&"f\n".repeat(20));
+        end.run();
+        let RecordedRound { entries, .. } = &sample_games()[0].rounds[0];
+        assert_eq!(end.position(), entries.len() - 1);
+    }
+
+    #[test]
+    fn test_split_hands_are_shown_apart() {
+        let text = "game seed=1 decks=1 rules=blackjack\n\
+                    round 1 stake=10 deck=8h,9c,8d,7s,3c,Td,Kc,2h\n\
+                    deal player 8h up\n\
+                    deal banker 9c up\n\
+                    deal player 8d up\n\
+                    deal banker 7s down\n\
+                    action player split\n\
+                    deal player 3c up\n\
+                    action player double\n\
+                    deal player Td up\n\
+                    deal player Kc up\n\
+                    action player stick\n\
+                    reveal banker 7s\n\
+                    deal banker 2h up\n\
+                    settle win player=21 banker=18 net=20\n\
+                    end\n";
+        let games = crate::history::replay::games(&crate::history::parse(text).unwrap()).unwrap();
+        let round = &games[0].rounds[0];
+        let hands = |step| -> Vec<String> {
+            let state = TableState::after(round, step);
+            let notation = |hand: &Hand| -> Vec<String> {
+                hand.cards().iter().map(|card| card.notation()).collect()
+            };
+            state.player.iter().map(|hand| notation(hand).join(",")).collect()
+        };
+        assert_eq!(hands(5), ["8h,8d"]);
+        assert_eq!(hands(8), ["8h,3c", "8d"]);
+        assert_eq!(hands(11), ["8h,3c,Td", "8d,Kc"]);
+
+        let input = Input::new(Cursor::new("f\n".repeat(20)));
+        let mut viewer = ReplayViewer::new(Display::new(), input, games.clone());
+        viewer.run();
+        assert_eq!(viewer.position(), round.entries.len() - 1);
+    }
+
+    #[test]
+    fn test_face_down_deal_hides_the_card() {
+        let hidden = describe(&"deal banker 5c down".parse().unwrap());
+        assert!(!hidden.contains('5'));
+    }
+}
@@ -0,0 +1,144 @@
+//! End-to-end tests of the `pontoon` binary against golden files
+//!
+//! Each `tests/cli/<name>.script` is run as one scenario. Its `args:` line gives the
+//! command-line arguments, lines starting with `#` are comments, and every other line
+//! is typed at the game's prompts. Everything the game prints, and its exit code, must
+//! match `tests/cli/<name>.golden` exactly.
+//!
+//! When output changes on purpose, rewrite the golden files and review the diff:
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --test cli
+//! ```
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// One scripted run of the binary
+struct Script {
+    args: Vec<String>,
+    input: String,
+}
+
+impl Script {
+    fn parse(text: &str) -> Script {
+        let mut args = Vec::new();
+        let mut input = String::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("args:") {
+                args.extend(rest.split_whitespace().map(str::to_string));
+            } else if !line.starts_with('#') {
+                input.push_str(line);
+                input.push('\n');
+            }
+        }
+        Script { args, input }
+    }
+
+    /// Runs the binary with a private data directory, returning the transcript to compare
+    fn run(&self, name: &str) -> String {
+        let data_dir = env::temp_dir().join(format!("pontoon-cli-{}-{}", std::process::id(), name));
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pontoon"))
+            .args(&self.args)
+            .env("PONTOON_DATA_DIR", &data_dir)
+            .env_remove("PONTOON_LOG")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("the pontoon binary should start");
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // The game may stop reading early, so a broken pipe here is not a failure
+        let _ = stdin.write_all(self.input.as_bytes());
+        drop(stdin);
+        let output = child.wait_with_output().expect("the pontoon binary should finish");
+        let _ = fs::remove_dir_all(&data_dir);
+
+        let mut transcript = format!("$ pontoon {}\n", self.args.join(" "));
+        for line in self.input.lines() {
+            transcript.push_str(&format!("> {}\n", line));
+        }
+        transcript.push_str("--- stdout ---\n");
+        transcript.push_str(&String::from_utf8_lossy(&output.stdout));
+        if !output.stderr.is_empty() {
+            transcript.push_str("\n--- stderr ---\n");
+            transcript.push_str(&String::from_utf8_lossy(&output.stderr));
+        }
+        transcript.push_str(&format!("\n--- exit {} ---\n", output.status.code().unwrap_or(-1)));
+        transcript
+    }
+}
+
+fn scripts() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cli");
+    let mut scripts: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("tests/cli should exist")
+        .map(|entry| entry.expect("tests/cli should be readable").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "script"))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+/// Describes the first line where two transcripts differ
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for number in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (expected, actual) if expected != actual => {
+                return format!(
+                    "line {}:\n  expected: {}\n  actual:   {}",
+                    number,
+                    expected.unwrap_or("<end of file>"),
+                    actual.unwrap_or("<end of file>")
+                );
+            }
+            _ => {}
+        }
+    }
+    "line endings differ".to_string()
+}
+
+#[test]
+fn test_scripts_match_golden_files() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    let scripts = scripts();
+    assert!(!scripts.is_empty(), "tests/cli has no scripts");
+    for path in scripts {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let script = Script::parse(&fs::read_to_string(&path).unwrap());
+        let actual = script.run(&name);
+        let golden = path.with_extension("golden");
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => {
+                failures.push(format!("{}: {}", name, first_difference(&expected, &actual)))
+            }
+            Err(_) => failures.push(format!("{}: no golden file at {}", name, golden.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} script(s) differ from their golden files (rerun with UPDATE_GOLDEN=1 if the \
+         change is intended):\n\n{}",
+        failures.len(),
+        failures.join("\n\n")
+    );
+}
+
+#[test]
+fn test_script_lines() {
+    let script = Script::parse("# a comment\nargs: --seed 7 --practice\nt\n\ns\n");
+    assert_eq!(script.args, ["--seed", "7", "--practice"]);
+    assert_eq!(script.input, "t\n\ns\n");
+}